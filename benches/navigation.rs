@@ -0,0 +1,378 @@
+//! Benchmarks of the navigation hot path: reducing `ChangeRoute`,
+//! `BrowserChangeRoute` and `PollBrowserRoute` through the
+//! [RouteMiddleware], compared with a store without the middleware, and
+//! the cost of detecting navigations to the current route.
+//!
+//! The `allocations` group measures the number of heap allocations per
+//! navigation rather than the time.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{
+    black_box, criterion_group, criterion_main,
+    measurement::{Measurement, ValueFormatter},
+    Criterion, Throughput,
+};
+use reactive_state::{ReducerResult, StoreRef};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use switch_router::{Callback, SwitchRoute, SwitchRouteService};
+use switch_router_middleware::{
+    equality::RouteEquality, navigation::NavigationKind, IsRouteAction, RouteAction, RouteEvent,
+    RouteMiddleware, RouteState, RouteStore,
+};
+
+/// Counts the heap allocations, for the [Allocations] measurement.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A criterion measurement of the number of heap allocations.
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> usize {
+        ALLOCATIONS.load(Ordering::SeqCst)
+    }
+
+    fn end(&self, start: usize) -> usize {
+        ALLOCATIONS.load(Ordering::SeqCst) - start
+    }
+
+    fn add(&self, v1: &usize, v2: &usize) -> usize {
+        v1 + v2
+    }
+
+    fn zero(&self) -> usize {
+        0
+    }
+
+    fn to_f64(&self, value: &usize) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        match throughput {
+            Throughput::Elements(elements) => {
+                for value in values {
+                    *value /= *elements as f64;
+                }
+                "allocs/elem"
+            }
+            _ => "allocs",
+        }
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum AppRoute {
+    Home,
+    Item(u32),
+    Unknown(String),
+}
+
+impl SwitchRoute for AppRoute {
+    fn path(&self) -> String {
+        match self {
+            AppRoute::Home => "/".to_string(),
+            AppRoute::Item(id) => format!("/items/{}?tab=details&sort=name", id),
+            AppRoute::Unknown(path) => path.clone(),
+        }
+    }
+
+    fn switch(route: &str) -> Self {
+        match route.strip_prefix("/items/") {
+            Some(rest) => rest
+                .split('?')
+                .next()
+                .and_then(|id| id.parse().ok())
+                .map(AppRoute::Item)
+                .unwrap_or_else(|| AppRoute::Unknown(route.to_string())),
+            None if route == "/" => AppRoute::Home,
+            None => AppRoute::Unknown(route.to_string()),
+        }
+    }
+}
+
+/// A route service which only keeps the current route, so that the
+/// benchmarks measure the middleware rather than the browser.
+struct BenchRouteService {
+    route: AppRoute,
+}
+
+impl SwitchRouteService for BenchRouteService {
+    type Route = AppRoute;
+
+    fn set_route<SRI: Into<AppRoute>>(&mut self, switch_route: SRI) {
+        self.route = switch_route.into();
+    }
+
+    fn replace_route<SRI: Into<AppRoute>>(&mut self, switch_route: SRI) -> AppRoute {
+        std::mem::replace(&mut self.route, switch_route.into())
+    }
+
+    fn register_callback(&mut self, _callback: &Callback<AppRoute>) {}
+
+    fn back(&mut self) -> Option<AppRoute> {
+        None
+    }
+
+    fn get_route(&self) -> AppRoute {
+        self.route.clone()
+    }
+}
+
+#[derive(Debug)]
+struct AppState {
+    route: AppRoute,
+}
+
+impl RouteState<AppRoute> for AppState {
+    fn get_route(&self) -> &AppRoute {
+        &self.route
+    }
+}
+
+#[derive(Debug, Clone)]
+enum AppAction {
+    Route(RouteAction<AppRoute>),
+}
+
+impl From<RouteAction<AppRoute>> for AppAction {
+    fn from(action: RouteAction<AppRoute>) -> Self {
+        AppAction::Route(action)
+    }
+}
+
+impl IsRouteAction<AppRoute> for AppAction {
+    fn route_action(&self) -> Option<&RouteAction<AppRoute>> {
+        match self {
+            AppAction::Route(action) => Some(action),
+        }
+    }
+
+    fn into_route_action(self) -> Option<RouteAction<AppRoute>> {
+        match self {
+            AppAction::Route(action) => Some(action),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum AppEvent {
+    RouteChanged,
+}
+
+impl RouteEvent<AppRoute> for AppEvent {
+    fn route_changed() -> Self {
+        AppEvent::RouteChanged
+    }
+}
+
+type AppStore = StoreRef<AppState, AppAction, AppEvent, ()>;
+type AppMiddleware =
+    RouteMiddleware<AppRoute, BenchRouteService, AppState, AppAction, AppEvent, ()>;
+
+fn reducer(state: &Rc<AppState>, action: &AppAction) -> ReducerResult<AppState, AppEvent, ()> {
+    let route = match action {
+        AppAction::Route(RouteAction::ChangeRoute(route))
+        | AppAction::Route(RouteAction::BrowserChangeRoute(route, _)) => Some(route),
+        AppAction::Route(_) => None,
+    };
+    match route {
+        Some(route) => ReducerResult {
+            state: Rc::new(AppState {
+                route: route.clone(),
+            }),
+            events: vec![AppEvent::RouteChanged],
+            effects: vec![],
+        },
+        None => ReducerResult {
+            state: state.clone(),
+            events: vec![],
+            effects: vec![],
+        },
+    }
+}
+
+/// A store without the middleware, for comparison.
+fn bare_store() -> AppStore {
+    StoreRef::new(
+        reducer,
+        AppState {
+            route: AppRoute::Home,
+        },
+    )
+}
+
+fn store() -> AppStore {
+    store_with(|middleware| middleware)
+}
+
+/// A store with the middleware, configured by `configure`.
+fn store_with<F>(configure: F) -> AppStore
+where
+    F: FnOnce(AppMiddleware) -> AppMiddleware,
+{
+    let store = bare_store();
+    let middleware = RouteMiddleware::new(
+        BenchRouteService {
+            route: AppRoute::Home,
+        },
+        store.clone(),
+    );
+    store.add_middleware(configure(middleware));
+    store
+}
+
+fn change_route_without_middleware(c: &mut Criterion) {
+    let store = bare_store();
+    let mut id = 0;
+    c.bench_function("change_route_without_middleware", |b| {
+        b.iter(|| {
+            id += 1;
+            store.dispatch(RouteAction::ChangeRoute(black_box(AppRoute::Item(id))));
+        })
+    });
+}
+
+fn change_route(c: &mut Criterion) {
+    let store = store();
+    let mut id = 0;
+    c.bench_function("change_route", |b| {
+        b.iter(|| {
+            id += 1;
+            store.change_route(black_box(AppRoute::Item(id)));
+        })
+    });
+}
+
+fn browser_change_route(c: &mut Criterion) {
+    let store = store();
+    let mut id = 0;
+    c.bench_function("browser_change_route", |b| {
+        b.iter(|| {
+            id += 1;
+            store.dispatch(RouteAction::BrowserChangeRoute(
+                black_box(AppRoute::Item(id)),
+                NavigationKind::External,
+            ));
+        })
+    });
+}
+
+fn poll_browser_route(c: &mut Criterion) {
+    let store = store();
+    c.bench_function("poll_browser_route", |b| {
+        b.iter(|| store.dispatch(RouteAction::PollBrowserRoute))
+    });
+}
+
+/// Navigating to the current route, which is detected and replaces the
+/// current entry.
+fn change_route_unchanged(c: &mut Criterion) {
+    let store = store();
+    store.change_route(AppRoute::Item(1));
+    c.bench_function("change_route_unchanged", |b| {
+        b.iter(|| store.change_route(black_box(AppRoute::Item(1))))
+    });
+}
+
+/// As [change_route_unchanged()], comparing the routes by path.
+fn change_route_unchanged_path_equality(c: &mut Criterion) {
+    let store = store_with(|middleware| middleware.with_route_equality(RouteEquality::Path));
+    store.change_route(AppRoute::Item(1));
+    c.bench_function("change_route_unchanged_path_equality", |b| {
+        b.iter(|| store.change_route(black_box(AppRoute::Item(1))))
+    });
+}
+
+fn change_route_allocations(c: &mut Criterion<Allocations>) {
+    let store = store();
+    let mut id = 0;
+    c.bench_function("change_route_allocations", |b| {
+        b.iter(|| {
+            id += 1;
+            store.change_route(black_box(AppRoute::Item(id)));
+        })
+    });
+}
+
+fn change_route_without_middleware_allocations(c: &mut Criterion<Allocations>) {
+    let store = bare_store();
+    let mut id = 0;
+    c.bench_function("change_route_without_middleware_allocations", |b| {
+        b.iter(|| {
+            id += 1;
+            store.dispatch(RouteAction::ChangeRoute(black_box(AppRoute::Item(id))));
+        })
+    });
+}
+
+/// Polling an unchanged route, which must not allocate (see
+/// `tests/allocations.rs`).
+fn unchanged_route_allocations(c: &mut Criterion<Allocations>) {
+    let store = store();
+    c.bench_function("unchanged_route_allocations", |b| {
+        b.iter(|| store.dispatch(RouteAction::PollBrowserRoute))
+    });
+}
+
+criterion_group!(
+    benches,
+    change_route,
+    change_route_without_middleware,
+    browser_change_route,
+    poll_browser_route,
+    change_route_unchanged,
+    change_route_unchanged_path_equality
+);
+criterion_group! {
+    name = allocations;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = change_route_allocations,
+        change_route_without_middleware_allocations,
+        unchanged_route_allocations
+}
+criterion_main!(benches, allocations);
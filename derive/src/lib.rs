@@ -0,0 +1,561 @@
+//! Derive macros for
+//! [switch-router-middleware](https://github.com/kellpossible/switch-router-middleware),
+//! re-exported by it with the `derive` feature. See the documentation
+//! of the traits in that crate for their usage.
+//!
+//! The input is parsed with `proc_macro` alone, to keep the build of
+//! applications free of `syn` and `quote`, so only the shapes of items
+//! which the traits support are recognised.
+
+use proc_macro::{Delimiter, Group, Spacing, TokenStream, TokenTree};
+use std::iter::FromIterator;
+
+/// The path of the middleware crate in the generated code.
+const CRATE: &str = "::switch_router_middleware";
+
+/// Derive `RouteParams` for a struct holding the fields of a variant of
+/// the route enum, named with the `#[route_params(Route::Variant)]`
+/// attribute. A struct with named fields corresponds to a variant with
+/// the same named fields, a tuple struct to a tuple variant with the
+/// same number of fields, and a unit struct to a unit variant. Also
+/// implements `From<Struct>` for the route.
+#[proc_macro_derive(RouteParams, attributes(route_params))]
+pub fn derive_route_params(input: TokenStream) -> TokenStream {
+    let result = parse_struct(input).and_then(|input| {
+        let path = attribute(&input.attributes, "route_params").ok_or_else(|| {
+            "RouteParams requires a #[route_params(Route::Variant)] attribute".to_string()
+        })?;
+        route_params(&input, &path)
+    });
+    match result {
+        Ok(output) => output,
+        Err(message) => compile_error(&message),
+    }
+}
+
+fn route_params(input: &Struct, path: &[TokenTree]) -> Result<TokenStream, String> {
+    let route = route_of_variant(path)?;
+    let variant = stream(path);
+    let name = &input.name;
+    let (pattern, from_route, into_route) = match &input.fields {
+        Fields::Named(fields) => (
+            format!("{} {{ {} }}", variant, fields.join(", ")),
+            format!(
+                "{} {{ {} }}",
+                name,
+                fields
+                    .iter()
+                    .map(|field| format!("{}: ::std::clone::Clone::clone({})", field, field))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            format!(
+                "{} {{ {} }}",
+                variant,
+                fields
+                    .iter()
+                    .map(|field| format!("{}: self.{}", field, field))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ),
+        Fields::Unnamed(count) => {
+            let bindings: Vec<_> = (0..*count).map(|i| format!("__field{}", i)).collect();
+            (
+                format!("{}({})", variant, bindings.join(", ")),
+                format!(
+                    "{}({})",
+                    name,
+                    bindings
+                        .iter()
+                        .map(|binding| format!("::std::clone::Clone::clone({})", binding))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                format!(
+                    "{}({})",
+                    variant,
+                    (0..*count)
+                        .map(|i| format!("self.{}", i))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+        }
+        Fields::Unit => (variant.clone(), name.clone(), variant.clone()),
+    };
+    let output = format!(
+        "impl {krate}::params::RouteParams<{route}> for {name} {{
+            fn from_route(route: &{route}) -> ::std::option::Option<Self> {{
+                match route {{
+                    {pattern} => ::std::option::Option::Some({from_route}),
+                    #[allow(unreachable_patterns)]
+                    _ => ::std::option::Option::None,
+                }}
+            }}
+
+            fn into_route(self) -> {route} {{
+                {into_route}
+            }}
+        }}
+
+        impl ::std::convert::From<{name}> for {route} {{
+            fn from(params: {name}) -> Self {{
+                {krate}::params::RouteParams::into_route(params)
+            }}
+        }}",
+        krate = CRATE,
+        route = route,
+        name = name,
+        pattern = pattern,
+        from_route = from_route,
+        into_route = into_route,
+    );
+    parse(&output)
+}
+
+/// Derive `RouteEvent` for an enum with a variant for each constructor
+/// of the trait which the application handles, named with the
+/// `#[route_event(constructor, ...)]` attribute of the variant. The
+/// route type is named with the `#[route_event(Route)]` attribute of the
+/// enum. The fields of the variant hold the arguments of the
+/// constructor, in order (string arguments as an owned `String`), or a
+/// variant without fields ignores them.
+#[proc_macro_derive(RouteEvent, attributes(route_event))]
+pub fn derive_route_event(input: TokenStream) -> TokenStream {
+    match parse_enum(input).and_then(|input| route_event(&input)) {
+        Ok(output) => output,
+        Err(message) => compile_error(&message),
+    }
+}
+
+/// A constructor of `RouteEvent`: its name and its arguments (name and
+/// type), where the arguments of type `&str` are passed to the variant
+/// as a `String`.
+type Constructor = (&'static str, &'static [(&'static str, &'static str)]);
+
+/// The constructors of `RouteEvent`, `route_changed` first as the only
+/// one returning the event rather than an `Option`.
+const CONSTRUCTORS: &[Constructor] = &[
+    ("route_changed", &[]),
+    (
+        "url_too_long",
+        &[("length", "usize"), ("max_length", "usize")],
+    ),
+    ("navigation_blocked", &[]),
+    ("confirmation_required", &[("payload", "&str")]),
+    (
+        "route_signature_checked",
+        &[(
+            "result",
+            "::std::result::Result<(), ::switch_router_middleware::signing::SignatureError>",
+        )],
+    ),
+    (
+        "oauth_state_failed",
+        &[(
+            "error",
+            "::switch_router_middleware::oauth::OAuthStateError",
+        )],
+    ),
+    ("chunk_load_failed", &[("chunk_id", "&str")]),
+    (
+        "scope_changed",
+        &[
+            ("scope", "&str"),
+            (
+                "transition",
+                "::switch_router_middleware::scope::ScopeTransition",
+            ),
+        ],
+    ),
+    ("initial_navigation_complete", &[]),
+    ("navigation_failed", &[("path", "&str")]),
+    ("history_traversed", &[("delta", "isize")]),
+];
+
+fn route_event(input: &Enum) -> Result<TokenStream, String> {
+    let route = attribute(&input.attributes, "route_event")
+        .map(|route| stream(&route))
+        .ok_or_else(|| {
+            "RouteEvent requires a #[route_event(Route)] attribute naming the route type"
+                .to_string()
+        })?;
+    let mut methods = Vec::new();
+    let mut handled: Vec<String> = Vec::new();
+    for variant in &input.variants {
+        let constructors = match attribute(&variant.attributes, "route_event") {
+            Some(constructors) => constructors,
+            None => continue,
+        };
+        for constructor in split_top_level(TokenStream::from_iter(constructors)) {
+            let name = match constructor.as_slice() {
+                [TokenTree::Ident(ident)] => ident.to_string(),
+                _ => {
+                    return Err(format!(
+                        "expected the names of RouteEvent constructors for {}, found {:?}",
+                        variant.name,
+                        stream(&constructor)
+                    ))
+                }
+            };
+            let arguments = match CONSTRUCTORS.iter().find(|(method, _)| *method == name) {
+                Some((_, arguments)) => *arguments,
+                None => return Err(format!("`{}` is not a constructor of RouteEvent", name)),
+            };
+            if handled.contains(&name) {
+                return Err(format!("`{}` is handled by more than one variant", name));
+            }
+            methods.push(route_event_method(&name, arguments, variant)?);
+            handled.push(name);
+        }
+    }
+    if !handled.iter().any(|name| name == "route_changed") {
+        return Err(
+            "RouteEvent requires a variant with the #[route_event(route_changed)] attribute"
+                .to_string(),
+        );
+    }
+    parse(&format!(
+        "impl {krate}::RouteEvent<{route}> for {name} {{
+            {methods}
+        }}",
+        krate = CRATE,
+        route = route,
+        name = input.name,
+        methods = methods.join("\n\n"),
+    ))
+}
+
+/// The implementation of the constructor `name` with `arguments`,
+/// returning `variant`.
+fn route_event_method(
+    name: &str,
+    arguments: &[(&str, &str)],
+    variant: &Variant,
+) -> Result<String, String> {
+    let values: Vec<_> = arguments
+        .iter()
+        .map(|(argument, ty)| match *ty {
+            "&str" => format!("::std::borrow::ToOwned::to_owned({})", argument),
+            _ => argument.to_string(),
+        })
+        .collect();
+    let (parameters, event) = match &variant.fields {
+        Fields::Unit => (
+            arguments
+                .iter()
+                .map(|(_, ty)| format!("_: {}", ty))
+                .collect::<Vec<_>>(),
+            format!("Self::{}", variant.name),
+        ),
+        Fields::Unnamed(count) if *count == arguments.len() => (
+            parameters(arguments),
+            format!("Self::{}({})", variant.name, values.join(", ")),
+        ),
+        Fields::Named(fields) if fields.len() == arguments.len() => (
+            parameters(arguments),
+            format!(
+                "Self::{} {{ {} }}",
+                variant.name,
+                fields
+                    .iter()
+                    .zip(&values)
+                    .map(|(field, value)| format!("{}: {}", field, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ),
+        _ => {
+            return Err(format!(
+                "the variant {} for the RouteEvent constructor `{}` needs no fields, \
+                 or a field for each of its arguments ({})",
+                variant.name,
+                name,
+                arguments
+                    .iter()
+                    .map(|(argument, _)| *argument)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        }
+    };
+    let parameters = parameters.join(", ");
+    Ok(if name == "route_changed" {
+        format!("fn {}({}) -> Self {{ {} }}", name, parameters, event)
+    } else {
+        format!(
+            "fn {}({}) -> ::std::option::Option<Self> {{
+                ::std::option::Option::Some({})
+            }}",
+            name, parameters, event
+        )
+    })
+}
+
+fn parameters(arguments: &[(&str, &str)]) -> Vec<String> {
+    arguments
+        .iter()
+        .map(|(argument, ty)| format!("{}: {}", argument, ty))
+        .collect()
+}
+
+/// The fields of a struct or variant.
+enum Fields {
+    Named(Vec<String>),
+    /// The number of fields of a tuple struct.
+    Unnamed(usize),
+    Unit,
+}
+
+/// The parts of a struct which the derives use.
+struct Struct {
+    /// The bracketed groups of the outer attributes.
+    attributes: Vec<Group>,
+    name: String,
+    fields: Fields,
+}
+
+/// The parts of an enum which the derives use.
+struct Enum {
+    /// The bracketed groups of the outer attributes.
+    attributes: Vec<Group>,
+    name: String,
+    variants: Vec<Variant>,
+}
+
+struct Variant {
+    attributes: Vec<Group>,
+    name: String,
+    fields: Fields,
+}
+
+fn parse_struct(input: TokenStream) -> Result<Struct, String> {
+    let (attributes, name, body) = parse_item(input, "struct", "RouteParams")?;
+    let fields = match body.and_then(|body| fields(&body)) {
+        Some(fields) => fields?,
+        None => {
+            return Err(format!(
+                "RouteParams can't be derived for {}, generic structs are not supported",
+                name
+            ))
+        }
+    };
+    Ok(Struct {
+        attributes,
+        name,
+        fields,
+    })
+}
+
+fn parse_enum(input: TokenStream) -> Result<Enum, String> {
+    let (attributes, name, body) = parse_item(input, "enum", "RouteEvent")?;
+    let variants = match body {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            split_top_level(group.stream())
+        }
+        _ => {
+            return Err(format!(
+                "RouteEvent can't be derived for {}, generic enums are not supported",
+                name
+            ))
+        }
+    };
+    let variants = variants
+        .into_iter()
+        .map(|variant| {
+            let mut tokens = variant.into_iter().peekable();
+            let attributes = outer_attributes(&mut tokens)?;
+            let name = match tokens.next() {
+                Some(TokenTree::Ident(ident)) => ident.to_string(),
+                _ => return Err("expected the name of a variant".to_string()),
+            };
+            // A variant without fields may be followed by its discriminant.
+            let fields = match tokens.next().and_then(|body| fields(&body)) {
+                Some(fields) => fields?,
+                None => Fields::Unit,
+            };
+            Ok(Variant {
+                attributes,
+                name,
+                fields,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Enum {
+        attributes,
+        name,
+        variants,
+    })
+}
+
+/// Parse the outer attributes, the visibility, the `keyword` and the
+/// name of an item, returning the attributes, the name and the token
+/// following the name.
+fn parse_item(
+    input: TokenStream,
+    keyword: &str,
+    derive: &str,
+) -> Result<(Vec<Group>, String, Option<TokenTree>), String> {
+    let mut tokens = input.into_iter().peekable();
+    let attributes = outer_attributes(&mut tokens)?;
+    skip_visibility(&mut tokens);
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == keyword => {}
+        _ => return Err(format!("{} can only be derived for {}s", derive, keyword)),
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err(format!("expected the name of the {}", keyword)),
+    };
+    Ok((attributes, name, tokens.next()))
+}
+
+/// The bracketed groups of the outer attributes at the start of
+/// `tokens`.
+fn outer_attributes<I>(tokens: &mut std::iter::Peekable<I>) -> Result<Vec<Group>, String>
+where
+    I: Iterator<Item = TokenTree>,
+{
+    let mut attributes = Vec::new();
+    while let Some(TokenTree::Punct(punct)) = tokens.peek() {
+        if punct.as_char() != '#' {
+            break;
+        }
+        tokens.next();
+        match tokens.next() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                attributes.push(group)
+            }
+            _ => return Err("expected an attribute".to_string()),
+        }
+    }
+    Ok(attributes)
+}
+
+/// The fields declared by `body`, the token following the name of a
+/// struct or variant, or `None` if it doesn't declare fields.
+fn fields(body: &TokenTree) -> Option<Result<Fields, String>> {
+    match body {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+            Some(named_fields(group.stream()).map(Fields::Named))
+        }
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+            Some(Ok(Fields::Unnamed(split_top_level(group.stream()).len())))
+        }
+        TokenTree::Punct(punct) if punct.as_char() == ';' => Some(Ok(Fields::Unit)),
+        _ => None,
+    }
+}
+
+/// Skip `pub`, `pub(crate)` and the like.
+fn skip_visibility<I>(tokens: &mut std::iter::Peekable<I>)
+where
+    I: Iterator<Item = TokenTree>,
+{
+    if let Some(TokenTree::Ident(ident)) = tokens.peek() {
+        if ident.to_string() == "pub" {
+            tokens.next();
+            if let Some(TokenTree::Group(group)) = tokens.peek() {
+                if group.delimiter() == Delimiter::Parenthesis {
+                    tokens.next();
+                }
+            }
+        }
+    }
+}
+
+/// The names of the fields of a struct with named fields.
+fn named_fields(stream: TokenStream) -> Result<Vec<String>, String> {
+    split_top_level(stream)
+        .into_iter()
+        .map(|field| {
+            let mut tokens = field.into_iter().peekable();
+            outer_attributes(&mut tokens)?;
+            skip_visibility(&mut tokens);
+            match tokens.next() {
+                Some(TokenTree::Ident(ident)) => Ok(ident.to_string()),
+                _ => Err("expected the name of a field".to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Split `stream` on the commas which aren't within the angle brackets
+/// of generic arguments, skipping empty parts (after a trailing comma).
+fn split_top_level(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut arrow = false;
+    for token in stream {
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                // The `>` of a `->` doesn't close an angle bracket.
+                '>' if !arrow => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    parts.push(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+            arrow = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        parts.last_mut().unwrap().push(token);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// The arguments of the outer attribute `name`, e.g. the tokens within
+/// the parentheses of `#[name(...)]`.
+fn attribute(attributes: &[Group], name: &str) -> Option<Vec<TokenTree>> {
+    attributes.iter().find_map(|attribute| {
+        let mut tokens = attribute.stream().into_iter();
+        match (tokens.next(), tokens.next()) {
+            (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(group)))
+                if ident.to_string() == name && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                Some(group.stream().into_iter().collect())
+            }
+            _ => None,
+        }
+    })
+}
+
+/// The path of the enum of the variant with `path`, e.g. `routes::AppRoute`
+/// for `routes::AppRoute::UserPage`.
+fn route_of_variant(path: &[TokenTree]) -> Result<String, String> {
+    let separator = path.windows(2).rposition(|pair| match pair {
+        [TokenTree::Punct(first), TokenTree::Punct(second)] => {
+            first.as_char() == ':' && first.spacing() == Spacing::Joint && second.as_char() == ':'
+        }
+        _ => false,
+    });
+    match (separator, path.last()) {
+        (Some(separator), Some(TokenTree::Ident(_))) if separator + 3 == path.len() => {
+            Ok(stream(&path[..separator]))
+        }
+        _ => Err(format!(
+            "expected the path of a variant, e.g. Route::Variant, found {:?}",
+            stream(path)
+        )),
+    }
+}
+
+fn stream(tokens: &[TokenTree]) -> String {
+    TokenStream::from_iter(tokens.iter().cloned()).to_string()
+}
+
+fn parse(code: &str) -> Result<TokenStream, String> {
+    code.parse()
+        .map_err(|err| format!("generated invalid code: {:?}", err))
+}
+
+fn compile_error(message: &str) -> TokenStream {
+    format!("::std::compile_error!({:?});", message)
+        .parse()
+        .unwrap()
+}
@@ -0,0 +1,105 @@
+//! A minimal application using the default build of the middleware, see
+//! `Cargo.toml`.
+
+use reactive_state::{ReducerResult, StoreRef};
+use std::rc::Rc;
+use switch_router::SwitchRoute;
+use switch_router_middleware::{
+    memory::MemoryRouteService, IsRouteAction, RouteAction, RouteEvent, RouteMiddleware,
+    RouteState, RouteStore,
+};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[derive(Debug, Clone, PartialEq)]
+enum AppRoute {
+    Home,
+    Item(u32),
+}
+
+impl SwitchRoute for AppRoute {
+    fn path(&self) -> String {
+        match self {
+            AppRoute::Home => "/".to_string(),
+            AppRoute::Item(id) => format!("/items/{}", id),
+        }
+    }
+
+    fn switch(route: &str) -> Self {
+        route
+            .strip_prefix("/items/")
+            .and_then(|id| id.parse().ok())
+            .map(AppRoute::Item)
+            .unwrap_or(AppRoute::Home)
+    }
+}
+
+struct AppState {
+    route: AppRoute,
+}
+
+impl RouteState<AppRoute> for AppState {
+    fn get_route(&self) -> &AppRoute {
+        &self.route
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AppAction(RouteAction<AppRoute>);
+
+impl From<RouteAction<AppRoute>> for AppAction {
+    fn from(action: RouteAction<AppRoute>) -> Self {
+        AppAction(action)
+    }
+}
+
+impl IsRouteAction<AppRoute> for AppAction {
+    fn route_action(&self) -> Option<&RouteAction<AppRoute>> {
+        Some(&self.0)
+    }
+
+    fn into_route_action(self) -> Option<RouteAction<AppRoute>> {
+        Some(self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RouteChanged;
+
+impl RouteEvent<AppRoute> for RouteChanged {
+    fn route_changed() -> Self {
+        RouteChanged
+    }
+}
+
+fn reducer(state: &Rc<AppState>, action: &AppAction) -> ReducerResult<AppState, RouteChanged, ()> {
+    match &action.0 {
+        RouteAction::ChangeRoute(route) | RouteAction::BrowserChangeRoute(route, _) => {
+            ReducerResult {
+                state: Rc::new(AppState {
+                    route: route.clone(),
+                }),
+                events: vec![RouteChanged],
+                effects: vec![],
+            }
+        }
+        _ => ReducerResult {
+            state: state.clone(),
+            events: vec![],
+            effects: vec![],
+        },
+    }
+}
+
+#[wasm_bindgen(start)]
+pub fn start() {
+    let store = StoreRef::new(
+        reducer,
+        AppState {
+            route: AppRoute::Home,
+        },
+    );
+    let middleware = RouteMiddleware::new(MemoryRouteService::new(AppRoute::Home), store.clone());
+    store.add_middleware(middleware);
+    store.change_route(AppRoute::Item(1));
+    store.replace_route(AppRoute::Home);
+}
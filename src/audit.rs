@@ -0,0 +1,110 @@
+//! A log of the last route actions reduced by the
+//! [RouteMiddleware](crate::RouteMiddleware), and their outcomes, kept in
+//! a ring buffer of a fixed capacity. Requires the `audit` feature.
+//!
+//! Unlike recording the whole session, it's lightweight enough to keep
+//! enabled in production, so that the actions leading up to a routing
+//! problem can be included in a bug report.
+//!
+//! Enabled with
+//! [with_audit_log()](crate::RouteMiddleware::with_audit_log), the
+//! entries are part of the diagnostics (with the `diagnostics` feature),
+//! and of their report.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt::{self, Display},
+};
+
+/// What became of a route action, see [AuditEntry].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditOutcome {
+    /// The navigation was committed, changing the route to this path.
+    Committed(String),
+    /// The action was reduced without changing the route.
+    Unchanged,
+    /// The navigation was blocked by a guard or the interceptor.
+    Blocked,
+    /// A hook panicked while reducing the navigation.
+    Failed,
+    /// The navigation is waiting to be confirmed, see
+    /// [interceptor](crate::interceptor).
+    AwaitingConfirmation,
+    /// The navigation is waiting for the chunk with this id to load, see
+    /// [chunks](crate::chunks).
+    AwaitingChunk(String),
+}
+
+impl Display for AuditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditOutcome::Committed(path) => write!(f, "committed {}", path),
+            AuditOutcome::Unchanged => write!(f, "unchanged"),
+            AuditOutcome::Blocked => write!(f, "blocked"),
+            AuditOutcome::Failed => write!(f, "failed"),
+            AuditOutcome::AwaitingConfirmation => write!(f, "awaiting confirmation"),
+            AuditOutcome::AwaitingChunk(chunk_id) => write!(f, "awaiting chunk {:?}", chunk_id),
+        }
+    }
+}
+
+/// A route action reduced by the middleware, and its outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// The number of the action, counting from `0` when the middleware
+    /// was created, so that gaps show where entries were discarded.
+    pub sequence: u64,
+    /// The action, displayed with its routes' paths, e.g.
+    /// `ChangeRoute(/items/5)`.
+    pub action: String,
+    pub outcome: AuditOutcome,
+}
+
+impl Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}: {}", self.sequence, self.action, self.outcome)
+    }
+}
+
+/// The ring buffer of [AuditEntry]s.
+pub(crate) struct AuditLog {
+    capacity: usize,
+    sequence: Cell<u64>,
+    entries: RefCell<VecDeque<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            sequence: Cell::new(0),
+            entries: RefCell::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Record the `outcome` of the `action`, discarding the oldest entry
+    /// if the log is full.
+    pub(crate) fn record(&self, action: &dyn Display, outcome: AuditOutcome) {
+        let sequence = self.sequence.get();
+        self.sequence.set(sequence + 1);
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.borrow_mut();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(AuditEntry {
+            sequence,
+            action: action.to_string(),
+            outcome,
+        });
+    }
+
+    /// The entries, oldest first.
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.borrow().iter().cloned().collect()
+    }
+}
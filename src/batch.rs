@@ -0,0 +1,92 @@
+//! Batches of actions containing both route and other actions, for
+//! applications (or middleware stacks) which dispatch several actions as
+//! one.
+//!
+//! Without configuration, the middleware doesn't look inside a batch:
+//! the batch reaches the reducer as a single non-route action, and
+//! whether its route actions navigate at all depends on how (and where
+//! in the middleware stack) it is unpacked. Configured with
+//! [with_action_batches()](crate::RouteMiddleware::with_action_batches),
+//! the middleware unpacks batches itself, and handles their actions one
+//! at a time in the [BatchOrder], so that e.g. the data a route depends
+//! on has been reduced before the navigation is guarded:
+//!
+//! ```ignore
+//! let middleware = RouteMiddleware::new(route_service, store.clone()).with_action_batches(
+//!     |action: &AppAction| match action {
+//!         AppAction::Batch(actions) => Some(actions.as_slice()),
+//!         _ => None,
+//!     },
+//!     BatchOrder::RouteActionsLast,
+//! );
+//! ```
+//!
+//! The order of the route actions among themselves, and of the other
+//! actions among themselves, is always preserved. Batches nested inside
+//! a batch are unpacked in turn, each counting as a non-route action in
+//! the order of the enclosing batch.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The order in which the actions of a batch are handled, see the
+/// [module documentation](self).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchOrder {
+    /// In the order they were batched.
+    #[default]
+    AsDispatched,
+    /// The route actions first, then the other actions.
+    RouteActionsFirst,
+    /// The other actions first, then the route actions.
+    RouteActionsLast,
+}
+
+impl BatchOrder {
+    /// The `actions` in this order, where `is_route_action` decides
+    /// whether an action is a route action.
+    pub fn apply<Action, F>(self, actions: &[Action], is_route_action: F) -> Vec<&Action>
+    where
+        F: Fn(&Action) -> bool,
+    {
+        let (first, last): (Vec<&Action>, Vec<&Action>) = match self {
+            BatchOrder::AsDispatched => return actions.iter().collect(),
+            BatchOrder::RouteActionsFirst => actions.iter().partition(|a| is_route_action(a)),
+            BatchOrder::RouteActionsLast => actions.iter().partition(|a| !is_route_action(a)),
+        };
+        first.into_iter().chain(last).collect()
+    }
+}
+
+/// Returns the actions of a batch, or `None` if the action isn't one.
+pub(crate) type SplitBatchFn<Action> = dyn Fn(&Action) -> Option<&[Action]>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Odd numbers are route actions.
+    fn apply(order: BatchOrder, actions: &[u32]) -> Vec<u32> {
+        order
+            .apply(actions, |action| action % 2 == 1)
+            .into_iter()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn route_actions_keep_their_relative_order() {
+        let actions = [2, 1, 4, 3, 6, 5];
+        assert_eq!(apply(BatchOrder::AsDispatched, &actions), actions);
+        assert_eq!(
+            apply(BatchOrder::RouteActionsFirst, &actions),
+            [1, 3, 5, 2, 4, 6]
+        );
+        assert_eq!(
+            apply(BatchOrder::RouteActionsLast, &actions),
+            [2, 4, 6, 1, 3, 5]
+        );
+        assert!(apply(BatchOrder::RouteActionsLast, &[]).is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+//! A thin middleware which only bridges the URL and the store's actions,
+//! for applications which keep the route outside of the store (e.g. in
+//! a component, or a separate signal), and so can't implement
+//! [RouteState](crate::RouteState).
+//!
+//! A [RouteBridge] dispatches
+//! [RouteAction::BrowserChangeRoute](crate::RouteAction::BrowserChangeRoute)
+//! when the route changes in the browser, and performs the navigations
+//! of [RouteAction::ChangeRoute](crate::RouteAction::ChangeRoute),
+//! [RouteAction::ReplaceRoute](crate::RouteAction::ReplaceRoute),
+//! [RouteAction::Back](crate::RouteAction::Back) and
+//! [RouteAction::Forward](crate::RouteAction::Forward) on the route
+//! service. Nothing else is required of the store's types: the `Event`
+//! type needn't implement [RouteEvent](crate::RouteEvent), as the bridge
+//! emits no events.
+//!
+//! Everything which needs to know the route of the state is left out:
+//! navigations to the current route aren't deduplicated, and there are
+//! no guards, interceptors, lifecycle actions or subscriptions. The
+//! other route actions are passed on to the reducer untouched. Switching
+//! to the [RouteMiddleware](crate::RouteMiddleware) later only requires
+//! implementing [RouteState](crate::RouteState).
+//!
+//! ```ignore
+//! let bridge = RouteBridge::new(route_service, store.clone());
+//! store.add_middleware(bridge);
+//! ```
+
+use crate::{
+    logging::{debug, error},
+    navigation::NavigationKind,
+    route_dispatcher, IsRouteAction, RouteAction, ServiceOp,
+};
+use reactive_state::{
+    middleware::{Middleware, ReduceFn, ReduceMiddlewareResult},
+    Store, StoreRef,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    hash::Hash,
+    rc::Rc,
+};
+use switch_router::{SwitchRoute, SwitchRouteService};
+
+/// Bridges the route service and the store's actions, without state
+/// integration, see the [module documentation](self).
+pub struct RouteBridge<R, RS> {
+    route_service: RefCell<RS>,
+    /// Held so that the callback registered with the route service
+    /// remains alive.
+    _callback: switch_router::Callback<R>,
+    pending_service_ops: RefCell<VecDeque<ServiceOp<R>>>,
+}
+
+impl<R, RS> RouteBridge<R, RS>
+where
+    R: SwitchRoute + 'static,
+    RS: SwitchRouteService<Route = R> + 'static,
+{
+    pub fn new<State, Action, Event, Effect>(
+        route_service: RS,
+        store: StoreRef<State, Action, Event, Effect>,
+    ) -> Self
+    where
+        State: 'static,
+        Action: IsRouteAction<R> + 'static,
+        Event: Clone + Hash + Eq + 'static,
+        Effect: 'static,
+    {
+        let mut route_service = route_service;
+        let dispatch = route_dispatcher(store.downgrade(), Rc::new(Cell::new(None)), Action::from);
+        let callback: switch_router::Callback<R> = switch_router::Callback::new(move |route: R| {
+            dispatch(RouteAction::BrowserChangeRoute(
+                route,
+                NavigationKind::External,
+            ))
+        });
+        route_service.register_callback(&callback);
+        Self {
+            route_service: RefCell::new(route_service),
+            _callback: callback,
+            pending_service_ops: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Perform `op` on the route service, queuing it if the service is
+    /// in use by a re-entrant navigation.
+    fn service_op(&self, op: ServiceOp<R>) {
+        self.pending_service_ops.borrow_mut().push_back(op);
+        loop {
+            let mut route_service = match self.route_service.try_borrow_mut() {
+                Ok(route_service) => route_service,
+                Err(_) => {
+                    debug!("Route service is in use, queuing the operation");
+                    return;
+                }
+            };
+            let op = match self.pending_service_ops.borrow_mut().pop_front() {
+                Some(op) => op,
+                None => return,
+            };
+            op.apply(&mut *route_service);
+        }
+    }
+}
+
+impl<R, RS, State, Action, Event, Effect> Middleware<State, Action, Event, Effect>
+    for RouteBridge<R, RS>
+where
+    R: SwitchRoute + 'static,
+    RS: SwitchRouteService<Route = R> + 'static,
+    Action: IsRouteAction<R>,
+    Event: Clone + Hash + Eq,
+{
+    fn on_reduce(
+        &self,
+        store: &Store<State, Action, Event, Effect>,
+        action: Option<&Action>,
+        reduce: ReduceFn<State, Action, Event, Effect>,
+    ) -> ReduceMiddlewareResult<Event, Effect> {
+        let route_action = match action.and_then(|action| action.route_action()) {
+            Some(route_action) => route_action,
+            None => return reduce(store, action),
+        };
+        match route_action {
+            RouteAction::ChangeRoute(route) => {
+                self.service_op(ServiceOp::Set(route.clone()));
+                reduce(store, action)
+            }
+            RouteAction::ReplaceRoute(route) => {
+                self.service_op(ServiceOp::Replace(route.clone()));
+                reduce(store, Some(&RouteAction::ChangeRoute(route.clone()).into()))
+            }
+            RouteAction::Back => {
+                self.service_op(ServiceOp::Back);
+                reduce(store, None)
+            }
+            RouteAction::Forward => {
+                #[cfg(feature = "web")]
+                crate::web::history_forward();
+                #[cfg(not(feature = "web"))]
+                error!("Unable to go forward: requires the web feature");
+                reduce(store, None)
+            }
+            RouteAction::PollBrowserRoute => match self.route_service.try_borrow() {
+                Ok(route_service) => {
+                    let route = route_service.get_route();
+                    drop(route_service);
+                    reduce(
+                        store,
+                        Some(
+                            &RouteAction::BrowserChangeRoute(route, NavigationKind::External)
+                                .into(),
+                        ),
+                    )
+                }
+                Err(err) => {
+                    error!("Unable to poll the route service: {}", err);
+                    reduce(store, None)
+                }
+            },
+            _ => reduce(store, action),
+        }
+    }
+}
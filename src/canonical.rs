@@ -0,0 +1,69 @@
+//! Policies for the canonical form of route paths. Routes which are not
+//! in their canonical form are rewritten before they reach the store, and
+//! the browser URL is corrected using a replace navigation, so that
+//! different spellings of the same route don't become different states
+//! in history and analytics.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Split a `path` into the path portion, and the remainder (query and
+/// fragment, including the leading `?` or `#`).
+pub(crate) fn split_path(path: &str) -> (&str, &str) {
+    match path.find(['?', '#']) {
+        Some(index) => path.split_at(index),
+        None => (path, ""),
+    }
+}
+
+/// Policy for trailing slashes at the end of the path portion of a
+/// route (the query and fragment are unaffected). The root path `/` is
+/// always left as is.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+    /// Paths always end with a slash, `/users` becomes `/users/`.
+    Always,
+    /// Paths never end with a slash, `/users/` becomes `/users`.
+    Never,
+    /// Paths are left as they are.
+    #[default]
+    Preserve,
+}
+
+impl TrailingSlash {
+    /// Apply this policy to the specified `path`.
+    pub fn apply(self, path: &str) -> String {
+        let (path_portion, remainder) = split_path(path);
+        let trimmed = path_portion.trim_end_matches('/');
+        let path_portion = match self {
+            TrailingSlash::Preserve => return path.to_string(),
+            _ if trimmed.is_empty() => path_portion,
+            TrailingSlash::Always => return format!("{}/{}", trimmed, remainder),
+            TrailingSlash::Never => trimmed,
+        };
+        format!("{}{}", path_portion, remainder)
+    }
+}
+
+/// Lower case the path portion of `path` (the query and fragment are
+/// unaffected), leaving the hex digits of percent-encodings in upper
+/// case as produced by [normalize_path()](crate::encoding::normalize_path).
+pub fn lowercase_path(path: &str) -> String {
+    let (path_portion, remainder) = split_path(path);
+    let mut lowercased = String::with_capacity(path.len());
+    let mut escape_digits = 0;
+    for c in path_portion.chars() {
+        if escape_digits > 0 {
+            escape_digits -= 1;
+            lowercased.push(c);
+        } else if c == '%' {
+            escape_digits = 2;
+            lowercased.push(c);
+        } else {
+            lowercased.extend(c.to_lowercase());
+        }
+    }
+    lowercased.push_str(remainder);
+    lowercased
+}
@@ -0,0 +1,124 @@
+//! Code-split routing, for applications which lazy-load a WASM/JS chunk
+//! per route.
+//!
+//! The chunk of a route is declared with
+//! [RouteMetadata::with_chunk()](crate::metadata::RouteMetadata::with_chunk).
+//! When navigating to a route whose chunk hasn't been loaded yet, the
+//! [RouteMiddleware](crate::RouteMiddleware) holds the navigation back,
+//! and emits a [RouteEffect::LoadChunk] effect instead. Once the
+//! application has loaded the chunk, it dispatches
+//! [RouteAction::ChunkLoaded](crate::RouteAction::ChunkLoaded), and the
+//! navigation is committed. If it dispatches
+//! [RouteAction::ChunkLoadFailed](crate::RouteAction::ChunkLoadFailed)
+//! instead, or the chunk doesn't load within the timeout (requires the
+//! `web` feature, see
+//! [with_chunk_timeout_ms()](crate::RouteMiddleware::with_chunk_timeout_ms)),
+//! the navigation is abandoned and
+//! [RouteEvent::chunk_load_failed()](crate::RouteEvent::chunk_load_failed)
+//! is emitted.
+//!
+//! Chunk loading is enabled with
+//! [with_chunk_loading()](crate::RouteMiddleware::with_chunk_loading),
+//! for stores whose `Effect` implements `From<RouteEffect>`.
+
+use crate::RouteAction;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+};
+
+/// Effects emitted by the [RouteMiddleware](crate::RouteMiddleware).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RouteEffect {
+    /// Load the chunk with this id, then dispatch
+    /// [RouteAction::ChunkLoaded](crate::RouteAction::ChunkLoaded).
+    LoadChunk(String),
+}
+
+/// The default time to wait for a chunk to load, in milliseconds.
+pub const DEFAULT_CHUNK_TIMEOUT_MS: u32 = 10_000;
+
+struct PendingChunk<R> {
+    chunk_id: String,
+    action: RouteAction<R>,
+    #[cfg(feature = "web")]
+    generation: u64,
+}
+
+/// Tracks the loaded chunks, and the navigation waiting for a chunk.
+pub(crate) struct ChunkLoader<R> {
+    loaded: RefCell<HashSet<String>>,
+    pending: RefCell<Option<PendingChunk<R>>>,
+    generation: Cell<u64>,
+}
+
+impl<R> ChunkLoader<R> {
+    pub(crate) fn new() -> Self {
+        Self {
+            loaded: RefCell::new(HashSet::new()),
+            pending: RefCell::new(None),
+            generation: Cell::new(0),
+        }
+    }
+
+    pub(crate) fn is_loaded(&self, chunk_id: &str) -> bool {
+        self.loaded.borrow().contains(chunk_id)
+    }
+
+    /// Hold back the navigation `action` until the chunk is loaded,
+    /// replacing any navigation already waiting. Returns the generation
+    /// to [expire()](Self::expire) it with.
+    pub(crate) fn begin(&self, chunk_id: &str, action: RouteAction<R>) -> u64 {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        *self.pending.borrow_mut() = Some(PendingChunk {
+            chunk_id: chunk_id.to_string(),
+            action,
+            #[cfg(feature = "web")]
+            generation,
+        });
+        generation
+    }
+
+    /// The id of the chunk the waiting navigation is waiting for.
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn waiting_chunk(&self) -> Option<String> {
+        self.pending
+            .borrow()
+            .as_ref()
+            .map(|pending| pending.chunk_id.clone())
+    }
+
+    /// Record that the chunk has loaded, returns the navigation which
+    /// was waiting for it.
+    pub(crate) fn loaded(&self, chunk_id: &str) -> Option<RouteAction<R>> {
+        self.loaded.borrow_mut().insert(chunk_id.to_string());
+        self.take_pending(chunk_id)
+    }
+
+    /// Record that the chunk failed to load, returns the navigation
+    /// which was waiting for it.
+    pub(crate) fn failed(&self, chunk_id: &str) -> Option<RouteAction<R>> {
+        self.take_pending(chunk_id)
+    }
+
+    /// The id of the chunk the navigation started with `generation` is
+    /// still waiting for, once its timeout has elapsed.
+    #[cfg(feature = "web")]
+    pub(crate) fn expire(&self, generation: u64) -> Option<String> {
+        match &*self.pending.borrow() {
+            Some(pending) if pending.generation == generation => Some(pending.chunk_id.clone()),
+            _ => None,
+        }
+    }
+
+    fn take_pending(&self, chunk_id: &str) -> Option<RouteAction<R>> {
+        let mut pending = self.pending.borrow_mut();
+        match &*pending {
+            Some(waiting) if waiting.chunk_id == chunk_id => {
+                pending.take().map(|pending| pending.action)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Custom router verbs. Downstream crates can extend the router with
+//! their own commands without forking [RouteAction]: a command is
+//! dispatched as [RouteAction::Custom], and the handler registered for
+//! its type with
+//! [RouteMiddleware::with_command_handler()](crate::RouteMiddleware::with_command_handler)
+//! translates it into a built-in [RouteAction], given the current state.
+
+use crate::RouteAction;
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    marker::PhantomData,
+    rc::Rc,
+};
+
+/// A custom router verb for routes of type `R`, see the
+/// [module documentation](self).
+pub trait RouteCommand<R>: Debug + 'static {}
+
+trait AnyCommand: Debug {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T> AnyCommand for T
+where
+    T: Debug + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A type erased [RouteCommand], carried by [RouteAction::Custom].
+/// Clones share the same command, and only clones are equal to each
+/// other.
+pub struct CustomCommand<R> {
+    command: Rc<dyn AnyCommand>,
+    route: PhantomData<fn() -> R>,
+}
+
+impl<R> CustomCommand<R> {
+    pub fn new<C: RouteCommand<R>>(command: C) -> Self {
+        Self {
+            command: Rc::new(command),
+            route: PhantomData,
+        }
+    }
+
+    /// The command, if it is of type `C`.
+    pub fn downcast_ref<C: RouteCommand<R>>(&self) -> Option<&C> {
+        self.command.as_any().downcast_ref()
+    }
+
+    fn command_type(&self) -> TypeId {
+        self.command.as_any().type_id()
+    }
+}
+
+impl<R> Clone for CustomCommand<R> {
+    fn clone(&self) -> Self {
+        Self {
+            command: self.command.clone(),
+            route: PhantomData,
+        }
+    }
+}
+
+impl<R> PartialEq for CustomCommand<R> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.command, &other.command)
+    }
+}
+
+impl<R> Debug for CustomCommand<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.command.fmt(f)
+    }
+}
+
+type HandlerFn<R, State> = dyn Fn(&State, &CustomCommand<R>) -> Option<RouteAction<R>>;
+
+/// The handlers of [RouteCommand]s, one for each command type.
+pub struct RouteCommands<R, State> {
+    handlers: HashMap<TypeId, Box<HandlerFn<R, State>>>,
+}
+
+impl<R, State> RouteCommands<R, State> {
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Handle commands of type `C` with `handler`, which returns the
+    /// [RouteAction] to perform instead of the command, or `None` to
+    /// reduce the command as is. Replaces any previous handler for `C`.
+    pub fn with_handler<C, F>(mut self, handler: F) -> Self
+    where
+        C: RouteCommand<R>,
+        F: Fn(&State, &C) -> Option<RouteAction<R>> + 'static,
+    {
+        self.handlers.insert(
+            TypeId::of::<C>(),
+            Box::new(move |state, command| {
+                command
+                    .downcast_ref::<C>()
+                    .and_then(|command| handler(state, command))
+            }),
+        );
+        self
+    }
+
+    /// Translate the `command` using its handler. Returns `None` if no
+    /// handler has been registered for its type.
+    pub(crate) fn translate(
+        &self,
+        state: &State,
+        command: &CustomCommand<R>,
+    ) -> Option<Option<RouteAction<R>>> {
+        self.handlers
+            .get(&command.command_type())
+            .map(|handler| handler(state, command))
+    }
+}
+
+impl<R, State> Default for RouteCommands<R, State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
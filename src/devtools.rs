@@ -0,0 +1,155 @@
+//! Integration with state devtools (e.g. a Redux DevTools bridge
+//! middleware for the store), which record the dispatched actions and
+//! can jump back to a previous state ("time travel"), requires the
+//! `devtools` feature.
+//!
+//! + [action_label()] gives route actions a readable label for the
+//!   devtools' action list, e.g. `route/ChangeRoute(/items/5)`.
+//! + When the devtools replace the state of the store, the route of the
+//!   state no longer matches the URL of the route service. Register the
+//!   devtools' action for jumping to a state with
+//!   [with_time_travel()](crate::RouteMiddleware::with_time_travel), and
+//!   the middleware replaces the URL with the route of the restored
+//!   state once that action has been reduced, without navigating.
+//!   Devtools which replace the state in another way can dispatch
+//!   [RouteAction::SyncUrl] (see
+//!   [RouteStore::sync_url()](crate::RouteStore::sync_url)) afterwards.
+//! + In development with a [MemoryRouteService], a [HistoryRecording]
+//!   restores the history stack along with the state when the devtools
+//!   jump to an action.
+//!
+//! ```ignore
+//! let middleware = RouteMiddleware::new(route_service, store.clone())
+//!     .with_time_travel(|action: &AppAction| matches!(action, AppAction::Devtools(_)));
+//!
+//! let devtools = DevtoolsMiddleware::new().with_label(|action: &AppAction| {
+//!     devtools::action_label(action).unwrap_or_else(|| format!("{:?}", action))
+//! });
+//! ```
+
+use crate::{logging::debug, memory::MemoryRouteService, IsRouteAction, RouteAction};
+use std::{cell::RefCell, rc::Rc};
+use switch_router::SwitchRoute;
+
+/// The prefix of the labels of route actions.
+pub const LABEL_PREFIX: &str = "route/";
+
+/// Whether an action restores a previous state of the store, see
+/// [RouteMiddleware::with_time_travel()](crate::RouteMiddleware::with_time_travel).
+pub(crate) type TimeTravelFn<Action> = dyn Fn(&Action) -> bool;
+
+/// A label for the `action` in the devtools' action list, if it is a
+/// route action, e.g. `route/ChangeRoute(/items/5)`. Routes are labelled
+/// by their paths, see [RouteAction]'s `Display` implementation.
+pub fn action_label<SR, Action>(action: &Action) -> Option<String>
+where
+    SR: SwitchRoute + 'static,
+    Action: IsRouteAction<SR>,
+{
+    action.route_action().map(route_action_label)
+}
+
+/// A label for the route `action`, see [action_label()].
+pub fn route_action_label<SR>(action: &RouteAction<SR>) -> String
+where
+    SR: SwitchRoute,
+{
+    format!("{}{}", LABEL_PREFIX, action)
+}
+
+/// The history stack of a [MemoryRouteService] at a step of a
+/// [HistoryRecording].
+#[derive(PartialEq)]
+struct HistoryStack<R> {
+    entries: Vec<R>,
+    index: usize,
+}
+
+/// Records the history stack of a [MemoryRouteService] after each action
+/// dispatched to the store, so that when the devtools jump to an action,
+/// the history stack as it was at that point is restored, and back and
+/// forward continue to behave as they would have.
+///
+/// Step `0` is the history when the recording was created (the initial
+/// state of the devtools), and step `n` the history after the `n`th
+/// recorded action. Steps which leave the history unchanged share the
+/// recorded stack.
+///
+/// ```ignore
+/// let recording = HistoryRecording::new(route_service.clone());
+/// // After each action dispatched by the application:
+/// recording.record();
+/// // When the devtools jump to the state after action `n`:
+/// recording.jump_to(n);
+/// store.sync_url();
+/// ```
+pub struct HistoryRecording<R> {
+    service: MemoryRouteService<R>,
+    steps: RefCell<Vec<Rc<HistoryStack<R>>>>,
+}
+
+impl<R> HistoryRecording<R>
+where
+    R: SwitchRoute,
+{
+    /// Record the history of `service`, a clone of the service used by
+    /// the [RouteMiddleware](crate::RouteMiddleware).
+    pub fn new(service: MemoryRouteService<R>) -> Self {
+        let recording = Self {
+            service,
+            steps: RefCell::new(Vec::new()),
+        };
+        recording.record();
+        recording
+    }
+
+    /// Record the current history stack as the next step, returns the
+    /// step. Call after each action dispatched by the application, but
+    /// not for the actions of the devtools themselves.
+    pub fn record(&self) -> usize {
+        let stack = HistoryStack {
+            entries: self.service.entries(),
+            index: self.service.index(),
+        };
+        let mut steps = self.steps.borrow_mut();
+        let stack = match steps.last() {
+            Some(last) if **last == stack => last.clone(),
+            _ => Rc::new(stack),
+        };
+        steps.push(stack);
+        steps.len() - 1
+    }
+
+    /// The number of recorded steps.
+    pub fn len(&self) -> usize {
+        self.steps.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.borrow().is_empty()
+    }
+
+    /// Restore the history stack recorded at `step` to the service,
+    /// without notifying its callbacks. Returns the current route of the
+    /// restored history, or `None` if the step wasn't recorded. The
+    /// following steps are kept, so that the devtools can jump forward
+    /// again.
+    pub fn jump_to(&self, step: usize) -> Option<R> {
+        let stack = self.steps.borrow().get(step)?.clone();
+        debug!(
+            "Restoring the history of step {} at entry {} of {}",
+            step,
+            stack.index,
+            stack.entries.len()
+        );
+        self.service.set_history(stack.entries.clone(), stack.index);
+        Some(stack.entries[stack.index].clone())
+    }
+
+    /// Discard the steps from `len` onwards, e.g. when the application
+    /// dispatches an action after the devtools jumped back, replacing the
+    /// actions which followed.
+    pub fn truncate(&self, len: usize) {
+        self.steps.borrow_mut().truncate(len);
+    }
+}
@@ -0,0 +1,206 @@
+//! Health checks of the [RouteMiddleware](crate::RouteMiddleware), for
+//! when routing silently stops working (e.g. after a refactor drops the
+//! store, or registers the middleware with the wrong route service),
+//! requires the `diagnostics` feature.
+//!
+//! [RouteMiddleware::diagnostics()](crate::RouteMiddleware::diagnostics)
+//! describes the current condition of the middleware, and
+//! [Diagnostics::problems()] what looks wrong with it. In debug builds,
+//! [with_self_check()](crate::RouteMiddleware::with_self_check) logs the
+//! problems as warnings periodically.
+//!
+//! Diagnostics are displayed as a plain text report, for inclusion in bug
+//! reports, with the recent route actions if the [audit](crate::audit)
+//! log is enabled.
+
+use crate::audit::AuditEntry;
+use std::fmt::{self, Display};
+
+/// The cargo features this crate was built with.
+pub const FEATURES: &[&str] = &[
+    #[cfg(feature = "log")]
+    "log",
+    #[cfg(feature = "tracing")]
+    "tracing",
+    #[cfg(feature = "web")]
+    "web",
+    #[cfg(feature = "serde")]
+    "serde",
+    #[cfg(feature = "signing")]
+    "signing",
+    #[cfg(feature = "oauth")]
+    "oauth",
+    #[cfg(feature = "error-reporting")]
+    "error-reporting",
+    #[cfg(feature = "sentry")]
+    "sentry",
+    #[cfg(feature = "regex")]
+    "regex",
+    #[cfg(feature = "test-util")]
+    "test-util",
+    #[cfg(feature = "yew")]
+    "yew",
+    #[cfg(feature = "leptos")]
+    "leptos",
+    #[cfg(feature = "audit")]
+    "audit",
+    #[cfg(feature = "devtools")]
+    "devtools",
+    #[cfg(feature = "experiments")]
+    "experiments",
+    #[cfg(feature = "federation")]
+    "federation",
+    #[cfg(feature = "table-view")]
+    "table-view",
+    #[cfg(feature = "tauri")]
+    "tauri",
+    #[cfg(feature = "worker")]
+    "worker",
+    #[cfg(feature = "derive")]
+    "derive",
+    #[cfg(feature = "prefetch")]
+    "prefetch",
+    #[cfg(feature = "route-table")]
+    "route-table",
+    #[cfg(feature = "scroll")]
+    "scroll",
+    #[cfg(feature = "short-routes")]
+    "short-routes",
+    #[cfg(feature = "wizard")]
+    "wizard",
+];
+
+/// A navigation which the middleware has started, but not committed yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingNavigation {
+    /// The navigation is waiting for
+    /// [RouteAction::ConfirmPendingNavigation](crate::RouteAction::ConfirmPendingNavigation),
+    /// see [interceptor](crate::interceptor).
+    Confirmation(String),
+    /// The navigation is waiting for the chunk with this id to load, see
+    /// [chunks](crate::chunks).
+    Chunk(String),
+    /// A [RouteAction::ReplaceStackTop](crate::RouteAction::ReplaceStackTop)
+    /// is going back through this many history entries.
+    StackCompaction(usize),
+}
+
+impl Display for PendingNavigation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PendingNavigation::Confirmation(action) => {
+                write!(f, "{} awaiting confirmation", action)
+            }
+            PendingNavigation::Chunk(chunk_id) => write!(f, "awaiting chunk {:?}", chunk_id),
+            PendingNavigation::StackCompaction(remaining) => {
+                write!(f, "going back {} history entries", remaining)
+            }
+        }
+    }
+}
+
+/// The condition of a [RouteMiddleware](crate::RouteMiddleware), see the
+/// [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Whether the middleware's callback was registered with the route
+    /// service, without it routes arriving from the browser are missed.
+    pub callback_registered: bool,
+    /// Whether the store is alive. The middleware only holds a weak
+    /// reference to it, once it has been dropped nothing is dispatched.
+    pub store_alive: bool,
+    /// Whether the route service could be borrowed.
+    pub service_available: bool,
+    /// The path of the route service's route, if it is available.
+    pub service_path: Option<String>,
+    /// The path of the state's route, if the store is alive.
+    pub state_path: Option<String>,
+    /// Whether the route service displays the route of the state
+    /// (taking [masking](crate::masking) into account), if both are
+    /// available.
+    pub in_sync: Option<bool>,
+    pub pending: Vec<PendingNavigation>,
+    /// The number of route service operations queued while the service
+    /// was in use.
+    pub queued_service_ops: usize,
+    /// The last internal failure of the middleware.
+    pub last_error: Option<String>,
+    /// The entries of the [audit](crate::audit) log, oldest first, empty
+    /// if it isn't enabled.
+    pub recent_actions: Vec<AuditEntry>,
+    /// See [FEATURES].
+    pub features: &'static [&'static str],
+}
+
+impl Diagnostics {
+    /// Descriptions of what looks wrong with the middleware, empty if it
+    /// is healthy.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.callback_registered {
+            problems.push(
+                "the callback isn't registered with the route service, \
+                 routes arriving from the browser are missed"
+                    .to_string(),
+            );
+        }
+        if !self.store_alive {
+            problems.push("the store has been dropped".to_string());
+        }
+        if !self.service_available {
+            problems.push("the route service is in use".to_string());
+        }
+        // A navigation in progress is expected to leave them out of sync.
+        if self.in_sync == Some(false) && self.pending.is_empty() {
+            problems.push(format!(
+                "the route service displays {} rather than the route of the state {}",
+                self.service_path.as_deref().unwrap_or_default(),
+                self.state_path.as_deref().unwrap_or_default()
+            ));
+        }
+        if let Some(error) = &self.last_error {
+            problems.push(format!("internal failure: {}", error));
+        }
+        problems
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+/// The report of the diagnostics.
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "callback registered: {}", self.callback_registered)?;
+        writeln!(f, "store alive: {}", self.store_alive)?;
+        writeln!(f, "service available: {}", self.service_available)?;
+        writeln!(
+            f,
+            "service route: {}",
+            self.service_path.as_deref().unwrap_or("-")
+        )?;
+        writeln!(
+            f,
+            "state route: {}",
+            self.state_path.as_deref().unwrap_or("-")
+        )?;
+        for pending in &self.pending {
+            writeln!(f, "pending: {}", pending)?;
+        }
+        writeln!(f, "queued service operations: {}", self.queued_service_ops)?;
+        writeln!(
+            f,
+            "last error: {}",
+            self.last_error.as_deref().unwrap_or("-")
+        )?;
+        writeln!(f, "features: {}", self.features.join(", "))?;
+        for problem in self.problems() {
+            writeln!(f, "problem: {}", problem)?;
+        }
+        for entry in &self.recent_actions {
+            writeln!(f, "action {}", entry)?;
+        }
+        Ok(())
+    }
+}
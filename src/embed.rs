@@ -0,0 +1,106 @@
+//! Driving the router of an app embedded in an iframe from the parent
+//! page, e.g. in a micro-frontend setup.
+//!
+//! The parent page sends [EmbedMessage]s to the iframe with
+//! `postMessage`, which are translated into [RouteAction::ChangeRoute]
+//! and [RouteAction::Back] actions, and the iframe reports each committed
+//! route back to the parent with an [EmbedMessage::RouteCommitted]
+//! message. Messages from origins which are not allowed by the
+//! [EmbedConfig] are ignored.
+//!
+//! The bridge is installed with
+//! [RouteMiddleware::with_embed()](crate::RouteMiddleware::with_embed)
+//! (requires the `web` feature).
+
+use crate::RouteAction;
+use switch_router::SwitchRoute;
+
+/// The prefix of all encoded [EmbedMessage]s, distinguishing them from
+/// other messages posted between the windows.
+pub const MESSAGE_PREFIX: &str = "switch-router:";
+
+/// A message exchanged between the parent page and the embedded app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbedMessage {
+    /// Sent by the parent, navigate to the path.
+    Navigate(String),
+    /// Sent by the parent, go back in the embedded app's history.
+    Back,
+    /// Sent by the embedded app, the path of the route it committed.
+    RouteCommitted(String),
+}
+
+impl EmbedMessage {
+    /// Encode this message as the string data of a `postMessage`, e.g.
+    /// `switch-router:navigate:/settings`.
+    pub fn encode(&self) -> String {
+        match self {
+            EmbedMessage::Navigate(path) => format!("{}navigate:{}", MESSAGE_PREFIX, path),
+            EmbedMessage::Back => format!("{}back", MESSAGE_PREFIX),
+            EmbedMessage::RouteCommitted(path) => format!("{}route:{}", MESSAGE_PREFIX, path),
+        }
+    }
+
+    /// Decode a message [encoded](Self::encode) in `data`, returns `None`
+    /// if it isn't one.
+    pub fn decode(data: &str) -> Option<Self> {
+        let message = data.strip_prefix(MESSAGE_PREFIX)?;
+        if message == "back" {
+            Some(EmbedMessage::Back)
+        } else if let Some(path) = message.strip_prefix("navigate:") {
+            Some(EmbedMessage::Navigate(path.to_string()))
+        } else {
+            message
+                .strip_prefix("route:")
+                .map(|path| EmbedMessage::RouteCommitted(path.to_string()))
+        }
+    }
+
+    /// The action to dispatch in the embedded app for this message, if
+    /// it was sent by the parent.
+    pub fn into_route_action<R>(self) -> Option<RouteAction<R>>
+    where
+        R: SwitchRoute,
+    {
+        match self {
+            EmbedMessage::Navigate(path) => Some(RouteAction::ChangeRoute(R::switch(&path))),
+            EmbedMessage::Back => Some(RouteAction::Back),
+            EmbedMessage::RouteCommitted(_) => None,
+        }
+    }
+}
+
+/// Which origins may drive the embedded app's router, and where
+/// committed routes are reported to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedConfig {
+    parent_origin: String,
+    allowed_origins: Vec<String>,
+}
+
+impl EmbedConfig {
+    /// Accept messages from, and report committed routes to, the parent
+    /// page at `parent_origin`, e.g. `https://host.example.com`.
+    pub fn new<S: Into<String>>(parent_origin: S) -> Self {
+        let parent_origin = parent_origin.into();
+        Self {
+            allowed_origins: vec![parent_origin.clone()],
+            parent_origin,
+        }
+    }
+
+    /// Also accept messages from `origin`.
+    pub fn allow_origin<S: Into<String>>(mut self, origin: S) -> Self {
+        self.allowed_origins.push(origin.into());
+        self
+    }
+
+    pub fn parent_origin(&self) -> &str {
+        &self.parent_origin
+    }
+
+    /// Whether messages from `origin` are accepted.
+    pub fn is_allowed(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|allowed| allowed == origin)
+    }
+}
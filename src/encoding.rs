@@ -0,0 +1,287 @@
+//! Normalization of route paths with respect to percent-encoding, so that
+//! a route survives the round trip through the browser's address bar.
+//!
+//! Browsers are inconsistent about which characters they escape (IRIs,
+//! emoji in slugs, `+` vs `%20` in queries, lower case hex digits), so a
+//! route set by the application may come back via
+//! [RouteAction::BrowserChangeRoute](crate::RouteAction::BrowserChangeRoute)
+//! in a slightly different form. [normalize_path()] maps all of these
+//! forms onto one canonical form, following the normalization rules of
+//! [RFC 3986](https://tools.ietf.org/html/rfc3986#section-6.2.2):
+//!
+//! + Percent-encoded unreserved characters are decoded.
+//! + Hex digits in percent-encodings are upper case.
+//! + Characters not allowed in their component (including all non-ASCII
+//!   characters) are percent-encoded as UTF-8.
+//! + A `+` in the query is treated as a space, and spaces are encoded as
+//!   `%20`.
+//! + A `%` which does not start a valid percent-encoding is encoded as
+//!   `%25`.
+
+use std::fmt::Write;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Component {
+    Path,
+    Query,
+    Fragment,
+}
+
+impl Component {
+    fn allows(self, byte: u8) -> bool {
+        if is_unreserved(byte) {
+            return true;
+        }
+        match self {
+            Component::Path => b"!$&'()*+,;=:@/".contains(&byte),
+            // `&`, `=` and `+` are delimiters within the query.
+            Component::Query => b"!$'()*,;:@/?".contains(&byte),
+            Component::Fragment => b"!$&'()*+,;=:@/?".contains(&byte),
+        }
+    }
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~".contains(&byte)
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+fn push_encoded(output: &mut String, byte: u8) {
+    write!(output, "%{:02X}", byte).expect("writing to a String cannot fail");
+}
+
+fn normalize_component(input: &str, component: Component) -> String {
+    let bytes = input.as_bytes();
+    let mut output = String::with_capacity(input.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if byte == b'%' {
+            let escaped = bytes
+                .get(i + 1)
+                .and_then(|high| hex_value(*high))
+                .and_then(|high| {
+                    bytes
+                        .get(i + 2)
+                        .and_then(|low| hex_value(*low))
+                        .map(|low| high * 16 + low)
+                });
+            match escaped {
+                Some(decoded) if is_unreserved(decoded) => output.push(decoded as char),
+                Some(decoded) => push_encoded(&mut output, decoded),
+                None => {
+                    push_encoded(&mut output, byte);
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 3;
+            continue;
+        }
+
+        if component == Component::Query && byte == b'+' {
+            push_encoded(&mut output, b' ');
+        } else if component.allows(byte) {
+            output.push(byte as char);
+        } else {
+            push_encoded(&mut output, byte);
+        }
+        i += 1;
+    }
+    output
+}
+
+fn normalize_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| {
+            let mut key_value = pair.splitn(2, '=');
+            let key = normalize_component(key_value.next().unwrap_or(""), Component::Query);
+            match key_value.next() {
+                Some(value) => format!("{}={}", key, normalize_component(value, Component::Query)),
+                None => key,
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// Normalize the percent-encoding of a route `path` (which may include
+/// a query and fragment). See the [module documentation](self) for the
+/// rules which are applied.
+pub fn normalize_path(path: &str) -> String {
+    let (path, fragment) = match path.find('#') {
+        Some(index) => (&path[..index], Some(&path[index + 1..])),
+        None => (path, None),
+    };
+    let (path, query) = match path.find('?') {
+        Some(index) => (&path[..index], Some(&path[index + 1..])),
+        None => (path, None),
+    };
+
+    let mut normalized = normalize_component(path, Component::Path);
+    if let Some(query) = query {
+        normalized.push('?');
+        normalized.push_str(&normalize_query(query));
+    }
+    if let Some(fragment) = fragment {
+        normalized.push('#');
+        normalized.push_str(&normalize_component(fragment, Component::Fragment));
+    }
+    normalized
+}
+
+/// Decode a percent-encoded query key or value, treating `+` as a space.
+/// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+pub fn decode_query_component(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let escaped = if byte == b'%' {
+            bytes
+                .get(i + 1)
+                .and_then(|high| hex_value(*high))
+                .and_then(|high| {
+                    bytes
+                        .get(i + 2)
+                        .and_then(|low| hex_value(*low))
+                        .map(|low| high * 16 + low)
+                })
+        } else {
+            None
+        };
+        match escaped {
+            Some(escaped) => {
+                decoded.push(escaped);
+                i += 3;
+            }
+            None => {
+                decoded.push(if byte == b'+' { b' ' } else { byte });
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Percent-encode a query key or value, in the canonical form produced
+/// by [normalize_path()].
+pub fn encode_query_component(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if Component::Query.allows(byte) {
+            encoded.push(byte as char);
+        } else {
+            push_encoded(&mut encoded, byte);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Characters which are reserved or otherwise special in a route,
+    /// and fragments of (possibly malformed) percent-encodings.
+    const ROUTE_CHARS: &str = "[-a-zA-Z0-9%+&=#?/ ._~:@!$'()*,;éß日🦀]{0,24}";
+
+    /// Percent-encode every byte, including those which needn't be.
+    fn encode_all(bytes: &[u8]) -> String {
+        let mut encoded = String::new();
+        for byte in bytes {
+            push_encoded(&mut encoded, *byte);
+        }
+        encoded
+    }
+
+    proptest! {
+        #[test]
+        fn query_component_roundtrip(input in any::<String>()) {
+            prop_assert_eq!(decode_query_component(&encode_query_component(&input)), input);
+        }
+
+        #[test]
+        fn reserved_characters_roundtrip(input in ROUTE_CHARS) {
+            let encoded = encode_query_component(&input);
+            prop_assert!(
+                !encoded.contains(['&', '=', '+', '#', ' ']),
+                "delimiter left unencoded in {:?}",
+                encoded
+            );
+            prop_assert_eq!(decode_query_component(&encoded), input);
+        }
+
+        #[test]
+        fn encoded_query_is_canonical(input in any::<String>()) {
+            let path = format!("/search?q={}", encode_query_component(&input));
+            prop_assert_eq!(normalize_path(&path), path);
+        }
+
+        #[test]
+        fn normalize_is_idempotent(path in ROUTE_CHARS) {
+            let normalized = normalize_path(&path);
+            prop_assert!(normalized.is_ascii(), "{:?} is not ASCII", normalized);
+            prop_assert_eq!(normalize_path(&normalized), normalized);
+        }
+
+        #[test]
+        fn normalize_preserves_query_values(value in ROUTE_CHARS) {
+            let value = value.replace(['&', '=', '#'], "");
+            let normalized = normalize_path(&format!("/?q={}", value));
+            let normalized_value = normalized.strip_prefix("/?q=").unwrap();
+            prop_assert_eq!(
+                decode_query_component(normalized_value),
+                decode_query_component(&value)
+            );
+        }
+
+        #[test]
+        fn invalid_utf8_is_replaced(bytes in prop::collection::vec(any::<u8>(), 0..16)) {
+            prop_assert_eq!(
+                decode_query_component(&encode_all(&bytes)),
+                String::from_utf8_lossy(&bytes).into_owned()
+            );
+        }
+    }
+
+    #[test]
+    fn plus_is_a_space_in_the_query_only() {
+        assert_eq!(normalize_path("/a+b?c+d=e+f#g+h"), "/a+b?c%20d=e%20f#g+h");
+        assert_eq!(decode_query_component("a+b%2Bc"), "a b+c");
+        assert_eq!(encode_query_component("a b+c"), "a%20b%2Bc");
+    }
+
+    #[test]
+    fn unreserved_characters_are_decoded() {
+        assert_eq!(normalize_path("/%7euser/%41%2d%2f"), "/~user/A-%2F");
+    }
+
+    #[test]
+    fn non_ascii_characters_are_encoded() {
+        assert_eq!(normalize_path("/café/🦀"), "/caf%C3%A9/%F0%9F%A6%80");
+        assert_eq!(normalize_path("/caf%c3%a9"), "/caf%C3%A9");
+    }
+
+    #[test]
+    fn malformed_escapes_are_encoded() {
+        assert_eq!(normalize_path("/100%"), "/100%25");
+        assert_eq!(normalize_path("/%zz/%4"), "/%25zz/%254");
+        assert_eq!(decode_query_component("%"), "%");
+        assert_eq!(decode_query_component("%4"), "%4");
+        assert_eq!(decode_query_component("%zz"), "%zz");
+    }
+
+    #[test]
+    fn invalid_utf8_escapes_are_preserved() {
+        assert_eq!(normalize_path("/%ff%fe"), "/%FF%FE");
+        assert_eq!(decode_query_component("%FF"), "\u{FFFD}");
+        assert_eq!(decode_query_component("%C3"), "\u{FFFD}");
+    }
+}
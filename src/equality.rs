@@ -0,0 +1,75 @@
+//! What counts as the "same route" for the
+//! [RouteMiddleware](crate::RouteMiddleware): navigating to the same
+//! route as the current one replaces the current history entry rather
+//! than adding a new one, and doesn't count as a navigation for features
+//! which track route changes (such as prefetching,
+//! [embed](crate::embed) and federation).
+//!
+//! By default routes are compared with [PartialEq], the
+//! [RouteEquality] is configured with
+//! [with_route_equality()](crate::RouteMiddleware::with_route_equality).
+
+use crate::{canonical::split_path, query::QueryParams};
+use switch_router::SwitchRoute;
+
+type CompareFn<R> = dyn Fn(&R, &R) -> bool;
+
+/// A strategy for comparing routes, see the
+/// [module documentation](self).
+#[derive(Default)]
+pub enum RouteEquality<R> {
+    /// Routes are the same if they are equal.
+    #[default]
+    Full,
+    /// Routes are the same if the path portions of their paths are
+    /// equal, ignoring the query and fragment.
+    Path,
+    /// Routes are the same if the comparator returns true.
+    Custom(Box<CompareFn<R>>),
+}
+
+impl<R> RouteEquality<R>
+where
+    R: SwitchRoute,
+{
+    /// Routes are the same if the comparator returns true.
+    pub fn custom<F>(compare: F) -> Self
+    where
+        F: Fn(&R, &R) -> bool + 'static,
+    {
+        RouteEquality::Custom(Box::new(compare))
+    }
+
+    /// Routes are the same if their paths are equal, ignoring the query
+    /// parameters with the `ignored` keys (e.g. `utm_source` and other
+    /// analytics parameters) and the fragment.
+    pub fn ignoring_query_params(ignored: &[&str]) -> Self
+    where
+        R: 'static,
+    {
+        let ignored: Vec<String> = ignored.iter().map(|key| key.to_string()).collect();
+        Self::custom(move |a: &R, b: &R| {
+            let (a, b) = (a.path(), b.path());
+            if split_path(&a).0 != split_path(&b).0 {
+                return false;
+            }
+            let retained = |path: &str| {
+                let mut params = QueryParams::from_path(path);
+                for key in &ignored {
+                    params.remove(key);
+                }
+                params
+            };
+            retained(&a) == retained(&b)
+        })
+    }
+
+    /// Whether `a` and `b` are the same route.
+    pub fn same(&self, a: &R, b: &R) -> bool {
+        match self {
+            RouteEquality::Full => a == b,
+            RouteEquality::Path => split_path(&a.path()).0 == split_path(&b.path()).0,
+            RouteEquality::Custom(compare) => compare(a, b),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! The "something went wrong" page pattern, at the router level.
+//!
+//! The application declares its error route with
+//! [with_error_route()](crate::RouteMiddleware::with_error_route). When a
+//! page fails (e.g. its data failed to load), it calls
+//! [RouteStore::navigate_to_error()](crate::RouteStore::navigate_to_error),
+//! which replaces the failed route with the error route, while the
+//! middleware stashes the failed route. A "Try again" button on the
+//! error page then calls
+//! [RouteStore::retry_failed_route()](crate::RouteStore::retry_failed_route)
+//! to navigate back to it.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A description of the error which caused the navigation to the error
+/// route.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorInfo {
+    pub message: String,
+    /// An optional machine readable code, e.g. `"not_found"`.
+    pub code: Option<String>,
+}
+
+impl ErrorInfo {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Self {
+            message: message.into(),
+            code: None,
+        }
+    }
+
+    pub fn with_code<S: Into<String>>(mut self, code: S) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+}
+
+impl std::fmt::Display for ErrorInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} ({})", self.message, code),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
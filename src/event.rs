@@ -0,0 +1,149 @@
+//! Implementing [RouteEvent](crate::RouteEvent) for the application's
+//! event enum.
+//!
+//! Most of the constructors of [RouteEvent](crate::RouteEvent) default
+//! to ignoring the event, so a hand-written implementation silently
+//! falls behind as the middleware emits more of them. With the `derive`
+//! feature, `#[derive(RouteEvent)]` generates the implementation from
+//! `route_event` attributes on the variants of the event enum, naming
+//! the constructors each variant is built by. The fields of the variant
+//! hold the arguments of the constructor, in order, or a variant
+//! without fields ignores them:
+//!
+//! ```ignore
+//! #[derive(RouteEvent)]
+//! #[route_event(AppRoute)]
+//! enum AppEvent {
+//!     #[route_event(route_changed)]
+//!     RouteChanged,
+//!     #[route_event(navigation_blocked, navigation_failed)]
+//!     NavigationFailed,
+//!     #[route_event(url_too_long)]
+//!     UrlTooLong(usize, usize),
+//!     #[route_event(scope_changed)]
+//!     ScopeChanged { scope: String, transition: ScopeTransition },
+//!     Saved,
+//! }
+//! ```
+//!
+//! Without the feature, the
+//! [route_event!](crate::route_event) macro generates the implementation
+//! from a table of the constructors the application handles, mapping
+//! each to a variant of its event enum (or a function or closure
+//! returning the event):
+//!
+//! ```ignore
+//! enum AppEvent {
+//!     RouteChanged,
+//!     NavigationBlocked,
+//!     UrlTooLong(usize, usize),
+//!     ChunkLoadFailed(String),
+//!     ScopeChanged { scope: String, transition: ScopeTransition },
+//! }
+//!
+//! route_event! {
+//!     impl RouteEvent<AppRoute> for AppEvent {
+//!         route_changed => AppEvent::RouteChanged,
+//!         navigation_blocked => AppEvent::NavigationBlocked,
+//!         url_too_long => AppEvent::UrlTooLong,
+//!         chunk_load_failed => AppEvent::ChunkLoadFailed,
+//!         scope_changed => |scope, transition| AppEvent::ScopeChanged { scope, transition },
+//!     }
+//! }
+//! ```
+//!
+//! The constructors without arguments (`route_changed`,
+//! `navigation_blocked` and `initial_navigation_complete`) map to the
+//! event itself, the others to something called with the arguments of
+//! the constructor, where string arguments are passed as an owned
+//! `String`. With either, constructors which are left out keep their
+//! default, and misspelled ones fail to compile.
+//!
+//! Applications which don't handle route events at all implement
+//! [NoRouteEvents](crate::NoRouteEvents) for their event type instead
+//! (or use `()`).
+
+/// Implement [RouteEvent](crate::RouteEvent) from a table of
+/// constructors, see the [module documentation](crate::event).
+#[macro_export]
+macro_rules! route_event {
+    (
+        impl RouteEvent<$route:ty> for $event:ty {
+            $($method:ident => $constructor:expr),* $(,)?
+        }
+    ) => {
+        impl $crate::RouteEvent<$route> for $event {
+            $($crate::route_event!(@method $method $constructor);)*
+        }
+    };
+    (@method route_changed $constructor:expr) => {
+        fn route_changed() -> Self {
+            $constructor
+        }
+    };
+    (@method navigation_blocked $constructor:expr) => {
+        fn navigation_blocked() -> ::std::option::Option<Self> {
+            ::std::option::Option::Some($constructor)
+        }
+    };
+    (@method initial_navigation_complete $constructor:expr) => {
+        fn initial_navigation_complete() -> ::std::option::Option<Self> {
+            ::std::option::Option::Some($constructor)
+        }
+    };
+    (@method url_too_long $constructor:expr) => {
+        fn url_too_long(length: usize, max_length: usize) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(length, max_length))
+        }
+    };
+    (@method confirmation_required $constructor:expr) => {
+        fn confirmation_required(payload: &str) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(::std::borrow::ToOwned::to_owned(payload)))
+        }
+    };
+    (@method route_signature_checked $constructor:expr) => {
+        fn route_signature_checked(
+            result: ::std::result::Result<(), $crate::signing::SignatureError>,
+        ) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(result))
+        }
+    };
+    (@method oauth_state_failed $constructor:expr) => {
+        fn oauth_state_failed(error: $crate::oauth::OAuthStateError) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(error))
+        }
+    };
+    (@method chunk_load_failed $constructor:expr) => {
+        fn chunk_load_failed(chunk_id: &str) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(::std::borrow::ToOwned::to_owned(chunk_id)))
+        }
+    };
+    (@method scope_changed $constructor:expr) => {
+        fn scope_changed(
+            scope: &str,
+            transition: $crate::scope::ScopeTransition,
+        ) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(
+                ::std::borrow::ToOwned::to_owned(scope),
+                transition,
+            ))
+        }
+    };
+    (@method navigation_failed $constructor:expr) => {
+        fn navigation_failed(path: &str) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(::std::borrow::ToOwned::to_owned(path)))
+        }
+    };
+    (@method history_traversed $constructor:expr) => {
+        fn history_traversed(delta: isize) -> ::std::option::Option<Self> {
+            ::std::option::Option::Some(($constructor)(delta))
+        }
+    };
+    (@method $method:ident $constructor:expr) => {
+        ::std::compile_error!(::std::concat!(
+            "`",
+            ::std::stringify!($method),
+            "` is not a constructor of RouteEvent"
+        ));
+    };
+}
@@ -0,0 +1,205 @@
+//! A/B experiment routing (requires the `experiments` feature). An
+//! [Experiment] rewrites requested routes to
+//! the route of the variant which the current subject (user, device) is
+//! assigned to.
+//!
+//! Assignment is deterministic (a hash of the experiment name and the
+//! subject id), and sticky: once assigned, the variant is persisted in
+//! an [AssignmentStorage] so that changing the variant weights doesn't
+//! move existing subjects between variants.
+
+use crate::hash::fnv1a;
+use std::{cell::RefCell, collections::HashMap};
+
+/// Persistent storage of experiment assignments.
+pub trait AssignmentStorage {
+    /// Load the variant which was assigned for `experiment`.
+    fn load(&self, experiment: &str) -> Option<String>;
+    /// Save the `variant` assigned for `experiment`.
+    fn save(&self, experiment: &str, variant: &str);
+}
+
+/// An [AssignmentStorage] which keeps assignments in memory. See
+/// [LocalStorageAssignments](crate::web::LocalStorageAssignments) for
+/// persistent assignments.
+#[derive(Debug, Default)]
+pub struct MemoryAssignmentStorage {
+    assignments: RefCell<HashMap<String, String>>,
+}
+
+impl MemoryAssignmentStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AssignmentStorage for MemoryAssignmentStorage {
+    fn load(&self, experiment: &str) -> Option<String> {
+        self.assignments.borrow().get(experiment).cloned()
+    }
+
+    fn save(&self, experiment: &str, variant: &str) {
+        self.assignments
+            .borrow_mut()
+            .insert(experiment.to_string(), variant.to_string());
+    }
+}
+
+type RewriteFn<R> = dyn Fn(&R) -> R;
+type AppliesToFn<R> = dyn Fn(&R) -> bool;
+
+/// A variant of an [Experiment].
+pub struct Variant<R> {
+    name: String,
+    weight: u32,
+    rewrite: Option<Box<RewriteFn<R>>>,
+}
+
+impl<R> Variant<R> {
+    /// The control variant, which leaves the requested route unchanged.
+    pub fn control<S: Into<String>>(name: S, weight: u32) -> Self {
+        Self {
+            name: name.into(),
+            weight,
+            rewrite: None,
+        }
+    }
+
+    /// A variant which rewrites the requested route using `rewrite`.
+    pub fn new<S, F>(name: S, weight: u32, rewrite: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&R) -> R + 'static,
+    {
+        Self {
+            name: name.into(),
+            weight,
+            rewrite: Some(Box::new(rewrite)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// An experiment applying to the routes accepted by its `applies_to`
+/// predicate.
+pub struct Experiment<R> {
+    name: String,
+    applies_to: Box<AppliesToFn<R>>,
+    variants: Vec<Variant<R>>,
+}
+
+impl<R> Experiment<R> {
+    pub fn new<S, F>(name: S, applies_to: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&R) -> bool + 'static,
+    {
+        Self {
+            name: name.into(),
+            applies_to: Box::new(applies_to),
+            variants: Vec::new(),
+        }
+    }
+
+    pub fn with_variant(mut self, variant: Variant<R>) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Deterministically choose a variant for the `subject`, weighted by
+    /// the variant weights.
+    fn choose(&self, subject: &str) -> Option<&Variant<R>> {
+        let total: u64 = self.variants.iter().map(|v| u64::from(v.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut point = fnv1a(&format!("{}:{}", self.name, subject)) % total;
+        self.variants.iter().find(|variant| {
+            let weight = u64::from(variant.weight);
+            if point < weight {
+                true
+            } else {
+                point -= weight;
+                false
+            }
+        })
+    }
+}
+
+type SubjectFn<State> = dyn Fn(&State) -> Option<String>;
+type AssignmentFn = dyn Fn(&str, &str);
+
+/// The set of running experiments.
+pub struct Experiments<R, State> {
+    experiments: Vec<Experiment<R>>,
+    storage: Box<dyn AssignmentStorage>,
+    subject: Box<SubjectFn<State>>,
+    on_assignment: Option<Box<AssignmentFn>>,
+}
+
+impl<R, State> Experiments<R, State> {
+    /// Create a new [Experiments], where `subject` provides the id of the
+    /// current subject from the state. While there is no subject,
+    /// requested routes are left unchanged.
+    pub fn new<S, F>(storage: S, subject: F) -> Self
+    where
+        S: AssignmentStorage + 'static,
+        F: Fn(&State) -> Option<String> + 'static,
+    {
+        Self {
+            experiments: Vec::new(),
+            storage: Box::new(storage),
+            subject: Box::new(subject),
+            on_assignment: None,
+        }
+    }
+
+    pub fn with_experiment(mut self, experiment: Experiment<R>) -> Self {
+        self.experiments.push(experiment);
+        self
+    }
+
+    /// Analytics callback, invoked with the experiment name and variant
+    /// name whenever a subject is first assigned to a variant.
+    pub fn with_analytics<F>(mut self, on_assignment: F) -> Self
+    where
+        F: Fn(&str, &str) + 'static,
+    {
+        self.on_assignment = Some(Box::new(on_assignment));
+        self
+    }
+
+    /// Find the assigned variant of `experiment`, assigning one if
+    /// necessary.
+    fn variant<'a>(&self, experiment: &'a Experiment<R>, subject: &str) -> Option<&'a Variant<R>> {
+        if let Some(name) = self.storage.load(&experiment.name) {
+            if let Some(variant) = experiment.variants.iter().find(|v| v.name == name) {
+                return Some(variant);
+            }
+        }
+
+        let variant = experiment.choose(subject)?;
+        self.storage.save(&experiment.name, &variant.name);
+        if let Some(on_assignment) = &self.on_assignment {
+            on_assignment(&experiment.name, &variant.name);
+        }
+        Some(variant)
+    }
+
+    /// Rewrite the requested `route` to the assigned variant route of the
+    /// first experiment which applies to it, returns `None` if the route
+    /// is unchanged.
+    pub(crate) fn rewrite(&self, state: &State, route: &R) -> Option<R> {
+        let experiment = self.experiments.iter().find(|e| (e.applies_to)(route))?;
+        let subject = (self.subject)(state)?;
+        let variant = self.variant(experiment, &subject)?;
+        variant.rewrite.as_ref().map(|rewrite| rewrite(route))
+    }
+}
@@ -0,0 +1,263 @@
+//! Micro-frontend route federation: a host app delegates a URL prefix
+//! (e.g. `/shop`) to a child app with its own store and
+//! [RouteMiddleware](crate::RouteMiddleware), running in the same page.
+//! Requires the `federation` feature.
+//!
+//! The protocol has two sides:
+//!
+//! + The child's middleware uses a [FederatedRouteService], created from
+//!   a [FederatedChild]. Its navigations are reported up to the host
+//!   through a [FederationParent] (usually a [HostLink]), which
+//!   dispatches them as route actions on the host's store with the
+//!   prefix added, so they are recorded in the host's (browser) history.
+//! + The host's middleware is given the [FederatedChild] with
+//!   [with_delegation()](crate::RouteMiddleware::with_delegation), and
+//!   forwards the sub-path of every committed route under the prefix to
+//!   it, where it is delivered to the child's middleware like a
+//!   popstate event.
+//!
+//! Going back and forward in the browser changes the host's route, which
+//! is forwarded to the child, so the host and the child behave as one
+//! history.
+
+use crate::RouteAction;
+use std::{cell::RefCell, rc::Rc};
+use switch_router::{Callback, SwitchRoute, SwitchRouteService};
+
+type DispatchFn<R> = dyn Fn(RouteAction<R>);
+
+/// The host's side of the protocol, which receives the navigations of a
+/// child app. Paths are relative to the delegated prefix.
+pub trait FederationParent {
+    /// The child navigated to `path`, replacing its current history
+    /// entry if `replace` is true.
+    fn navigate(&self, path: &str, replace: bool);
+    /// The child wants to go back in the history.
+    fn back(&self);
+}
+
+/// The child's side of the protocol, which receives the sub-paths of
+/// the host's routes under the delegated prefix.
+pub trait FederationChild {
+    fn forward(&self, path: &str);
+}
+
+/// A [FederationParent] which dispatches the navigations of the child
+/// on the host's store, with the `prefix` added to their paths.
+pub struct HostLink<R> {
+    prefix: String,
+    dispatch: Box<DispatchFn<R>>,
+}
+
+impl<R> HostLink<R>
+where
+    R: SwitchRoute,
+{
+    /// Create a new [HostLink] for the child delegated `prefix`, which
+    /// dispatches route actions on the host's store with `dispatch`.
+    pub fn new<S, F>(prefix: S, dispatch: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(RouteAction<R>) + 'static,
+    {
+        Self {
+            prefix: prefix.into(),
+            dispatch: Box::new(dispatch),
+        }
+    }
+}
+
+impl<R> FederationParent for HostLink<R>
+where
+    R: SwitchRoute,
+{
+    fn navigate(&self, path: &str, replace: bool) {
+        let route = R::switch(&join_prefix(&self.prefix, path));
+        if replace {
+            (self.dispatch)(RouteAction::ReplaceRoute(route));
+        } else {
+            (self.dispatch)(RouteAction::ChangeRoute(route));
+        }
+    }
+
+    fn back(&self) {
+        (self.dispatch)(RouteAction::Back);
+    }
+}
+
+struct ChildRoute<R> {
+    route: R,
+    callbacks: Vec<Callback<R>>,
+}
+
+/// The current route of a child app, shared between the
+/// [FederatedRouteService] of the child and the host which forwards
+/// routes to it. Clones share the same route.
+pub struct FederatedChild<R> {
+    inner: Rc<RefCell<ChildRoute<R>>>,
+}
+
+impl<R> FederatedChild<R>
+where
+    R: SwitchRoute,
+{
+    pub fn new(initial: R) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(ChildRoute {
+                route: initial,
+                callbacks: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn route(&self) -> R {
+        self.inner.borrow().route.clone()
+    }
+
+    /// The route service for the child's middleware, reporting its
+    /// navigations to `parent`.
+    pub fn service<P>(&self, parent: P) -> FederatedRouteService<R>
+    where
+        P: FederationParent + 'static,
+    {
+        FederatedRouteService {
+            child: self.clone(),
+            parent: Rc::new(parent),
+        }
+    }
+}
+
+impl<R> Clone for FederatedChild<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<R> FederationChild for FederatedChild<R>
+where
+    R: SwitchRoute,
+{
+    /// Deliver the route for `path` to the child's callbacks, unless it
+    /// is already the current route (e.g. because the child navigated
+    /// to it).
+    fn forward(&self, path: &str) {
+        let route = R::switch(path);
+        let callbacks = {
+            let mut inner = self.inner.borrow_mut();
+            if inner.route == route {
+                return;
+            }
+            inner.route = route.clone();
+            inner.callbacks.clone()
+        };
+        // The callbacks are invoked outside of the borrow, they dispatch
+        // actions which use the service.
+        for callback in callbacks {
+            callback.emit(route.clone());
+        }
+    }
+}
+
+/// The [SwitchRouteService] of a child app, see the
+/// [module documentation](self).
+pub struct FederatedRouteService<R> {
+    child: FederatedChild<R>,
+    parent: Rc<dyn FederationParent>,
+}
+
+impl<R> SwitchRouteService for FederatedRouteService<R>
+where
+    R: SwitchRoute,
+{
+    type Route = R;
+
+    fn set_route<SRI: Into<R>>(&mut self, switch_route: SRI) {
+        let route = switch_route.into();
+        let path = route.path();
+        self.child.inner.borrow_mut().route = route;
+        self.parent.navigate(&path, false);
+    }
+
+    fn replace_route<SRI: Into<R>>(&mut self, switch_route: SRI) -> R {
+        let route = switch_route.into();
+        let path = route.path();
+        let previous = std::mem::replace(&mut self.child.inner.borrow_mut().route, route);
+        self.parent.navigate(&path, true);
+        previous
+    }
+
+    fn register_callback(&mut self, callback: &Callback<R>) {
+        self.child
+            .inner
+            .borrow_mut()
+            .callbacks
+            .push(callback.clone());
+    }
+
+    /// Asks the host to go back, the route is delivered to the callbacks
+    /// when the host forwards it.
+    fn back(&mut self) -> Option<R> {
+        self.parent.back();
+        None
+    }
+
+    fn get_route(&self) -> R {
+        self.child.route()
+    }
+}
+
+/// The URL prefixes delegated to child apps by a host.
+pub(crate) struct Delegations {
+    delegations: Vec<(String, Box<dyn FederationChild>)>,
+}
+
+impl Delegations {
+    pub(crate) fn new() -> Self {
+        Self {
+            delegations: Vec::new(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.delegations.is_empty()
+    }
+
+    pub(crate) fn insert(&mut self, prefix: String, child: Box<dyn FederationChild>) {
+        self.delegations.push((prefix, child));
+    }
+
+    /// Forward the sub-path of the host's `path` to the children whose
+    /// prefix it is under.
+    pub(crate) fn forward(&self, path: &str) {
+        for (prefix, child) in &self.delegations {
+            if let Some(sub_path) = strip_prefix(prefix, path) {
+                child.forward(&sub_path);
+            }
+        }
+    }
+}
+
+/// The host's path for the child's `path` under `prefix`.
+fn join_prefix(prefix: &str, path: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    if path == "/" {
+        prefix.to_string()
+    } else if path.starts_with('/') {
+        format!("{}{}", prefix, path)
+    } else {
+        format!("{}/{}", prefix, path)
+    }
+}
+
+/// The child's path for the host's `path`, if it is under `prefix`.
+fn strip_prefix(prefix: &str, path: &str) -> Option<String> {
+    let remainder = path.strip_prefix(prefix.trim_end_matches('/'))?;
+    match remainder.chars().next() {
+        None => Some("/".to_string()),
+        Some('/') => Some(remainder.to_string()),
+        Some('?') | Some('#') => Some(format!("/{}", remainder)),
+        Some(_) => None,
+    }
+}
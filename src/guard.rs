@@ -0,0 +1,394 @@
+//! Guards decide whether a navigation to a route may proceed. They are
+//! run both for navigations initiated by the application
+//! (`ChangeRoute`) and for routes arriving from the browser
+//! (`BrowserChangeRoute`), so that a route can't be reached by typing
+//! its URL directly.
+
+use crate::{
+    metadata::RouteMetadata, navigation::NavigationKind, params::RouteParams, services::Services,
+};
+use std::{cell::RefCell, rc::Rc};
+use switch_router::SwitchRoute;
+
+/// Everything a [RouteGuard] may need in order to make its decision.
+pub struct GuardContext<'a, R, State> {
+    /// A snapshot of the store's state.
+    pub state: Rc<State>,
+    /// The route being navigated to.
+    pub target: &'a R,
+    /// The route currently in the store.
+    pub previous: &'a R,
+    /// How the navigation was initiated.
+    pub kind: NavigationKind,
+    /// The metadata registered for the `target` route (if any).
+    pub metadata: Option<&'a RouteMetadata>,
+    /// The services provided to the middleware with
+    /// [with_service()](crate::RouteMiddleware::with_service).
+    pub services: &'a Services,
+}
+
+impl<'a, R, State> GuardContext<'a, R, State> {
+    /// The [RouteParams] of the `target` route, or `None` if it is
+    /// another route.
+    pub fn target_params<P: RouteParams<R>>(&self) -> Option<P> {
+        P::from_route(self.target)
+    }
+}
+
+/// The outcome of a [RouteGuard] check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuardOutcome<R> {
+    /// Allow the navigation to proceed.
+    Allow,
+    /// Navigate to the specified route instead.
+    Redirect(R),
+    /// Don't navigate, remain on the current route.
+    Block,
+}
+
+/// Decides whether a navigation may proceed, given its [GuardContext].
+///
+/// Guards are run in the order they were added to the middleware, the
+/// first outcome other than [GuardOutcome::Allow] wins. Routes which are
+/// the target of a redirect are not guarded again.
+pub trait RouteGuard<R, State> {
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R>;
+}
+
+impl<R, State, F> RouteGuard<R, State> for F
+where
+    F: Fn(&GuardContext<'_, R, State>) -> GuardOutcome<R>,
+{
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R> {
+        (self)(context)
+    }
+}
+
+type FlagEnabledFn<State> = dyn Fn(&State, &str) -> bool;
+
+/// A [RouteGuard] which only allows navigating to routes whose
+/// [feature flags](RouteMetadata::requires_feature_flag) are all enabled.
+pub struct FeatureFlagGuard<R, State> {
+    is_enabled: Box<FlagEnabledFn<State>>,
+    redirect: Option<R>,
+}
+
+impl<R, State> FeatureFlagGuard<R, State> {
+    /// Create a new [FeatureFlagGuard], where `is_enabled` decides
+    /// whether a flag is enabled given the current state. Navigations to
+    /// gated routes are blocked.
+    pub fn new<F>(is_enabled: F) -> Self
+    where
+        F: Fn(&State, &str) -> bool + 'static,
+    {
+        Self {
+            is_enabled: Box::new(is_enabled),
+            redirect: None,
+        }
+    }
+
+    /// Redirect navigations to gated routes to `route`, instead of
+    /// blocking them.
+    pub fn with_redirect(mut self, route: R) -> Self {
+        self.redirect = Some(route);
+        self
+    }
+}
+
+impl<R, State> RouteGuard<R, State> for FeatureFlagGuard<R, State>
+where
+    R: Clone,
+{
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R> {
+        let enabled = context
+            .metadata
+            .map(|metadata| {
+                metadata
+                    .feature_flags()
+                    .iter()
+                    .all(|flag| (self.is_enabled)(&context.state, flag))
+            })
+            .unwrap_or(true);
+
+        if enabled {
+            GuardOutcome::Allow
+        } else {
+            match &self.redirect {
+                Some(route) => GuardOutcome::Redirect(route.clone()),
+                None => GuardOutcome::Block,
+            }
+        }
+    }
+}
+
+type MatcherFn<R> = dyn Fn(&R) -> bool;
+type IsAuthenticatedFn<State> = dyn Fn(&State) -> bool;
+type HasPermissionFn<State> = dyn Fn(&State, &str) -> bool;
+
+struct AuthorizationRule<R> {
+    matcher: Box<MatcherFn<R>>,
+    permissions: Vec<String>,
+}
+
+/// A [RouteGuard] implementing a declarative authorization table, which
+/// maps routes to the roles/permissions required to access them.
+///
+/// For the first rule matching the target route:
+///
+/// + If the user is not authenticated, redirect to the login route.
+/// + If the user lacks any of the required permissions, redirect to the
+///   forbidden (403) route.
+///
+/// Routes matching no rule are allowed.
+pub struct AuthorizationGuard<R, State> {
+    rules: Vec<AuthorizationRule<R>>,
+    is_authenticated: Box<IsAuthenticatedFn<State>>,
+    has_permission: Box<HasPermissionFn<State>>,
+    login_route: R,
+    forbidden_route: R,
+}
+
+impl<R, State> AuthorizationGuard<R, State> {
+    /// Create a new [AuthorizationGuard], reading the authentication
+    /// status and permissions of the user from the state.
+    pub fn new<A, P>(
+        is_authenticated: A,
+        has_permission: P,
+        login_route: R,
+        forbidden_route: R,
+    ) -> Self
+    where
+        A: Fn(&State) -> bool + 'static,
+        P: Fn(&State, &str) -> bool + 'static,
+    {
+        Self {
+            rules: Vec::new(),
+            is_authenticated: Box::new(is_authenticated),
+            has_permission: Box::new(has_permission),
+            login_route,
+            forbidden_route,
+        }
+    }
+
+    /// Require the user to be authenticated and to have all the specified
+    /// `permissions` (which may be empty) for routes accepted by
+    /// `matcher`.
+    pub fn rule<F, I, S>(mut self, matcher: F, permissions: I) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rules.push(AuthorizationRule {
+            matcher: Box::new(matcher),
+            permissions: permissions.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+}
+
+impl<R, State> RouteGuard<R, State> for AuthorizationGuard<R, State>
+where
+    R: Clone,
+{
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R> {
+        let rule = match self
+            .rules
+            .iter()
+            .find(|rule| (rule.matcher)(context.target))
+        {
+            Some(rule) => rule,
+            None => return GuardOutcome::Allow,
+        };
+
+        if !(self.is_authenticated)(&context.state) {
+            return GuardOutcome::Redirect(self.login_route.clone());
+        }
+
+        let permitted = rule
+            .permissions
+            .iter()
+            .all(|permission| (self.has_permission)(&context.state, permission));
+        if permitted {
+            GuardOutcome::Allow
+        } else {
+            GuardOutcome::Redirect(self.forbidden_route.clone())
+        }
+    }
+}
+
+type IsActiveFn<State> = dyn Fn(&State) -> bool;
+
+/// A [RouteGuard] for a global maintenance mode: while the flag read
+/// from the state is set, every navigation is redirected to the
+/// maintenance route, except to the routes on the allowlist (e.g. a
+/// status page, or login).
+///
+/// Configured with
+/// [with_maintenance_mode()](crate::RouteMiddleware::with_maintenance_mode),
+/// it is run before all the other guards, and also follows the flag
+/// itself: when an action sets it, the current route is replaced with
+/// the maintenance route, and when an action clears it while the
+/// maintenance route is displayed, it is replaced with the route which
+/// was redirected (the intended route).
+pub struct MaintenanceGuard<R, State> {
+    is_active: Box<IsActiveFn<State>>,
+    maintenance_route: R,
+    allowlist: Vec<Box<MatcherFn<R>>>,
+    intended: RefCell<Option<R>>,
+}
+
+impl<R, State> MaintenanceGuard<R, State>
+where
+    R: SwitchRoute,
+{
+    /// Create a new [MaintenanceGuard], where `is_active` reads the
+    /// maintenance flag from the state.
+    pub fn new<F>(is_active: F, maintenance_route: R) -> Self
+    where
+        F: Fn(&State) -> bool + 'static,
+    {
+        Self {
+            is_active: Box::new(is_active),
+            maintenance_route,
+            allowlist: Vec::new(),
+            intended: RefCell::new(None),
+        }
+    }
+
+    /// Allow navigating to the routes accepted by `matcher` during
+    /// maintenance.
+    pub fn allow<F>(mut self, matcher: F) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.allowlist.push(Box::new(matcher));
+        self
+    }
+
+    fn is_allowed(&self, route: &R) -> bool {
+        route.path() == self.maintenance_route.path()
+            || self.allowlist.iter().any(|matcher| matcher(route))
+    }
+
+    /// The maintenance route, if navigating to `target` is to be
+    /// redirected to it in `state`, remembering `target` as the
+    /// intended route.
+    pub(crate) fn redirect(&self, state: &State, target: &R) -> Option<R> {
+        if !(self.is_active)(state) || self.is_allowed(target) {
+            return None;
+        }
+        self.intended.replace(Some(target.clone()));
+        Some(self.maintenance_route.clone())
+    }
+
+    /// The route to replace the `current` route with, if reducing an
+    /// action from the `previous` state has set or cleared the flag.
+    pub(crate) fn transition(&self, previous: &State, state: &State, current: &R) -> Option<R> {
+        match ((self.is_active)(previous), (self.is_active)(state)) {
+            (false, true) => self.redirect(state, current),
+            (true, false) if current.path() == self.maintenance_route.path() => {
+                self.intended.take()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<R, State> RouteGuard<R, State> for MaintenanceGuard<R, State>
+where
+    R: SwitchRoute,
+{
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R> {
+        match self.redirect(&context.state, context.target) {
+            Some(route) => GuardOutcome::Redirect(route),
+            None => GuardOutcome::Allow,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_kit::{TestEvent, TestKit, TestRoute, TestState},
+        RouteStore,
+    };
+    use std::cell::Cell;
+
+    /// A kit whose guard blocks `/admin` while `locked`, recording the
+    /// kinds of the navigations it checks.
+    fn kit(locked: Rc<Cell<bool>>, kinds: Rc<RefCell<Vec<NavigationKind>>>) -> TestKit {
+        TestKit::with_middleware("/", move |middleware| {
+            middleware.with_guard(move |context: &GuardContext<'_, TestRoute, TestState>| {
+                if context.target.0 != "/admin" {
+                    return GuardOutcome::Allow;
+                }
+                kinds.borrow_mut().push(context.kind);
+                if locked.get() {
+                    GuardOutcome::Block
+                } else {
+                    GuardOutcome::Allow
+                }
+            })
+        })
+    }
+
+    #[test]
+    fn retry_reruns_the_blocked_navigation() {
+        let locked = Rc::new(Cell::new(true));
+        let kinds = Rc::new(RefCell::new(Vec::new()));
+        let kit = kit(locked.clone(), kinds.clone());
+
+        kit.navigate("/admin");
+        kit.assert_route("/");
+        assert_eq!(kit.take_events(), vec![TestEvent::NavigationBlocked]);
+
+        locked.set(false);
+        kit.store.retry_last_navigation();
+        kit.assert_route("/admin");
+        assert_eq!(kit.history(), vec!["/", "/admin"]);
+        assert_eq!(
+            *kinds.borrow(),
+            vec![NavigationKind::Push, NavigationKind::Push]
+        );
+
+        // The navigation is only retried once.
+        kit.navigate("/");
+        kit.store.retry_last_navigation();
+        kit.assert_route("/");
+    }
+
+    #[test]
+    fn retry_keeps_the_kind_of_a_browser_navigation() {
+        let locked = Rc::new(Cell::new(true));
+        let kinds = Rc::new(RefCell::new(Vec::new()));
+        let kit = kit(locked.clone(), kinds.clone());
+
+        kit.browser_navigate("/admin");
+        // The URL is restored to the route of the state.
+        kit.assert_route("/");
+
+        locked.set(false);
+        kit.store.retry_last_navigation();
+        kit.assert_route("/admin");
+        assert_eq!(
+            *kinds.borrow(),
+            vec![NavigationKind::External, NavigationKind::External]
+        );
+    }
+
+    #[test]
+    fn retry_without_a_failed_navigation_does_nothing() {
+        let kinds = Rc::new(RefCell::new(Vec::new()));
+        let kit = kit(Rc::new(Cell::new(false)), kinds.clone());
+        kit.navigate("/users");
+        kit.take_events();
+
+        kit.store.retry_last_navigation();
+        kit.assert_route("/users");
+        assert_eq!(kit.history(), vec!["/", "/users"]);
+        assert!(kit.take_events().is_empty());
+        assert!(kinds.borrow().is_empty());
+    }
+}
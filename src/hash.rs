@@ -0,0 +1,10 @@
+/// 64 bit FNV-1a hash, used where a small, stable (across builds and
+/// platforms) hash is required.
+pub(crate) fn fnv1a(input: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in input.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
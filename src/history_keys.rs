@@ -0,0 +1,76 @@
+//! Keys identifying the browser's history entries, so that the direction
+//! and distance of a popstate navigation can be told apart, see
+//! [RouteMiddleware::with_history_keys()](crate::RouteMiddleware::with_history_keys).
+//!
+//! A unique key is stored in the `history.state` of each entry pushed by
+//! the middleware. The keys of the entries pushed since the page was
+//! loaded are kept in order, so when a popstate arrives, the position of
+//! its key gives the number of entries traversed.
+//!
+//! Keys carry the [format version](crate::storage::versioned()), keys of
+//! other versions are treated as unknown entries.
+
+use crate::storage;
+use std::cell::{Cell, RefCell};
+
+pub(crate) struct HistoryKeys {
+    /// Distinguishes the keys of this page load from the keys of
+    /// previous page loads, which remain in the history.
+    session: String,
+    next: Cell<u64>,
+    /// The keys of the known entries, oldest first.
+    entries: RefCell<Vec<String>>,
+    index: Cell<usize>,
+}
+
+impl HistoryKeys {
+    /// Create a new [HistoryKeys], with a key for the current entry.
+    pub(crate) fn new(session: String) -> Self {
+        let keys = Self {
+            session,
+            next: Cell::new(0),
+            entries: RefCell::new(Vec::new()),
+            index: Cell::new(0),
+        };
+        let key = keys.new_key();
+        keys.entries.borrow_mut().push(key);
+        keys
+    }
+
+    fn new_key(&self) -> String {
+        let next = self.next.get();
+        self.next.set(next + 1);
+        storage::versioned(&format!("{}:{}", self.session, next))
+    }
+
+    /// The key of the current entry.
+    pub(crate) fn current(&self) -> String {
+        self.entries.borrow()[self.index.get()].clone()
+    }
+
+    /// Record a pushed entry, discarding the entries forward of the
+    /// current one, returns its key.
+    pub(crate) fn push(&self) -> String {
+        let key = self.new_key();
+        let index = self.index.get() + 1;
+        let mut entries = self.entries.borrow_mut();
+        entries.truncate(index);
+        entries.push(key.clone());
+        self.index.set(index);
+        key
+    }
+
+    /// Record a traversal to the entry with `key`, returns the number of
+    /// entries traversed (negative when going back), or `None` if the
+    /// entry is unknown (e.g. it was pushed before the page was loaded).
+    pub(crate) fn traverse(&self, key: &str) -> Option<isize> {
+        let position = self
+            .entries
+            .borrow()
+            .iter()
+            .position(|entry| entry == key)?;
+        let delta = position as isize - self.index.get() as isize;
+        self.index.set(position);
+        Some(delta)
+    }
+}
@@ -0,0 +1,75 @@
+//! Generation of `href` values for routes, taking into account where
+//! the application is deployed.
+
+use switch_router::SwitchRoute;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Configuration for turning routes into `href` values suitable for
+/// anchors and `window.open()`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Href {
+    base_path: String,
+    hash_mode: bool,
+}
+
+impl Href {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The path prefix the application is deployed under, e.g. `/app`.
+    pub fn with_base_path<S: Into<String>>(mut self, base_path: S) -> Self {
+        self.base_path = base_path.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Whether routes are stored in the URL fragment (`/app/#/users`)
+    /// rather than the path (`/app/users`).
+    pub fn with_hash_mode(mut self, hash_mode: bool) -> Self {
+        self.hash_mode = hash_mode;
+        self
+    }
+
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    pub fn hash_mode(&self) -> bool {
+        self.hash_mode
+    }
+
+    /// Generate the `href` for the route with the specified `path`.
+    pub fn href_for_path(&self, path: &str) -> String {
+        if self.hash_mode {
+            format!("{}/#{}", self.base_path, path)
+        } else {
+            format!("{}{}", self.base_path, path)
+        }
+    }
+
+    /// The route path of an `href` generated by
+    /// [href_for_path()](Self::href_for_path) (without the origin), or
+    /// `None` if it is outside of the base path.
+    pub fn path_for_href(&self, href: &str) -> Option<String> {
+        let rest = href.strip_prefix(self.base_path.as_str())?;
+        let path = if self.hash_mode {
+            rest.trim_start_matches('/').strip_prefix('#')?
+        } else {
+            rest
+        };
+        match path {
+            "" => Some("/".to_string()),
+            path if path.starts_with('/') => Some(path.to_string()),
+            path if path.starts_with('?') || path.starts_with('#') => Some(format!("/{}", path)),
+            _ => None,
+        }
+    }
+
+    /// Generate the `href` for the specified `route`.
+    pub fn href<R: SwitchRoute>(&self, route: &R) -> String {
+        self.href_for_path(&route.path())
+    }
+}
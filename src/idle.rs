@@ -0,0 +1,137 @@
+//! Locking the application after a period of inactivity, e.g. for
+//! banking or medical applications which must not stay open on an
+//! unattended screen.
+//!
+//! When the session idles out, the middleware (configured with
+//! [with_idle_timeout()](crate::RouteMiddleware::with_idle_timeout))
+//! replaces the current route with the lock route, remembering the
+//! interrupted (intended) route, and dispatches the configured
+//! [action](IdleTimeout::with_action) (e.g. to clear sensitive state).
+//! While locked, every navigation is redirected to the lock route, so
+//! that the interrupted page can't be reached with the back button.
+//! Once the user has unlocked the session, the application dispatches
+//! [RouteAction::UnlockSession](crate::RouteAction::UnlockSession) (see
+//! [RouteStore::unlock_session()](crate::RouteStore::unlock_session)),
+//! and the intended route replaces the lock route.
+//!
+//! With the `web` feature, the middleware tracks the activity events on
+//! the window itself, and dispatches
+//! [RouteAction::SessionIdle](crate::RouteAction::SessionIdle) once
+//! there has been none for the timeout. Elsewhere (e.g. in a desktop
+//! shell which knows about system-wide inactivity) the application
+//! dispatches it with
+//! [RouteStore::session_idle()](crate::RouteStore::session_idle).
+//!
+//! ```ignore
+//! let middleware = RouteMiddleware::new(route_service, store.clone())
+//!     .with_idle_timeout(
+//!         IdleTimeout::new(AppRoute::Locked, 15 * 60 * 1000)
+//!             .with_action(AppAction::ClearAccountDetails),
+//!     );
+//!
+//! // Once the user has entered their PIN:
+//! store.unlock_session();
+//! ```
+
+use crate::logging::debug;
+use std::cell::RefCell;
+use switch_router::SwitchRoute;
+
+/// The events on the window which count as activity by default.
+pub const DEFAULT_ACTIVITY_EVENTS: &[&str] = &[
+    "pointerdown",
+    "pointermove",
+    "keydown",
+    "wheel",
+    "touchstart",
+];
+
+/// The configuration and state of the idle timeout, see the
+/// [module documentation](self).
+pub struct IdleTimeout<R, Action> {
+    lock_route: R,
+    timeout_ms: u32,
+    activity_events: Vec<String>,
+    action: Option<Action>,
+    /// The route which was interrupted, while locked.
+    intended: RefCell<Option<R>>,
+}
+
+impl<R, Action> IdleTimeout<R, Action>
+where
+    R: SwitchRoute,
+{
+    /// Create a new [IdleTimeout], locking the session by navigating to
+    /// `lock_route` after `timeout_ms` without activity.
+    pub fn new(lock_route: R, timeout_ms: u32) -> Self {
+        Self {
+            lock_route,
+            timeout_ms,
+            activity_events: DEFAULT_ACTIVITY_EVENTS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            action: None,
+            intended: RefCell::new(None),
+        }
+    }
+
+    /// The events on the window which count as activity, instead of
+    /// [DEFAULT_ACTIVITY_EVENTS].
+    pub fn with_activity_events<I, S>(mut self, events: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.activity_events = events.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Dispatch `action` when the session is locked.
+    pub fn with_action(mut self, action: Action) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+
+    pub fn activity_events(&self) -> &[String] {
+        &self.activity_events
+    }
+
+    pub fn action(&self) -> Option<&Action> {
+        self.action.as_ref()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.intended.borrow().is_some()
+    }
+
+    /// Lock the session, interrupting the `current` route. Returns the
+    /// lock route, or `None` if the session is already locked.
+    pub(crate) fn lock(&self, current: &R) -> Option<R> {
+        if self.is_locked() {
+            return None;
+        }
+        debug!("Session idle, locking {}", current.path());
+        self.intended.replace(Some(current.clone()));
+        Some(self.lock_route.clone())
+    }
+
+    /// The lock route, if navigating to `target` is to be redirected to
+    /// it while the session is locked.
+    pub(crate) fn redirect(&self, target: &R) -> Option<R> {
+        if self.is_locked() && target.path() != self.lock_route.path() {
+            Some(self.lock_route.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Unlock the session, returning the interrupted route to resume.
+    pub(crate) fn unlock(&self) -> Option<R> {
+        self.intended.take()
+    }
+}
@@ -0,0 +1,161 @@
+//! Interception of navigations, for example to protect a form with
+//! unsaved changes from being navigated away from.
+//!
+//! An interceptor returning [Interception::NeedsConfirmation] parks the
+//! navigation, and
+//! [RouteEvent::confirmation_required()](crate::RouteEvent::confirmation_required)
+//! is emitted so the application can ask the user with a dialog. Its
+//! answer is passed to
+//! [RouteStore::confirm_pending_navigation()](crate::RouteStore::confirm_pending_navigation),
+//! which either performs the parked navigation, or cancels it, restoring
+//! the URL if the navigation came from the browser. Another navigation
+//! started in the meantime discards the parked one.
+
+use crate::RouteAction;
+
+/// The decision of a [NavigationInterceptor].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Interception {
+    /// Allow the navigation to proceed.
+    Proceed,
+    /// Block the navigation, it won't be performed or reduced.
+    Block,
+    /// Park the navigation until the user has confirmed it, the payload
+    /// (e.g. the message of the dialog) is passed to
+    /// [RouteEvent::confirmation_required()](crate::RouteEvent::confirmation_required).
+    NeedsConfirmation(String),
+}
+
+/// Decides whether an outgoing navigation (`ChangeRoute`, `Back`,
+/// `ExternalNavigate`), or a navigation from the browser
+/// (`BrowserChangeRoute`, other than the initial route), may proceed,
+/// given the current `State`.
+pub trait NavigationInterceptor<R, State> {
+    fn intercept(&self, state: &State, action: &RouteAction<R>) -> Interception;
+}
+
+impl<R, State, F> NavigationInterceptor<R, State> for F
+where
+    F: Fn(&State, &RouteAction<R>) -> Interception,
+{
+    fn intercept(&self, state: &State, action: &RouteAction<R>) -> Interception {
+        (self)(state, action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        navigation::NavigationKind,
+        test_kit::{TestAction, TestEvent, TestKit, TestRoute, TestState},
+        RouteStore,
+    };
+    use reactive_state::{
+        middleware::{Middleware, ReduceFn, ReduceMiddlewareResult},
+        Store,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Records the route actions reaching the reducer.
+    struct Recorder(Rc<RefCell<Vec<RouteAction<TestRoute>>>>);
+
+    impl Middleware<TestState, TestAction, TestEvent, ()> for Recorder {
+        fn on_reduce(
+            &self,
+            store: &Store<TestState, TestAction, TestEvent, ()>,
+            action: Option<&TestAction>,
+            reduce: ReduceFn<TestState, TestAction, TestEvent, ()>,
+        ) -> ReduceMiddlewareResult<TestEvent, ()> {
+            if let Some(TestAction::Route(action)) = action {
+                self.0.borrow_mut().push(action.clone());
+            }
+            reduce(store, action)
+        }
+    }
+
+    /// A kit starting on a form with unsaved changes, where navigating to
+    /// `/users` needs confirmation.
+    fn kit() -> (TestKit, Rc<RefCell<Vec<RouteAction<TestRoute>>>>) {
+        let kit = TestKit::with_middleware("/form", |middleware| {
+            middleware.with_interceptor(|_state: &TestState, action: &RouteAction<TestRoute>| {
+                match action {
+                    RouteAction::ChangeRoute(route) | RouteAction::BrowserChangeRoute(route, _)
+                        if route.0 == "/users" =>
+                    {
+                        Interception::NeedsConfirmation("unsaved changes".to_string())
+                    }
+                    _ => Interception::Proceed,
+                }
+            })
+        });
+        let reduced = Rc::new(RefCell::new(Vec::new()));
+        kit.add_middleware(Recorder(reduced.clone()));
+        (kit, reduced)
+    }
+
+    #[test]
+    fn parked_navigations_are_not_committed() {
+        let (kit, reduced) = kit();
+        kit.navigate("/users");
+        kit.assert_route("/form");
+        assert_eq!(kit.history(), vec!["/form"]);
+        assert_eq!(kit.take_events(), vec![TestEvent::ConfirmationRequired]);
+        assert!(reduced.borrow().is_empty());
+    }
+
+    #[test]
+    fn confirming_commits_the_parked_navigation() {
+        let (kit, reduced) = kit();
+        kit.navigate("/users");
+        kit.store.confirm_pending_navigation(true);
+        kit.assert_route("/users");
+        assert_eq!(kit.history(), vec!["/form", "/users"]);
+        assert_eq!(
+            *reduced.borrow(),
+            vec![RouteAction::ChangeRoute(TestRoute::new("/users"))]
+        );
+
+        // A parked browser navigation keeps its kind.
+        let (kit, reduced) = self::kit();
+        kit.browser_navigate("/users");
+        assert_eq!(kit.route(), TestRoute::new("/form"));
+        kit.store.confirm_pending_navigation(true);
+        kit.assert_route("/users");
+        assert_eq!(
+            *reduced.borrow(),
+            vec![RouteAction::BrowserChangeRoute(
+                TestRoute::new("/users"),
+                NavigationKind::External
+            )]
+        );
+    }
+
+    #[test]
+    fn cancelling_discards_the_parked_navigation() {
+        let (kit, reduced) = kit();
+        kit.browser_navigate("/users");
+        kit.store.confirm_pending_navigation(false);
+        // The URL is restored to the route of the state.
+        kit.assert_route("/form");
+
+        kit.store.confirm_pending_navigation(true);
+        kit.assert_route("/form");
+        assert!(reduced.borrow().is_empty());
+    }
+
+    #[test]
+    fn another_navigation_discards_the_parked_navigation() {
+        let (kit, reduced) = kit();
+        kit.navigate("/users");
+        kit.navigate("/settings");
+        kit.assert_route("/settings");
+
+        kit.store.confirm_pending_navigation(true);
+        kit.assert_route("/settings");
+        assert_eq!(
+            *reduced.borrow(),
+            vec![RouteAction::ChangeRoute(TestRoute::new("/settings"))]
+        );
+    }
+}
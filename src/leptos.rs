@@ -0,0 +1,81 @@
+//! An adapter for [Leptos](https://leptos.dev/) applications routed by
+//! the [RouteMiddleware](crate::RouteMiddleware), requires the `leptos`
+//! feature.
+//!
+//! [RouteSignal::from_store()] exposes the current route of the store as
+//! a signal, updated on the store's
+//! [RouteEvent::route_changed()](crate::RouteEvent::route_changed) event,
+//! so views derived from it update when navigating:
+//!
+//! ```ignore
+//! let router = RouteSignal::from_store(&store);
+//! let route = router.route();
+//! view! {
+//!     <button on:click=move |_| router.navigate(AppRoute::Settings)>"Settings"</button>
+//!     {move || render_route(route.get())}
+//! }
+//! ```
+
+use crate::{route_dispatcher, RouteAction, RouteEvent, RouteState};
+use ::leptos::{create_signal, on_cleanup, ReadSignal, SignalSet};
+use reactive_state::StoreRef;
+use std::{cell::Cell, hash::Hash, rc::Rc};
+use switch_router::SwitchRoute;
+
+type DispatchFn<R> = dyn Fn(RouteAction<R>);
+
+/// The current route of a store as a signal, and functions to navigate.
+#[derive(Clone)]
+pub struct RouteSignal<R: 'static> {
+    route: ReadSignal<R>,
+    dispatch: Rc<DispatchFn<R>>,
+}
+
+impl<R> RouteSignal<R>
+where
+    R: SwitchRoute + 'static,
+{
+    /// Create a [RouteSignal] following the route of the `store`. The
+    /// store is subscribed to until the current reactive owner (e.g. the
+    /// component calling this) is cleaned up.
+    pub fn from_store<State, Action, Event, Effect>(
+        store: &StoreRef<State, Action, Event, Effect>,
+    ) -> Self
+    where
+        State: RouteState<R> + 'static,
+        Action: From<RouteAction<R>> + 'static,
+        Event: RouteEvent<R> + Clone + Hash + Eq + 'static,
+        Effect: 'static,
+    {
+        let (route, set_route) = create_signal(store.state().get_route().clone());
+        let listener =
+            reactive_state::Callback::new(move |state: Rc<State>, _event: Option<Event>| {
+                set_route.set(state.get_route().clone())
+            });
+        store.subscribe_event(&listener, Event::route_changed());
+        on_cleanup(move || drop(listener));
+
+        Self {
+            route,
+            dispatch: Rc::new(route_dispatcher(
+                store.downgrade(),
+                Rc::new(Cell::new(None)),
+                Action::from,
+            )),
+        }
+    }
+
+    /// The signal of the current route.
+    pub fn route(&self) -> ReadSignal<R> {
+        self.route
+    }
+
+    /// Navigate to `route`, see [RouteAction::ChangeRoute].
+    pub fn navigate(&self, route: R) {
+        self.dispatch(RouteAction::ChangeRoute(route));
+    }
+
+    pub fn dispatch(&self, action: RouteAction<R>) {
+        (self.dispatch)(action)
+    }
+}
@@ -1,169 +1,3453 @@
-use log::error;
 use reactive_state::{
-    middleware::{Middleware, ReduceFn},
-    Store, StoreRef,
+    middleware::{Middleware, ReduceFn, ReduceMiddlewareResult},
+    Store, StoreRef, WeakStoreRef,
 };
 use std::{
-    cell::RefCell,
+    any::Any,
+    borrow::Cow,
+    cell::{BorrowError, Cell, RefCell},
+    collections::VecDeque,
     fmt::{Debug, Display},
     hash::Hash,
     marker::PhantomData,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
 };
 
+#[cfg(feature = "audit")]
+use audit::{AuditLog, AuditOutcome};
+use batch::{BatchOrder, SplitBatchFn};
+use canonical::TrailingSlash;
+use chunks::{ChunkLoader, RouteEffect};
+use command::{CustomCommand, RouteCommand, RouteCommands};
+#[cfg(feature = "devtools")]
+use devtools::TimeTravelFn;
+#[cfg(feature = "diagnostics")]
+use diagnostics::{Diagnostics, PendingNavigation};
+#[cfg(feature = "web")]
+use embed::{EmbedConfig, EmbedMessage};
+use equality::RouteEquality;
+use error_route::ErrorInfo;
+#[cfg(feature = "experiments")]
+use experiments::Experiments;
+#[cfg(feature = "federation")]
+use federation::{Delegations, FederationChild};
+use guard::{GuardContext, GuardOutcome, MaintenanceGuard, RouteGuard};
+#[cfg(feature = "web")]
+use history_keys::HistoryKeys;
+use href::Href;
+use idle::IdleTimeout;
+use interceptor::{Interception, NavigationInterceptor};
+use lifecycle::RouteLifecycle;
+use live_query::LiveQuery;
+#[cfg(feature = "diagnostics")]
+use logging::warning;
+use logging::{debug, error, ErrorSink, LogErrors};
+use mapper::ActionMapper;
+use masking::RouteMasks;
+use memory::MemoryRouteService;
+use metadata::{RouteMetadata, RouteRegistry};
+use migrations::Migrations;
+use navigation::NavigationKind;
+#[cfg(feature = "oauth")]
+use oauth::OAuthState;
+use offline::OfflineRouting;
+#[cfg(feature = "prefetch")]
+use prefetch::Prefetch;
+use query::{Pagination, QueryParams, QueryValidation};
+#[cfg(feature = "error-reporting")]
+use reporting::{ErrorReportingHook, NavigationReport};
+#[cfg(feature = "route-table")]
+use route_table::RouteTable;
+use router::{NamedRouteState, RouterId};
+use scope::{RouteScope, ScopeTransition};
+#[cfg(feature = "scroll")]
+use scroll::{ScrollPosition, ScrollPositions};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use services::Services;
+use share::{LinkError, ShareLink};
+#[cfg(feature = "short-routes")]
+use short_routes::ShortRoutes;
+#[cfg(feature = "web")]
+use shortcuts::KeyBindings;
+#[cfg(feature = "signing")]
+use signing::{SignatureFailure, SignedRoutes};
+use snapshot::{NavigationStatus, RouterSnapshot};
+use stack::MiddlewareStack;
+use subscription::RouteSubscription;
 use switch_router::{SwitchRoute, SwitchRouteService};
+#[cfg(feature = "tauri")]
+use tauri::WindowRouting;
+use undo::NavigationMarker;
+use update::ApplyUpdateFn;
+use url_length::UrlLengthGuard;
+
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod batch;
+pub mod bridge;
+pub mod canonical;
+pub mod chunks;
+pub mod command;
+#[cfg(feature = "devtools")]
+pub mod devtools;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod embed;
+pub mod encoding;
+pub mod equality;
+pub mod error_route;
+pub mod event;
+#[cfg(feature = "experiments")]
+pub mod experiments;
+#[cfg(feature = "federation")]
+pub mod federation;
+pub mod guard;
+mod hash;
+#[cfg(feature = "web")]
+mod history_keys;
+pub mod href;
+pub mod idle;
+pub mod interceptor;
+#[cfg(feature = "leptos")]
+pub mod leptos;
+pub mod lifecycle;
+pub mod live_query;
+pub mod logging;
+pub mod mapper;
+pub mod masking;
+pub mod memory;
+pub mod metadata;
+pub mod migrations;
+pub mod navigation;
+#[cfg(feature = "oauth")]
+pub mod oauth;
+pub mod offline;
+pub mod params;
+#[cfg(feature = "prefetch")]
+pub mod prefetch;
+pub mod query;
+#[cfg(feature = "error-reporting")]
+pub mod reporting;
+#[cfg(feature = "route-table")]
+pub mod route_table;
+pub mod router;
+pub mod scope;
+#[cfg(feature = "scroll")]
+pub mod scroll;
+pub mod service_object;
+pub mod services;
+pub mod share;
+pub mod shared;
+#[cfg(feature = "short-routes")]
+pub mod short_routes;
+pub mod shortcuts;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod slug;
+pub mod snapshot;
+pub mod stack;
+pub mod storage;
+pub mod subscription;
+#[cfg(feature = "table-view")]
+pub mod table_view;
+#[cfg(feature = "tauri")]
+pub mod tauri;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_kit;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod undo;
+pub mod update;
+pub mod url_length;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "wizard")]
+pub mod wizard;
+#[cfg(feature = "worker")]
+pub mod worker;
+#[cfg(feature = "yew")]
+pub mod yew;
+
+#[cfg(feature = "derive")]
+pub use switch_router_middleware_derive::RouteEvent;
 
 pub struct RouteMiddleware<R, RS, State, Action, Event, Effect> {
     pub route_service: RefCell<RS>,
     /// The callback to the SwitchRouteService. When this gets dropped
     /// this listener will be removed from the route service.
-    _callback: switch_router::Callback<R>,
-    state_type: PhantomData<State>,
-    action_type: PhantomData<Action>,
-    event_type: PhantomData<Event>,
-    effect_type: PhantomData<Effect>,
+    callback: switch_router::Callback<R>,
+    store: WeakStoreRef<State, Action, Event, Effect>,
+    /// Shared with the callback, see [RouteMiddleware::with_router_id()].
+    router_id: Rc<Cell<Option<RouterId>>>,
+    named_route: Option<(RouterId, NamedRouteFn<State, R>)>,
+    #[cfg(feature = "web")]
+    key_listener: Option<web::KeyListener>,
+    #[cfg(feature = "web")]
+    embed: Option<(EmbedConfig, web::MessageListener)>,
+    /// Route service operations requested while the route service was
+    /// borrowed, see [RouteMiddleware::service_op()].
+    pending_service_ops: RefCell<VecDeque<ServiceOp<R>>>,
+    url_length_guard: Option<UrlLengthGuard<R>>,
+    normalize_paths: bool,
+    trailing_slash: TrailingSlash,
+    lowercase_paths: bool,
+    migrations: Migrations<R>,
+    #[cfg(feature = "signing")]
+    signed_routes: Option<SignedRoutes<R>>,
+    #[cfg(feature = "oauth")]
+    oauth_state: Option<OAuthState<R>>,
+    interceptor: Option<Box<dyn NavigationInterceptor<R, State>>>,
+    href: Href,
+    registry: RouteRegistry<R>,
+    guards: Vec<Box<dyn RouteGuard<R, State>>>,
+    maintenance: Option<MaintenanceGuard<R, State>>,
+    offline: Option<OfflineRouting<R>>,
+    #[cfg(feature = "web")]
+    connectivity_listener: Option<web::ConnectivityListener>,
+    idle: Option<IdleTimeout<R, Action>>,
+    #[cfg(feature = "web")]
+    idle_listener: Option<web::IdleListener>,
+    #[cfg(feature = "experiments")]
+    experiments: Option<Experiments<R, State>>,
+    masks: RouteMasks<R>,
+    #[cfg(feature = "scroll")]
+    scroll_positions: ScrollPositions,
+    services: Services,
+    lifecycle: Rc<RouteLifecycle<R, Action>>,
+    query_validation: Option<QueryValidation<R, Action>>,
+    pagination: Pagination,
+    live_query: LiveQuery<R>,
+    /// Converts between the store's actions and route actions.
+    actions: ActionMapper<R, Action>,
+    share_link: Option<ShareLink>,
+    #[cfg(feature = "short-routes")]
+    short_routes: Option<ShortRoutes<R>>,
+    #[cfg(feature = "prefetch")]
+    prefetch: Option<Rc<Prefetch<R>>>,
+    #[cfg(feature = "federation")]
+    delegations: Delegations,
+    #[cfg(feature = "tauri")]
+    window_routing: Option<WindowRouting<R>>,
+    chunk_loading: Option<(Rc<ChunkLoader<R>>, IntoEffectFn<Effect>)>,
+    /// Whether the initial route is being resolved, see
+    /// [RouteAction::ResolveInitialRoute].
+    resolving_initial: Cell<bool>,
+    parse_fallback: Option<Box<ParseFallbackFn<R>>>,
+    #[cfg(feature = "route-table")]
+    route_table: Option<RouteTable<R>>,
+    route_scopes: Option<(InScopeFn<R>, Vec<String>)>,
+    subscription: RouteSubscription<R>,
+    equality: RouteEquality<R>,
+    error_route: Option<Box<ErrorRouteFn<R>>>,
+    /// The route replaced by the error route, see [error_route].
+    failed_route: RefCell<Option<R>>,
+    /// Set when a guard blocks the navigation being reduced.
+    navigation_failed: Cell<bool>,
+    /// Set when a user-provided hook panics while reducing a navigation,
+    /// see [RouteMiddleware::catch_hook_panic()].
+    hook_panicked: Cell<bool>,
+    /// See [RouteMiddleware::last_failed_navigation()].
+    last_failed_navigation: RefCell<Option<RouteAction<R>>>,
+    /// The navigation parked by [Interception::NeedsConfirmation].
+    pending_confirmation: RefCell<Option<RouteAction<R>>>,
+    /// Set when performing a confirmed navigation, so it isn't
+    /// intercepted again.
+    navigation_confirmed: Cell<bool>,
+    /// The number of history entries left to go back, and the route to
+    /// replace the entry arrived at with, see
+    /// [RouteAction::ReplaceStackTop].
+    stack_compaction: RefCell<Option<(usize, R)>>,
+    #[cfg(feature = "web")]
+    canonical_url_rewrite: bool,
+    /// The path of the last canonical URL rewrite, see
+    /// [RouteMiddleware::with_canonical_url_rewrite()].
+    #[cfg(feature = "web")]
+    last_canonical_rewrite: RefCell<Option<String>>,
+    #[cfg(feature = "web")]
+    history_keys: Option<HistoryKeys>,
+    #[cfg(feature = "web")]
+    chunk_timeout_ms: u32,
+    #[cfg(feature = "web")]
+    performance_marks: bool,
+    #[cfg(feature = "error-reporting")]
+    error_reporting: Option<Box<dyn ErrorReportingHook>>,
+    navigation_marker: NavigationMarker,
+    commands: RouteCommands<R, State>,
+    strict: bool,
+    error_sink: Box<dyn ErrorSink>,
+    #[cfg(feature = "devtools")]
+    time_travel: Option<Box<TimeTravelFn<Action>>>,
+    batches: Option<(Box<SplitBatchFn<Action>>, BatchOrder)>,
+    apply_update: Option<Box<ApplyUpdateFn<R>>>,
+    update_pending: Cell<bool>,
+    callback_registered: Cell<bool>,
+    last_error: RefCell<Option<String>>,
+    /// The number of actions between self-checks, and the number reduced
+    /// since the last one, see [RouteMiddleware::with_self_check()].
+    #[cfg(feature = "diagnostics")]
+    self_check: Option<(u32, Cell<u32>)>,
+    #[cfg(feature = "audit")]
+    audit_log: Option<AuditLog>,
+    /// See [RouteMiddleware::with_initial_route()].
+    initial_route: Option<R>,
+    /// The history navigated instead of the route service's, see
+    /// [RouteMiddleware::with_memory_navigation()] and
+    /// [RouteAction::SetUrlSync].
+    memory_history: RefCell<Option<MemoryRouteService<R>>>,
+    /// Whether memory navigation was configured with
+    /// [RouteMiddleware::with_memory_navigation()], rather than enabled
+    /// with [RouteAction::SetUrlSync].
+    memory_navigation: bool,
+    store_types: StoreTypes<State, Action, Event, Effect>,
+}
+
+/// Marker for the type parameters of the [Store] which a
+/// [RouteMiddleware] is attached to.
+type StoreTypes<State, Action, Event, Effect> = PhantomData<fn() -> (State, Action, Event, Effect)>;
+
+/// A function dispatching route actions to the `store` (for as long as
+/// it is alive), wrapped for the router if it has an id.
+fn route_dispatcher<R, State, Action, Event, Effect, W>(
+    store: WeakStoreRef<State, Action, Event, Effect>,
+    router_id: Rc<Cell<Option<RouterId>>>,
+    wrap: W,
+) -> impl Fn(RouteAction<R>)
+where
+    R: SwitchRoute,
+    Event: Clone + Hash + Eq,
+    W: Fn(RouteAction<R>) -> Action,
+{
+    move |action| match store.upgrade() {
+        Some(store) => match router_id.get() {
+            Some(router) => store.dispatch(wrap(action.for_router(router))),
+            None => store.dispatch(wrap(action)),
+        },
+        None => debug!("Ignoring {}, the store has been dropped", action),
+    }
 }
 
-impl<R, RS, State, Action, Event, Effect> RouteMiddleware<R, RS, State, Action, Event, Effect>
-where
-    R: SwitchRoute + 'static,
-    RS: SwitchRouteService<Route = R> + 'static,
-    State: 'static,
-    Action: IsRouteAction<R> + 'static,
-    Event: Clone + Hash + Eq + 'static,
-    Effect: 'static,
-{
-    pub fn new(route_service: RS, store: StoreRef<State, Action, Event, Effect>) -> Self {
-        let router = RefCell::new(route_service);
-        let callback: switch_router::Callback<R> = switch_router::Callback::new(move |route: R| {
-            store.dispatch(RouteAction::BrowserChangeRoute(route));
-        });
+/// Reads the route of a named router from the state, see
+/// [NamedRouteState].
+type NamedRouteFn<State, R> = for<'a> fn(&'a State, RouterId) -> &'a R;
+
+/// Converts the effects emitted by the middleware into the store's
+/// effects, see [RouteMiddleware::with_chunk_loading()].
+type IntoEffectFn<Effect> = fn(RouteEffect) -> Effect;
+
+/// Whether a route belongs to a scope, see
+/// [RouteMiddleware::with_route_scope()].
+type InScopeFn<R> = fn(&R, &str) -> bool;
+
+/// Chooses the route to navigate to when the route service fails to
+/// parse a path, see [RouteMiddleware::with_parse_fallback()].
+type ParseFallbackFn<R> = dyn Fn(&str) -> Option<R>;
+
+/// Constructs the error route for an error, see
+/// [RouteMiddleware::with_error_route()].
+type ErrorRouteFn<R> = dyn Fn(&ErrorInfo) -> R;
+
+/// An operation on the route service.
+enum ServiceOp<R> {
+    Set(R),
+    Replace(R),
+    Back,
+}
+
+impl<R> Display for ServiceOp<R>
+where
+    R: SwitchRoute,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceOp::Set(route) => write!(f, "set the route {}", route.path()),
+            ServiceOp::Replace(route) => write!(f, "replace the route with {}", route.path()),
+            ServiceOp::Back => write!(f, "go back"),
+        }
+    }
+}
+
+impl<R> ServiceOp<R> {
+    fn apply<RS>(self, route_service: &mut RS) -> Option<R>
+    where
+        RS: SwitchRouteService<Route = R>,
+    {
+        match self {
+            ServiceOp::Set(route) => {
+                route_service.set_route(route);
+                None
+            }
+            ServiceOp::Replace(route) => Some(route_service.replace_route(route)),
+            ServiceOp::Back => route_service.back(),
+        }
+    }
+}
+
+/// Builder for a [RouteMiddleware], created with
+/// [RouteMiddleware::for_store()]. All of the type parameters of the
+/// middleware are inferred from the store and the route service.
+pub struct RouteMiddlewareBuilder<State, Action, Event, Effect> {
+    store: StoreRef<State, Action, Event, Effect>,
+}
+
+impl<State, Action, Event, Effect> RouteMiddlewareBuilder<State, Action, Event, Effect>
+where
+    State: 'static,
+    Event: Clone + Hash + Eq + 'static,
+    Effect: 'static,
+{
+    /// Create the [RouteMiddleware] using the specified `route_service`.
+    pub fn with_route_service<R, RS>(
+        self,
+        route_service: RS,
+    ) -> RouteMiddleware<R, RS, State, Action, Event, Effect>
+    where
+        R: SwitchRoute + 'static,
+        RS: SwitchRouteService<Route = R> + 'static,
+        Action: IsRouteAction<R> + 'static,
+    {
+        RouteMiddleware::new(route_service, self.store)
+    }
+}
+
+impl<State, Action, Event, Effect> RouteMiddleware<(), (), State, Action, Event, Effect> {
+    /// Start building a [RouteMiddleware] for `store`:
+    ///
+    /// ```ignore
+    /// let middleware = RouteMiddleware::for_store(store.clone()).with_route_service(service);
+    /// ```
+    pub fn for_store(
+        store: StoreRef<State, Action, Event, Effect>,
+    ) -> RouteMiddlewareBuilder<State, Action, Event, Effect> {
+        RouteMiddlewareBuilder { store }
+    }
+}
+
+impl<R, RS, State, Action, Event, Effect> RouteMiddleware<R, RS, State, Action, Event, Effect>
+where
+    R: SwitchRoute + 'static,
+    RS: SwitchRouteService<Route = R> + 'static,
+    State: 'static,
+    Action: Clone + 'static,
+    Event: Clone + Hash + Eq + 'static,
+    Effect: 'static,
+{
+    pub fn new(route_service: RS, store: StoreRef<State, Action, Event, Effect>) -> Self
+    where
+        Action: IsRouteAction<R>,
+    {
+        Self::new_with_action_mapper(route_service, store, ActionMapper::from_trait())
+    }
+
+    /// Create a new [RouteMiddleware] for a `store` whose action type
+    /// doesn't implement [IsRouteAction], converting its actions with
+    /// `actions` instead, see [mapper].
+    pub fn new_with_action_mapper(
+        route_service: RS,
+        store: StoreRef<State, Action, Event, Effect>,
+        actions: ActionMapper<R, Action>,
+    ) -> Self {
+        let router = RefCell::new(route_service);
+        // Only a weak reference to the store is held by the callback,
+        // otherwise there would be a reference cycle (store -> middleware
+        // -> callback -> store) keeping both alive forever.
+        let weak_store = store.downgrade();
+        let router_id: Rc<Cell<Option<RouterId>>> = Rc::new(Cell::new(None));
+        let dispatch = route_dispatcher(weak_store.clone(), router_id.clone(), actions.wrapper());
+        let callback: switch_router::Callback<R> = switch_router::Callback::new(move |route: R| {
+            dispatch(RouteAction::BrowserChangeRoute(
+                route,
+                NavigationKind::External,
+            ))
+        });
+
+        // FIXME: there is multiple borrow error with this callback
+        let callback_registered = match router.try_borrow_mut() {
+            Ok(mut router_mut) => {
+                router_mut.register_callback(&callback);
+                true
+            }
+            Err(err) => {
+                error!("Unable to register callback {:?}: {}", callback, err);
+                false
+            }
+        };
+
+        Self {
+            route_service: router,
+            callback,
+            store: weak_store,
+            router_id,
+            named_route: None,
+            #[cfg(feature = "web")]
+            key_listener: None,
+            #[cfg(feature = "web")]
+            embed: None,
+            pending_service_ops: RefCell::new(VecDeque::new()),
+            url_length_guard: None,
+            normalize_paths: false,
+            trailing_slash: TrailingSlash::default(),
+            lowercase_paths: false,
+            migrations: Migrations::new(),
+            #[cfg(feature = "signing")]
+            signed_routes: None,
+            #[cfg(feature = "oauth")]
+            oauth_state: None,
+            interceptor: None,
+            href: Href::default(),
+            registry: RouteRegistry::new(),
+            guards: Vec::new(),
+            maintenance: None,
+            offline: None,
+            #[cfg(feature = "web")]
+            connectivity_listener: None,
+            idle: None,
+            #[cfg(feature = "web")]
+            idle_listener: None,
+            #[cfg(feature = "experiments")]
+            experiments: None,
+            masks: RouteMasks::new(),
+            #[cfg(feature = "scroll")]
+            scroll_positions: ScrollPositions::new(),
+            services: Services::new(),
+            lifecycle: Rc::new(RouteLifecycle::new()),
+            query_validation: None,
+            pagination: Pagination::default(),
+            live_query: LiveQuery::new(),
+            actions,
+            share_link: None,
+            #[cfg(feature = "short-routes")]
+            short_routes: None,
+            #[cfg(feature = "prefetch")]
+            prefetch: None,
+            #[cfg(feature = "federation")]
+            delegations: Delegations::new(),
+            #[cfg(feature = "tauri")]
+            window_routing: None,
+            chunk_loading: None,
+            resolving_initial: Cell::new(false),
+            parse_fallback: None,
+            #[cfg(feature = "route-table")]
+            route_table: None,
+            route_scopes: None,
+            subscription: RouteSubscription::new(),
+            equality: RouteEquality::Full,
+            error_route: None,
+            failed_route: RefCell::new(None),
+            navigation_failed: Cell::new(false),
+            hook_panicked: Cell::new(false),
+            last_failed_navigation: RefCell::new(None),
+            pending_confirmation: RefCell::new(None),
+            navigation_confirmed: Cell::new(false),
+            stack_compaction: RefCell::new(None),
+            #[cfg(feature = "web")]
+            canonical_url_rewrite: false,
+            #[cfg(feature = "web")]
+            last_canonical_rewrite: RefCell::new(None),
+            #[cfg(feature = "web")]
+            history_keys: None,
+            #[cfg(feature = "web")]
+            chunk_timeout_ms: chunks::DEFAULT_CHUNK_TIMEOUT_MS,
+            #[cfg(feature = "web")]
+            performance_marks: false,
+            #[cfg(feature = "error-reporting")]
+            error_reporting: None,
+            navigation_marker: NavigationMarker::new(),
+            commands: RouteCommands::new(),
+            strict: false,
+            error_sink: Box::new(LogErrors),
+            #[cfg(feature = "devtools")]
+            time_travel: None,
+            batches: None,
+            apply_update: None,
+            update_pending: Cell::new(false),
+            callback_registered: Cell::new(callback_registered),
+            last_error: RefCell::new(None),
+            #[cfg(feature = "diagnostics")]
+            self_check: None,
+            #[cfg(feature = "audit")]
+            audit_log: None,
+            initial_route: None,
+            memory_history: RefCell::new(None),
+            memory_navigation: false,
+            store_types: PhantomData,
+        }
+    }
+
+    /// Detect routes with URLs longer than the `guard` allows, as
+    /// formatted with the configured [Href]. See [url_length] for more
+    /// details.
+    pub fn with_url_length_guard(mut self, guard: UrlLengthGuard<R>) -> Self {
+        #[cfg(feature = "web")]
+        let guard = match (guard.origin(), web::location_origin()) {
+            (None, Some(origin)) => guard.with_origin(origin),
+            _ => guard,
+        };
+        self.url_length_guard = Some(guard);
+        self
+    }
+
+    /// Normalize the percent-encoding of routes (both those being
+    /// navigated to, and those arriving from the browser) using
+    /// [encoding::normalize_path()]. Disabled by default.
+    pub fn with_path_normalization(mut self, normalize: bool) -> Self {
+        self.normalize_paths = normalize;
+        self
+    }
+
+    /// Enforce the specified [TrailingSlash] policy on routes. Defaults
+    /// to [TrailingSlash::Preserve].
+    pub fn with_trailing_slash(mut self, policy: TrailingSlash) -> Self {
+        self.trailing_slash = policy;
+        self
+    }
+
+    /// Treat paths case-insensitively by lower casing them with
+    /// [canonical::lowercase_path()], so `/About` resolves to the same
+    /// route as `/about`. Disabled by default.
+    pub fn with_case_insensitive_paths(mut self, case_insensitive: bool) -> Self {
+        self.lowercase_paths = case_insensitive;
+        self
+    }
+
+    /// Map legacy paths arriving from the browser onto current routes,
+    /// before they are handled as not found. See [migrations] for more
+    /// details.
+    pub fn with_migrations(mut self, migrations: Migrations<R>) -> Self {
+        self.migrations = migrations;
+        self
+    }
+
+    /// Verify the signature of routes arriving from the browser. See
+    /// [signing] for more details.
+    #[cfg(feature = "signing")]
+    pub fn with_signed_routes(mut self, signed_routes: SignedRoutes<R>) -> Self {
+        self.signed_routes = Some(signed_routes);
+        self
+    }
+
+    /// Validate the OAuth state of callback routes arriving from the
+    /// browser. See [oauth] for more details.
+    #[cfg(feature = "oauth")]
+    pub fn with_oauth_state(mut self, oauth_state: OAuthState<R>) -> Self {
+        self.oauth_state = Some(oauth_state);
+        self
+    }
+
+    /// Generate and stash a new OAuth state, returning `url` with the
+    /// state appended. Returns `None` if the middleware was not
+    /// configured [with_oauth_state()](RouteMiddleware::with_oauth_state).
+    #[cfg(feature = "oauth")]
+    pub fn oauth_authorize_url(&self, url: &str) -> Option<String> {
+        match self.oauth_state.as_ref()?.authorize_url(url) {
+            Ok(url) => Some(url),
+            Err(err) => {
+                error!("Unable to generate OAuth state: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Intercept navigations initiated by the application. See
+    /// [interceptor] for more details.
+    pub fn with_interceptor<I>(mut self, interceptor: I) -> Self
+    where
+        I: NavigationInterceptor<R, State> + 'static,
+    {
+        self.interceptor = Some(Box::new(interceptor));
+        self
+    }
+
+    /// A callback for the route service to report paths which it is
+    /// unable to parse into a route (e.g. malformed escapes, or unknown
+    /// shapes), dispatching a [RouteAction::RouteParseFailed].
+    pub fn parse_failure_callback(&self) -> switch_router::Callback<String> {
+        let dispatch = self.dispatcher();
+        switch_router::Callback::new(move |path: String| {
+            dispatch(RouteAction::RouteParseFailed(path))
+        })
+    }
+
+    /// Configure what counts as the same route, see [equality] for more
+    /// details.
+    pub fn with_route_equality(mut self, equality: RouteEquality<R>) -> Self {
+        self.equality = equality;
+        self
+    }
+
+    /// Declare the error route, constructed for each error by
+    /// `error_route`. See [error_route] for more details.
+    pub fn with_error_route<F>(mut self, error_route: F) -> Self
+    where
+        F: Fn(&ErrorInfo) -> R + 'static,
+    {
+        self.error_route = Some(Box::new(error_route));
+        self
+    }
+
+    /// The route which was replaced by the error route, if any.
+    pub fn failed_route(&self) -> Option<R> {
+        self.failed_route.borrow().clone()
+    }
+
+    /// The last navigation which failed, because it was blocked by a
+    /// guard, the chunk of its route failed to load (or timed out), or
+    /// its route was replaced by the error route. It is re-run with
+    /// [RouteAction::RetryLastNavigation].
+    pub fn last_failed_navigation(&self) -> Option<RouteAction<R>> {
+        self.last_failed_navigation.borrow().clone()
+    }
+
+    /// The navigation waiting to be confirmed, see [interceptor].
+    pub fn pending_confirmation(&self) -> Option<RouteAction<R>> {
+        self.pending_confirmation.borrow().clone()
+    }
+
+    /// When the route service fails to parse a path, replace it with the
+    /// route returned by `fallback` (e.g. a not found route), if any.
+    pub fn with_parse_fallback<F>(mut self, fallback: F) -> Self
+    where
+        F: Fn(&str) -> Option<R> + 'static,
+    {
+        self.parse_fallback = Some(Box::new(fallback));
+        self
+    }
+
+    /// When the route service fails to parse a path, resolve it with the
+    /// `table` before treating it as a failed navigation, see
+    /// [route_table].
+    #[cfg(feature = "route-table")]
+    pub fn with_route_table(mut self, table: RouteTable<R>) -> Self {
+        self.route_table = Some(table);
+        self
+    }
+
+    /// The route of `path` in the route table, if any.
+    #[cfg(feature = "route-table")]
+    fn route_table_route(&self, path: &str) -> Option<R> {
+        self.route_table.as_ref()?.resolve(path)
+    }
+
+    #[cfg(not(feature = "route-table"))]
+    fn route_table_route(&self, _path: &str) -> Option<R> {
+        None
+    }
+
+    /// After reducing a route arriving from the browser whose
+    /// serialization differs from the address bar (e.g. extra slashes,
+    /// or reordered query parameters), replace the address with the
+    /// canonical serialization. To avoid loops with route types whose
+    /// serialization doesn't round trip, a path is never rewritten twice
+    /// in a row. Off by default.
+    #[cfg(feature = "web")]
+    pub fn with_canonical_url_rewrite(mut self, enabled: bool) -> Self {
+        self.canonical_url_rewrite = enabled;
+        self
+    }
+
+    /// Store a unique key in the `history.state` of each entry pushed by
+    /// the middleware, so that popstate navigations can be told apart by
+    /// direction and distance, emitting
+    /// [RouteEvent::history_traversed()]. The browser's route service
+    /// must not use `history.state` itself.
+    #[cfg(feature = "web")]
+    pub fn with_history_keys(mut self) -> Self {
+        let keys = HistoryKeys::new(web::history_session());
+        web::set_history_key(&keys.current());
+        self.history_keys = Some(keys);
+        self
+    }
+
+    /// Configure how `href` values are generated for routes, see
+    /// [RouteMiddleware::href()].
+    pub fn with_href(mut self, href: Href) -> Self {
+        self.href = href;
+        self
+    }
+
+    /// A [RouteContext](yew::RouteContext) for the Yew components,
+    /// following the route of the store and formatting links with the
+    /// configured [Href]. Create it before adding the middleware to the
+    /// store, returns `None` if the store has been dropped. See [yew]
+    /// for more details.
+    #[cfg(feature = "yew")]
+    pub fn route_context(&self) -> Option<yew::RouteContext<R>>
+    where
+        R: 'static,
+        State: RouteState<R> + 'static,
+        Action: From<RouteAction<R>> + 'static,
+        Event: RouteEvent<R> + Clone + Hash + Eq + 'static,
+        Effect: 'static,
+    {
+        let store = self.store.upgrade()?;
+        Some(yew::RouteContext::from_store(&store).with_href(self.href.clone()))
+    }
+
+    /// Generate the `href` for `route`, respecting the configured base
+    /// path and hash mode.
+    pub fn href(&self, route: &R) -> String {
+        self.href.href(route)
+    }
+
+    /// Provide metadata about the application's routes. See [metadata]
+    /// for more details.
+    pub fn with_registry(mut self, registry: RouteRegistry<R>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    pub fn registry(&self) -> &RouteRegistry<R> {
+        &self.registry
+    }
+
+    /// A handle to subscribe to route changes with, see [subscription].
+    pub fn route_subscription(&self) -> RouteSubscription<R> {
+        self.subscription.clone()
+    }
+
+    /// Add a guard to check navigations against. See [guard] for more
+    /// details.
+    pub fn with_guard<G>(mut self, guard: G) -> Self
+    where
+        G: RouteGuard<R, State> + 'static,
+    {
+        self.guards.push(Box::new(guard));
+        self
+    }
+
+    /// Redirect navigations to the maintenance route while maintenance
+    /// mode is active, before running the other guards. See
+    /// [MaintenanceGuard] for more details.
+    pub fn with_maintenance_mode(mut self, maintenance: MaintenanceGuard<R, State>) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Redirect navigations to routes requiring the network to the
+    /// offline route while offline, before running the other guards
+    /// (after maintenance mode). With the `web` feature, the
+    /// connectivity of the browser is followed. See [offline] for more
+    /// details.
+    pub fn with_offline_routing(mut self, offline: OfflineRouting<R>) -> Self {
+        #[cfg(feature = "web")]
+        let offline = {
+            let dispatch = self.dispatcher();
+            self.connectivity_listener = web::ConnectivityListener::new(dispatch);
+            offline.with_online(web::is_online())
+        };
+        self.offline = Some(offline);
+        self
+    }
+
+    /// Lock the session after a period of inactivity by navigating to
+    /// the lock route, redirecting every navigation to it until
+    /// [RouteAction::UnlockSession], before running the other guards.
+    /// With the `web` feature, the activity events on the window are
+    /// followed. See [idle] for more details.
+    pub fn with_idle_timeout(mut self, idle: IdleTimeout<R, Action>) -> Self {
+        #[cfg(feature = "web")]
+        {
+            self.idle_listener = web::IdleListener::new(
+                idle.timeout_ms(),
+                idle.activity_events(),
+                self.dispatcher(),
+            );
+        }
+        self.idle = Some(idle);
+        self
+    }
+
+    /// Rewrite requested routes to experiment variants. See
+    /// [experiments] for more details.
+    #[cfg(feature = "experiments")]
+    pub fn with_experiments(mut self, experiments: Experiments<R, State>) -> Self {
+        self.experiments = Some(experiments);
+        self
+    }
+
+    /// Track masked routes using `masks`, e.g. to persist them in
+    /// session storage. See [masking] for more details.
+    pub fn with_route_masks(mut self, masks: RouteMasks<R>) -> Self {
+        self.masks = masks;
+        self
+    }
+
+    /// Track scroll positions using `positions`, e.g. to persist them in
+    /// session storage. See [scroll] for more details.
+    #[cfg(feature = "scroll")]
+    pub fn with_scroll_positions(mut self, positions: ScrollPositions) -> Self {
+        self.scroll_positions = positions;
+        self
+    }
+
+    /// Save `position` as the scroll position of the displayed route,
+    /// see [scroll].
+    #[cfg(feature = "scroll")]
+    pub fn save_scroll_position(&self, position: ScrollPosition) {
+        match self.displayed_route() {
+            Ok(route) => self.scroll_positions.save(route.path(), position),
+            Err(err) => self.internal_error(format_args!(
+                "Unable to borrow route_service to save the scroll position: {}",
+                err
+            )),
+        }
+    }
+
+    /// The saved scroll position of `route`, see [scroll].
+    #[cfg(feature = "scroll")]
+    pub fn scroll_position(&self, route: &R) -> Option<ScrollPosition> {
+        self.scroll_positions.get(&route.path())
+    }
+
+    /// The experiment variant route to use in place of `route`, if any.
+    #[cfg(feature = "experiments")]
+    fn experiment_route(&self, state: &State, route: &R) -> Option<R> {
+        self.experiments.as_ref()?.rewrite(state, route)
+    }
+
+    #[cfg(not(feature = "experiments"))]
+    fn experiment_route(&self, _state: &State, _route: &R) -> Option<R> {
+        None
+    }
+
+    /// Provide a `service` to guards via [GuardContext::services].
+    pub fn with_service<T: 'static>(mut self, service: T) -> Self {
+        self.services.insert(service);
+        self
+    }
+
+    /// Dispatch actions when entering and leaving routes, and manage the
+    /// state slices belonging to them. See [lifecycle] for more details.
+    pub fn with_lifecycle(mut self, lifecycle: RouteLifecycle<R, Action>) -> Self {
+        self.lifecycle = Rc::new(lifecycle);
+        self
+    }
+
+    /// Prefetch the likely next routes after each navigation. See
+    /// [prefetch] for more details.
+    #[cfg(feature = "prefetch")]
+    pub fn with_prefetch(mut self, prefetch: Rc<Prefetch<R>>) -> Self {
+        self.prefetch = Some(prefetch);
+        self
+    }
+
+    /// Emit `performance.mark()` and `performance.measure()` entries
+    /// around navigations, so that they appear on the performance
+    /// timeline of the browser's devtools and in RUM tooling. The marks
+    /// are named `router:start:<path>` and `router:commit:<path>`, and
+    /// the measure `router:navigate:<path>`.
+    #[cfg(feature = "web")]
+    pub fn with_performance_marks(mut self, enabled: bool) -> Self {
+        self.performance_marks = enabled;
+        self
+    }
+
+    /// Report navigations and router errors to the `hook`. See
+    /// [reporting] for more details.
+    #[cfg(feature = "error-reporting")]
+    pub fn with_error_reporting<H>(mut self, hook: H) -> Self
+    where
+        H: ErrorReportingHook + 'static,
+    {
+        self.error_reporting = Some(Box::new(hook));
+        self
+    }
+
+    /// Validate the query parameters of committed routes, dispatching an
+    /// action on failure. See [QueryValidation] for more details.
+    pub fn with_query_validation(mut self, validation: QueryValidation<R, Action>) -> Self {
+        self.query_validation = Some(validation);
+        self
+    }
+
+    /// The binding of the query parameters used by
+    /// [RouteAction::ChangePage], [Pagination::default()] if not
+    /// specified.
+    pub fn with_pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = pagination;
+        self
+    }
+
+    /// How long typing has to pause before a
+    /// [RouteAction::LiveQueryUpdate] is navigated to,
+    /// [live_query::DEFAULT_DEBOUNCE_MS] by default.
+    #[cfg(feature = "web")]
+    pub fn with_live_query_debounce(mut self, debounce_ms: u32) -> Self {
+        self.live_query = self.live_query.with_debounce(debounce_ms);
+        self
+    }
+
+    /// Generate the links shared by [RouteAction::ShareCurrentRoute] with
+    /// `share_link`. By default the links are generated for the origin
+    /// of the current page, with the configured [Href].
+    pub fn with_share_link(mut self, share_link: ShareLink) -> Self {
+        self.share_link = Some(share_link);
+        self
+    }
+
+    /// Resolve the token routes of `short_routes` arriving from the
+    /// browser, replacing the URL with the full route. See
+    /// [short_routes] for more details.
+    #[cfg(feature = "short-routes")]
+    pub fn with_short_routes(mut self, short_routes: ShortRoutes<R>) -> Self {
+        self.short_routes = Some(short_routes);
+        self
+    }
+
+    /// Handle the custom commands of type `C` dispatched as
+    /// [RouteAction::Custom], see [command] for more details.
+    pub fn with_command_handler<C, F>(mut self, handler: F) -> Self
+    where
+        C: RouteCommand<R>,
+        F: Fn(&State, &C) -> Option<RouteAction<R>> + 'static,
+    {
+        self.commands = self.commands.with_handler(handler);
+        self
+    }
+
+    /// A handle marking the reduce cycles which contain route actions,
+    /// for coordination with undo middleware. See [undo] for more
+    /// details.
+    pub fn navigation_marker(&self) -> NavigationMarker {
+        self.navigation_marker.clone()
+    }
+
+    /// Identify this router as one of several routers in the store, it
+    /// only handles actions wrapped in a [RouteAction::Routed] for `id`.
+    /// See [router] for more details.
+    /// The route of this router is read from the state with
+    /// [NamedRouteState::get_route_for()].
+    pub fn with_router_id(mut self, id: RouterId) -> Self
+    where
+        State: NamedRouteState<R>,
+    {
+        self.router_id.set(Some(id));
+        self.named_route = Some((id, State::get_route_for));
+        self
+    }
+
+    /// Dispatch `keydown` events on the window matching the `bindings`.
+    /// The listener is removed when the middleware is dropped. See
+    /// [shortcuts] for more details.
+    #[cfg(feature = "web")]
+    pub fn with_key_bindings(mut self, bindings: KeyBindings<R>) -> Self {
+        let dispatch = self.dispatcher();
+        self.key_listener = web::KeyListener::new(bindings, dispatch);
+        self
+    }
+
+    /// Let the parent page drive this router with `postMessage`, and
+    /// report committed routes back to it. The listener is removed when
+    /// the middleware is dropped. See [embed] for more details.
+    #[cfg(feature = "web")]
+    pub fn with_embed(mut self, config: EmbedConfig) -> Self {
+        let dispatch = self.dispatcher();
+        self.embed =
+            web::MessageListener::new(config.clone(), dispatch).map(|listener| (config, listener));
+        self
+    }
+
+    /// Delegate the routes under the URL `prefix` to a child app, which
+    /// is forwarded the sub-path of each committed route under the
+    /// prefix. See [federation] for more details.
+    #[cfg(feature = "federation")]
+    pub fn with_delegation<S, C>(mut self, prefix: S, child: C) -> Self
+    where
+        S: Into<String>,
+        C: FederationChild + 'static,
+    {
+        self.delegations.insert(prefix.into(), Box::new(child));
+        self
+    }
+
+    /// Hold back navigations to routes whose code-split chunk hasn't
+    /// been loaded, emitting a [RouteEffect::LoadChunk] effect. See
+    /// [chunks] for more details.
+    pub fn with_chunk_loading(mut self) -> Self
+    where
+        Effect: From<RouteEffect>,
+    {
+        self.chunk_loading = Some((Rc::new(ChunkLoader::new()), Effect::from));
+        self
+    }
+
+    /// Emit [RouteEvent::scope_changed()] when a navigation enters,
+    /// leaves or moves within the `scope` (e.g. `/settings/*`). See
+    /// [scope] for more details.
+    pub fn with_route_scope<S: Into<String>>(mut self, scope: S) -> Self
+    where
+        R: RouteScope,
+    {
+        self.route_scopes
+            .get_or_insert_with(|| (R::in_scope, Vec::new()))
+            .1
+            .push(scope.into());
+        self
+    }
+
+    /// Abandon navigations whose chunk hasn't loaded after `timeout_ms`,
+    /// defaults to [DEFAULT_CHUNK_TIMEOUT_MS](chunks::DEFAULT_CHUNK_TIMEOUT_MS).
+    #[cfg(feature = "web")]
+    pub fn with_chunk_timeout_ms(mut self, timeout_ms: u32) -> Self {
+        self.chunk_timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Open the routes matching the rules of `routing` in their own
+    /// Tauri windows, and go back when the OS-level back gesture is used.
+    /// See [tauri] for more details.
+    #[cfg(feature = "tauri")]
+    pub fn with_window_routing(mut self, routing: WindowRouting<R>) -> Self {
+        let dispatch = self.dispatcher();
+        routing
+            .bridge()
+            .listen_back_gesture(Box::new(move || dispatch(RouteAction::Back)));
+        self.window_routing = Some(routing);
+        self
+    }
+
+    /// Wrap `action` for this router, if it has an id.
+    fn scoped(&self, action: RouteAction<R>) -> RouteAction<R> {
+        match self.router_id.get() {
+            Some(router) => action.for_router(router),
+            None => action,
+        }
+    }
+
+    /// Wrap `action` for this router, and into the store's action.
+    fn wrap(&self, action: RouteAction<R>) -> Action {
+        self.actions.wrap(self.scoped(action))
+    }
+
+    /// A function dispatching route actions to the store, see
+    /// [route_dispatcher()].
+    fn dispatcher(&self) -> impl Fn(RouteAction<R>) {
+        route_dispatcher(
+            self.store.clone(),
+            self.router_id.clone(),
+            self.actions.wrapper(),
+        )
+    }
+
+    /// In strict mode, internal failures (such as the route service
+    /// being unavailable, or an action requiring a disabled feature)
+    /// panic with the action and route involved, rather than only being
+    /// logged. Off by default, consider enabling it in development with
+    /// `with_strict_mode(cfg!(debug_assertions))`.
+    pub fn with_strict_mode(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Pass internal failures to `sink` rather than logging them, see
+    /// [logging].
+    pub fn with_error_sink<S>(mut self, sink: S) -> Self
+    where
+        S: ErrorSink + 'static,
+    {
+        self.error_sink = Box::new(sink);
+        self
+    }
+
+    /// In debug builds, check the [diagnostics](RouteMiddleware::diagnostics)
+    /// every `actions` actions reduced by the store, logging a warning for
+    /// each problem found. Disabled in release builds.
+    #[cfg(feature = "diagnostics")]
+    pub fn with_self_check(mut self, actions: u32) -> Self {
+        if cfg!(debug_assertions) && actions > 0 {
+            self.self_check = Some((actions, Cell::new(0)));
+        }
+        self
+    }
+
+    /// Resolve `route` as the initial route (see
+    /// [RouteAction::ResolveInitialRoute]), ignoring the route of the
+    /// route service, e.g. when embedding the application in
+    /// documentation or a demo, where the URL of the host page is
+    /// meaningless. Unless navigation is kept in memory with
+    /// [with_memory_navigation()](Self::with_memory_navigation), the
+    /// route service is then navigated to `route`.
+    pub fn with_initial_route(mut self, route: R) -> Self {
+        if let Some(memory_history) = &*self.memory_history.borrow() {
+            memory_history.set_history(vec![route.clone()], 0);
+        }
+        self.initial_route = Some(route);
+        self
+    }
+
+    /// Navigate an in-memory history (see [MemoryRouteService]) rather
+    /// than the route service, so that the application never changes the
+    /// URL of the page it is embedded in. Going back delivers the previous
+    /// route from the in-memory history. The history starts with the
+    /// [initial route](Self::with_initial_route) if there is one,
+    /// otherwise with the route of the route service.
+    pub fn with_memory_navigation(mut self, memory: bool) -> Self {
+        let memory_history = if memory {
+            let initial = match &self.initial_route {
+                Some(route) => route.clone(),
+                None => self.route_service.get_mut().get_route(),
+            };
+            Some(self.new_memory_history(initial))
+        } else {
+            None
+        };
+        self.memory_history = RefCell::new(memory_history);
+        self.memory_navigation = memory;
+        self
+    }
+
+    /// An in-memory history starting at `route`, delivering its routes
+    /// to the middleware's callback.
+    fn new_memory_history(&self, route: R) -> MemoryRouteService<R> {
+        let mut memory_history = MemoryRouteService::new(route);
+        memory_history.register_callback(&self.callback);
+        memory_history
+    }
+
+    /// The in-memory history, if navigation currently stays in memory.
+    fn memory_history(&self) -> Option<MemoryRouteService<R>> {
+        self.memory_history.borrow().clone()
+    }
+
+    /// The route displayed by the route service, or by the in-memory
+    /// history with [memory navigation](Self::with_memory_navigation).
+    fn displayed_route(&self) -> Result<R, BorrowError> {
+        match self.memory_history() {
+            Some(memory_history) => Ok(memory_history.get_route()),
+            None => self
+                .route_service
+                .try_borrow()
+                .map(|route_service| route_service.get_route()),
+        }
+    }
+
+    /// Keep the last `capacity` route actions and their outcomes, see
+    /// [audit] for more details.
+    #[cfg(feature = "audit")]
+    pub fn with_audit_log(mut self, capacity: usize) -> Self {
+        self.audit_log = Some(AuditLog::new(capacity));
+        self
+    }
+
+    /// Record the outcome of the route `action` in the audit log, if it
+    /// is enabled.
+    #[cfg(feature = "audit")]
+    fn audit<F>(&self, action: &RouteAction<R>, outcome: F)
+    where
+        F: FnOnce() -> AuditOutcome,
+    {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(action, outcome());
+        }
+    }
+
+    /// Replace the URL with the route of the state after reducing an
+    /// action for which `is_time_travel` returns `true`, i.e. an action
+    /// of the devtools restoring a previous state. See [devtools] for
+    /// more details.
+    #[cfg(feature = "devtools")]
+    pub fn with_time_travel<F>(mut self, is_time_travel: F) -> Self
+    where
+        F: Fn(&Action) -> bool + 'static,
+    {
+        self.time_travel = Some(Box::new(is_time_travel));
+        self
+    }
+
+    /// Unpack the batches of actions returned by `split`, handling their
+    /// actions one at a time in the specified `order`, see [batch].
+    pub fn with_action_batches<F>(mut self, split: F, order: BatchOrder) -> Self
+    where
+        F: Fn(&Action) -> Option<&[Action]> + 'static,
+    {
+        self.batches = Some((Box::new(split), order));
+        self
+    }
+
+    /// Run this router before the `other` middleware, see [stack].
+    pub fn before<M>(self, other: M) -> MiddlewareStack<State, Action, Event, Effect>
+    where
+        Self: Middleware<State, Action, Event, Effect>,
+        M: Middleware<State, Action, Event, Effect> + 'static,
+    {
+        MiddlewareStack::new(self).before(other)
+    }
+
+    /// Run this router after the `other` middleware, see [stack].
+    pub fn after<M>(self, other: M) -> MiddlewareStack<State, Action, Event, Effect>
+    where
+        Self: Middleware<State, Action, Event, Effect>,
+        M: Middleware<State, Action, Event, Effect> + 'static,
+    {
+        MiddlewareStack::new(self).after(other)
+    }
+
+    /// Call `apply` with the route navigated to after the first
+    /// navigation committed while an update is pending, see [update].
+    pub fn with_update_on_navigation<F>(mut self, apply: F) -> Self
+    where
+        F: Fn(&R) + 'static,
+    {
+        self.apply_update = Some(Box::new(apply));
+        self
+    }
+
+    /// Call the user-provided `hook`, catching a panic so that a bad hook
+    /// fails the navigation (emitting
+    /// [RouteEvent::navigation_failed()]), rather than leaving the
+    /// middleware with a navigation half reduced. Returns `None` if the
+    /// hook panicked. Panics are only caught when they unwind, i.e. not
+    /// with `panic = "abort"` (the default for `wasm32-unknown-unknown`).
+    fn catch_hook_panic<T, F>(&self, hook: &str, call: F) -> Option<T>
+    where
+        F: FnOnce() -> T,
+    {
+        let payload = match panic::catch_unwind(AssertUnwindSafe(call)) {
+            Ok(value) => return Some(value),
+            Err(payload) => payload,
+        };
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("non-string panic payload");
+        self.hook_panicked.set(true);
+        self.internal_error(format_args!("The {} panicked: {}", hook, message));
+        None
+    }
+
+    /// Report an internal failure, see
+    /// [with_strict_mode()](Self::with_strict_mode).
+    fn internal_error(&self, message: std::fmt::Arguments<'_>) {
+        #[cfg(feature = "error-reporting")]
+        if let Some(hook) = &self.error_reporting {
+            hook.router_error(&message.to_string());
+        }
+        if self.strict {
+            panic!("RouteMiddleware: {}", message);
+        } else {
+            self.last_error.replace(Some(message.to_string()));
+            self.error_sink.error(message);
+        }
+    }
+
+    /// Replace the route service with `service` (e.g. upgrading from
+    /// hash routing to history routing after feature detection, using a
+    /// [RouteServiceObject](service_object::RouteServiceObject)). The
+    /// middleware's callback is registered with the new service, which
+    /// is then synced to the current route of the previous service.
+    ///
+    /// Returns the previous service, or gives back `service` if the
+    /// route service is currently in use.
+    pub fn swap_service(&self, mut service: RS) -> Result<RS, RS> {
+        let mut router = match self.route_service.try_borrow_mut() {
+            Ok(router) => router,
+            Err(err) => {
+                self.internal_error(format_args!(
+                    "Unable to borrow route_service to swap it: {}",
+                    err
+                ));
+                return Err(service);
+            }
+        };
+        service.register_callback(&self.callback);
+        self.callback_registered.set(true);
+        service.replace_route(router.get_route());
+        Ok(std::mem::replace(&mut *router, service))
+    }
+
+    /// A snapshot of the router data tracked by the middleware, see
+    /// [snapshot] for more details.
+    pub fn snapshot(&self) -> Option<RouterSnapshot<R>> {
+        let (route, history, history_index) = match self.memory_history() {
+            Some(memory_history) => (
+                memory_history.get_route(),
+                memory_history.entries(),
+                memory_history.index(),
+            ),
+            None => match self.route_service.try_borrow() {
+                Ok(router) => {
+                    let route = router.get_route();
+                    (route.clone(), vec![route], 0)
+                }
+                Err(err) => {
+                    self.internal_error(format_args!(
+                        "Unable to borrow route_service to take a snapshot: {}",
+                        err
+                    ));
+                    return None;
+                }
+            },
+        };
+        Some(RouterSnapshot {
+            version: snapshot::SNAPSHOT_VERSION,
+            route,
+            history,
+            history_index,
+            status: NavigationStatus {
+                failed_route: self.failed_route(),
+                last_failed_navigation: self.last_failed_navigation(),
+                pending_confirmation: self.pending_confirmation(),
+            },
+            masks: self.masks.pairs(),
+            navigation_generation: self.navigation_marker.generation(),
+            #[cfg(feature = "scroll")]
+            scroll_positions: self.scroll_positions.pairs(),
+        })
+    }
+
+    /// Restore the router data from a `snapshot`. The history stack of
+    /// the in-memory history is replaced with the snapshot's, otherwise
+    /// the current route of the route service is replaced with the
+    /// snapshot's route. Snapshots of previous versions are migrated,
+    /// snapshots of unknown versions are discarded, see [snapshot].
+    pub fn restore(&self, snapshot: RouterSnapshot<R>) {
+        let snapshot = match snapshot.migrate() {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        self.masks.restore(snapshot.masks);
+        #[cfg(feature = "scroll")]
+        self.scroll_positions.restore(snapshot.scroll_positions);
+        self.navigation_marker
+            .restore_generation(snapshot.navigation_generation);
+        self.failed_route.replace(snapshot.status.failed_route);
+        self.last_failed_navigation
+            .replace(snapshot.status.last_failed_navigation);
+        self.pending_confirmation
+            .replace(snapshot.status.pending_confirmation);
+        match self.memory_history() {
+            Some(memory_history) if snapshot.history_index < snapshot.history.len() => {
+                memory_history.set_history(snapshot.history, snapshot.history_index)
+            }
+            _ => self.replace_route(snapshot.route),
+        }
+    }
+
+    /// Run the guards for a navigation from the `previous` route to
+    /// `target`.
+    fn guard_route(
+        &self,
+        state: Rc<State>,
+        previous: &R,
+        target: &R,
+        kind: NavigationKind,
+    ) -> GuardOutcome<R> {
+        if let Some(route) = self.idle.as_ref().and_then(|idle| idle.redirect(target)) {
+            return GuardOutcome::Redirect(route);
+        }
+        if let Some(route) = self
+            .maintenance
+            .as_ref()
+            .and_then(|maintenance| maintenance.redirect(&state, target))
+        {
+            return GuardOutcome::Redirect(route);
+        }
+        let metadata = self.registry.metadata(target);
+        if let Some(route) = self
+            .offline
+            .as_ref()
+            .and_then(|offline| offline.redirect(target, metadata))
+        {
+            return GuardOutcome::Redirect(route);
+        }
+        if self.guards.is_empty() {
+            return GuardOutcome::Allow;
+        }
+        let context = GuardContext {
+            state,
+            target,
+            previous,
+            kind,
+            metadata,
+            services: &self.services,
+        };
+        self.guards
+            .iter()
+            .map(|guard| {
+                self.catch_hook_panic("route guard", || guard.check(&context))
+                    .unwrap_or(GuardOutcome::Block)
+            })
+            .find(|outcome| !matches!(outcome, GuardOutcome::Allow))
+            .unwrap_or(GuardOutcome::Allow)
+    }
+
+    fn set_route(&self, route: R) {
+        self.service_op(ServiceOp::Set(route));
+    }
+
+    fn replace_route(&self, route: R) {
+        self.service_op(ServiceOp::Replace(route));
+    }
+
+    fn back(&self) -> Option<R> {
+        self.service_op(ServiceOp::Back)
+    }
+
+    /// The number of history entries (up to the current one) which a
+    /// [RouteAction::ReplaceStackTop] of `n` entries collapses: at least
+    /// one, and no more than the entries up to the current one, if the
+    /// history is known (the memory navigation history, or a
+    /// [MemoryRouteService]). There is no way to tell for the browser's
+    /// history.
+    fn stack_top_len(&self, n: usize) -> usize {
+        let index = match self.memory_history() {
+            Some(memory_history) => Some(memory_history.index()),
+            None => self
+                .route_service
+                .try_borrow()
+                .ok()
+                .and_then(|route_service| {
+                    (&*route_service as &dyn Any)
+                        .downcast_ref::<MemoryRouteService<R>>()
+                        .map(MemoryRouteService::index)
+                }),
+        };
+        match index {
+            Some(index) => n.clamp(1, index + 1),
+            None => n.max(1),
+        }
+    }
+
+    /// Perform `op` on the route service. If the service is already
+    /// borrowed, because this is a re-entrant navigation (e.g. dispatched
+    /// by a route service callback or a subscriber while the service is
+    /// being used), the operation is queued and performed in order once
+    /// the service has been released.
+    fn service_op(&self, op: ServiceOp<R>) -> Option<R> {
+        if let Some(mut memory_history) = self.memory_history() {
+            let back = matches!(op, ServiceOp::Back);
+            let result = op.apply(&mut memory_history);
+            if back {
+                memory_history.deliver_pending();
+            }
+            return result;
+        }
+        let mut router = match self.route_service.try_borrow_mut() {
+            Ok(router) => router,
+            Err(_) => {
+                debug!("Route service is in use, queuing the operation");
+                self.pending_service_ops.borrow_mut().push_back(op);
+                return None;
+            }
+        };
+        let result = self.apply_service_op(op, &mut *router);
+        drop(router);
+
+        loop {
+            let op = match self.pending_service_ops.borrow_mut().pop_front() {
+                Some(op) => op,
+                None => break,
+            };
+            match self.route_service.try_borrow_mut() {
+                Ok(mut router) => {
+                    self.apply_service_op(op, &mut *router);
+                }
+                Err(err) => {
+                    self.internal_error(format_args!(
+                        "Unable to borrow route_service to {}: {}",
+                        op, err
+                    ));
+                    self.pending_service_ops.borrow_mut().push_front(op);
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    fn apply_service_op(&self, op: ServiceOp<R>, route_service: &mut RS) -> Option<R> {
+        #[cfg(feature = "web")]
+        let entry = match op {
+            ServiceOp::Set(_) => Some(true),
+            ServiceOp::Replace(_) => Some(false),
+            ServiceOp::Back => None,
+        };
+        let result = op.apply(route_service);
+        // The key is (re)applied after the route service has updated the
+        // history entry, replacing an entry resets its state.
+        #[cfg(feature = "web")]
+        if let (Some(keys), Some(pushed)) = (&self.history_keys, entry) {
+            let key = if pushed { keys.push() } else { keys.current() };
+            web::set_history_key(&key);
+        }
+        result
+    }
+
+    /// Refine the `kind` of a route arriving from the browser, using the
+    /// history keys, and whether the initial route is being resolved.
+    fn browser_navigation_kind(&self, kind: NavigationKind) -> NavigationKind {
+        if kind != NavigationKind::External {
+            return kind;
+        }
+        if self.resolving_initial.get() {
+            return NavigationKind::Initial;
+        }
+        #[cfg(feature = "web")]
+        if let Some(keys) = &self.history_keys {
+            if let Some(delta) = web::history_key().and_then(|key| keys.traverse(&key)) {
+                return NavigationKind::Pop { delta };
+            }
+        }
+        NavigationKind::External
+    }
+
+    /// Navigate the route service to `route`, taking into account the
+    /// [UrlLengthGuard] if one is configured. If `replace` is `true`, or
+    /// the route is [replace-only](RouteMetadata::replace_only), the
+    /// current history entry is replaced.
+    fn change_route(&self, route: &R, replace: bool, events: &mut Vec<Event>)
+    where
+        Event: RouteEvent<R>,
+    {
+        let replace = replace
+            || self
+                .registry
+                .metadata(route)
+                .is_some_and(RouteMetadata::is_replace_only);
+        if let Some(token_route) = self.check_url_length(route, events) {
+            self.navigate_service(token_route, replace);
+            return;
+        }
+        self.masks.remove(route);
+        self.navigate_service(route.clone(), replace);
+    }
+
+    /// Check the length of the URL of `route` against the
+    /// [UrlLengthGuard] if one is configured, emitting
+    /// [RouteEvent::url_too_long()] if it is exceeded. Returns the token
+    /// route to display in its place if the guard has a [UrlOverflow].
+    fn check_url_length(&self, route: &R, events: &mut Vec<Event>) -> Option<R>
+    where
+        Event: RouteEvent<R>,
+    {
+        let guard = self.url_length_guard.as_ref()?;
+        let url_length = guard.url_length(&self.href.href(route));
+        if !guard.exceeds(url_length) {
+            return None;
+        }
+        events.extend(Event::url_too_long(url_length, guard.max_length()));
+        guard.overflow()?.overflow(&route.path())
+    }
+
+    fn navigate_service(&self, route: R, replace: bool) {
+        if replace {
+            self.replace_route(route);
+        } else {
+            self.set_route(route);
+        }
+    }
+
+    /// If the path of `route` is not in its canonical form, returns
+    /// the route for the canonical path.
+    fn canonical_route(&self, route: &R) -> Option<R> {
+        if !self.normalize_paths
+            && !self.lowercase_paths
+            && self.trailing_slash == TrailingSlash::Preserve
+        {
+            return None;
+        }
+        let path = route.path();
+        let mut canonical = if self.normalize_paths {
+            encoding::normalize_path(&path)
+        } else {
+            path.clone()
+        };
+        if self.lowercase_paths {
+            canonical = canonical::lowercase_path(&canonical);
+        }
+        canonical = self.trailing_slash.apply(&canonical);
+
+        if canonical == path {
+            None
+        } else {
+            Some(R::switch(&canonical))
+        }
+    }
+
+    /// Returns the route which should be reduced in place of `route`
+    /// arriving from the browser, if it differs. If `route` was a legacy
+    /// route, or not in its canonical form, the browser URL is corrected
+    /// with a replace navigation. The length of the URL displayed is
+    /// checked, see [RouteMiddleware::check_url_length()].
+    fn resolve_browser_route(&self, route: &R, events: &mut Vec<Event>) -> Option<R>
+    where
+        Event: RouteEvent<R>,
+    {
+        if let Some(masked) = self.masks.resolve(route) {
+            return Some(masked);
+        }
+        if let Some(resolved) = self.resolve_overflow(route) {
+            return Some(self.canonical_route(&resolved).unwrap_or(resolved));
+        }
+        #[cfg(feature = "short-routes")]
+        if let Some(resolved) = self
+            .short_routes
+            .as_ref()
+            .and_then(|short_routes| short_routes.resolve(route))
+        {
+            let resolved = self.canonical_route(&resolved).unwrap_or(resolved);
+            self.change_route(&resolved, true, events);
+            return Some(resolved);
+        }
+        if let Some(migrated) = self.migrations.apply_not_found(route) {
+            let migrated = self.canonical_route(&migrated).unwrap_or(migrated);
+            self.change_route(&migrated, true, events);
+            return Some(migrated);
+        }
+        match self.canonical_route(route) {
+            Some(canonical) => {
+                self.change_route(&canonical, true, events);
+                Some(canonical)
+            }
+            None => {
+                if let Some(token_route) = self.check_url_length(route, events) {
+                    self.replace_route(token_route);
+                }
+                None
+            }
+        }
+    }
+
+    /// If `route` arriving from the browser is an overflow token route,
+    /// resolve the full route that it represents.
+    fn resolve_overflow(&self, route: &R) -> Option<R> {
+        let overflow = self.url_length_guard.as_ref()?.overflow()?;
+        match overflow.resolve(route)? {
+            Ok(path) => Some(R::switch(&path)),
+            Err(token) => {
+                error!("Unable to resolve overflowed route for token {:?}", token);
+                None
+            }
+        }
+    }
+}
+
+impl<R, RS, State, Action, Event, Effect> RouteMiddleware<R, RS, State, Action, Event, Effect>
+where
+    R: SwitchRoute + 'static,
+    RS: SwitchRouteService<Route = R> + 'static,
+    Action: Clone + 'static,
+    State: RouteState<R> + 'static,
+    Event: RouteEvent<R> + Clone + Hash + Eq + 'static,
+    Effect: 'static,
+{
+    /// The route of this router in the `state`.
+    fn state_route<'a>(&self, state: &'a State) -> &'a R {
+        match self.named_route {
+            Some((id, get_route_for)) => get_route_for(state, id),
+            None => state.get_route(),
+        }
+    }
+
+    /// The condition of the middleware, see [diagnostics] for more
+    /// details.
+    #[cfg(feature = "diagnostics")]
+    pub fn diagnostics(&self) -> Diagnostics {
+        let service_route = self.displayed_route().ok();
+        let store = self.store.upgrade();
+        let state_route = store
+            .as_ref()
+            .map(|store| self.state_route(&store.state()).clone());
+        let in_sync = match (&service_route, &state_route) {
+            (Some(service_route), Some(state_route)) => Some(
+                service_route == state_route
+                    || self.masks.resolve(service_route).as_ref() == Some(state_route),
+            ),
+            _ => None,
+        };
+
+        let mut pending = Vec::new();
+        if let Some(action) = &*self.pending_confirmation.borrow() {
+            pending.push(PendingNavigation::Confirmation(action.to_string()));
+        }
+        if let Some(chunk_id) = self
+            .chunk_loading
+            .as_ref()
+            .and_then(|(chunks, _)| chunks.waiting_chunk())
+        {
+            pending.push(PendingNavigation::Chunk(chunk_id));
+        }
+        if let Some((remaining, _)) = &*self.stack_compaction.borrow() {
+            pending.push(PendingNavigation::StackCompaction(*remaining));
+        }
+
+        Diagnostics {
+            callback_registered: self.callback_registered.get(),
+            store_alive: store.is_some(),
+            service_available: service_route.is_some(),
+            service_path: service_route.map(|route| route.path()),
+            state_path: state_route.map(|route| route.path()),
+            in_sync,
+            pending,
+            queued_service_ops: self.pending_service_ops.borrow().len(),
+            last_error: self.last_error.borrow().clone(),
+            recent_actions: self
+                .audit_log
+                .as_ref()
+                .map(AuditLog::entries)
+                .unwrap_or_default(),
+            features: diagnostics::FEATURES,
+        }
+    }
+
+    /// Log the problems found by the [diagnostics](Self::diagnostics),
+    /// if a self-check is due, see [with_self_check()](Self::with_self_check).
+    #[cfg(feature = "diagnostics")]
+    fn self_check(&self) {
+        let (every, reduced) = match &self.self_check {
+            Some(self_check) => self_check,
+            None => return,
+        };
+        let count = reduced.get() + 1;
+        if count < *every {
+            reduced.set(count);
+            return;
+        }
+        reduced.set(0);
+        for problem in self.diagnostics().problems() {
+            warning!("RouteMiddleware self-check: {}", problem);
+        }
+    }
+
+    /// Apply the pending update if the navigation from the `previous`
+    /// state has been committed, see [update].
+    fn apply_pending_update(&self, previous: &State, state: &State) {
+        let apply = match &self.apply_update {
+            Some(apply) => apply,
+            None => return,
+        };
+        let route = self.state_route(state);
+        if self.equality.same(self.state_route(previous), route) {
+            return;
+        }
+        debug!(
+            "Applying the pending update after navigating to {}",
+            route.path()
+        );
+        self.update_pending.set(false);
+        self.catch_hook_panic("update hook", || apply(route));
+    }
+
+    /// Replace the route when reducing an action from the `previous`
+    /// state has set or cleared the maintenance flag, see
+    /// [MaintenanceGuard].
+    fn follow_maintenance(&self, store: &Store<State, Action, Event, Effect>, previous: &State) {
+        let maintenance = match &self.maintenance {
+            Some(maintenance) => maintenance,
+            None => return,
+        };
+        let state = store.state();
+        if let Some(route) = maintenance.transition(previous, &state, self.state_route(&state)) {
+            debug!(
+                "Maintenance mode changed, replacing the route with {}",
+                route.path()
+            );
+            store.dispatch(self.wrap(RouteAction::ReplaceRoute(route)));
+        }
+    }
+
+    /// The path of the route `action` navigates to, for
+    /// [RouteEvent::navigation_failed()], or of the route of the `state`
+    /// if it doesn't carry a route.
+    fn failed_path(&self, state: &State, action: &RouteAction<R>) -> String {
+        match action {
+            RouteAction::ChangeRoute(route)
+            | RouteAction::ReplaceRoute(route)
+            | RouteAction::ReplaceStackTop(_, route)
+            | RouteAction::ChangeRouteMasked { route, .. }
+            | RouteAction::BrowserChangeRoute(route, _)
+            | RouteAction::OpenInNewTab(route) => route.path(),
+            _ => self.state_route(state).path(),
+        }
+    }
+
+    /// Replace the URL of the route service with the route of the
+    /// `state` if they differ, without navigating, see
+    /// [RouteAction::SyncUrl].
+    fn sync_url(&self, state: &State) {
+        let route = self.state_route(state);
+        let in_sync = self
+            .displayed_route()
+            .map(|displayed| displayed == *route)
+            .unwrap_or(false);
+        if !in_sync {
+            debug!("Syncing the URL to the route of the state {}", route.path());
+            self.replace_route(route.clone());
+        }
+    }
+
+    /// Stop (`false`) or resume (`true`) updating the URL, see
+    /// [RouteAction::SetUrlSync].
+    fn set_url_sync(&self, sync: bool, state: &State) {
+        if self.memory_navigation {
+            debug!("Ignoring SetUrlSync({}): navigation stays in memory", sync);
+            return;
+        }
+        if !sync {
+            if self.memory_history.borrow().is_none() {
+                let route = self.state_route(state).clone();
+                self.memory_history
+                    .replace(Some(self.new_memory_history(route)));
+            }
+        } else if self.memory_history.replace(None).is_some() {
+            self.sync_url(state);
+        }
+    }
+
+    /// Reduce a navigation to `route` initiated by the application.
+    /// `action` is the [RouteAction::ChangeRoute] carrying the `route`,
+    /// which is reduced as is unless the route is rewritten, otherwise a
+    /// [RouteAction::ChangeRoute] is reduced for the final route.
+    fn reduce_change_route(
+        &self,
+        store: &Store<State, Action, Event, Effect>,
+        action: Option<&Action>,
+        route: Cow<'_, R>,
+        replace: bool,
+        reduce: ReduceFn<State, Action, Event, Effect>,
+        events: &mut Vec<Event>,
+    ) -> ReduceMiddlewareResult<Event, Effect> {
+        let state = store.state();
+        let mut replaced = self.canonical_route(&route);
+        if let Some(variant) = self.experiment_route(&state, replaced.as_ref().unwrap_or(&route)) {
+            replaced = Some(variant);
+        }
+        let target = replaced.as_ref().unwrap_or(&route);
+        match self.guard_route(
+            state.clone(),
+            self.state_route(&state),
+            target,
+            NavigationKind::Push,
+        ) {
+            GuardOutcome::Allow => {
+                let replace = replace || self.equality.same(self.state_route(&state), target);
+                self.change_route(target, replace, events);
+                match (replaced, action) {
+                    (None, Some(action)) => reduce(store, Some(action)),
+                    (replaced, _) => {
+                        let route = replaced.unwrap_or_else(|| route.into_owned());
+                        reduce(store, Some(&self.wrap(RouteAction::ChangeRoute(route))))
+                    }
+                }
+            }
+            GuardOutcome::Redirect(redirect) => {
+                self.change_route(&redirect, replace, events);
+                reduce(store, Some(&self.wrap(RouteAction::ChangeRoute(redirect))))
+            }
+            GuardOutcome::Block => {
+                self.navigation_failed.set(true);
+                events.extend(Event::navigation_blocked());
+                reduce(store, None)
+            }
+        }
+    }
+
+    /// Reduce the live query `params` which typing has paused on, with a
+    /// replace navigation, see [live_query].
+    fn reduce_live_query(
+        &self,
+        store: &Store<State, Action, Event, Effect>,
+        params: &QueryParams,
+        reduce: ReduceFn<State, Action, Event, Effect>,
+        events: &mut Vec<Event>,
+    ) -> ReduceMiddlewareResult<Event, Effect> {
+        let path = params.replace_in_path(&self.state_route(&store.state()).path());
+        self.reduce_change_route(
+            store,
+            None,
+            Cow::Owned(R::switch(&path)),
+            true,
+            reduce,
+            events,
+        )
+    }
+
+    /// Reduce a `route` which has arrived from the browser. `action` is
+    /// the [RouteAction::BrowserChangeRoute] carrying the `route` and
+    /// `kind`, or `None` if the route was polled from the route service,
+    /// is being reloaded, or the kind of the action was refined. An owned
+    /// `route` is moved into the reduced action, rather than cloned.
+    fn reduce_browser_route(
+        &self,
+        store: &Store<State, Action, Event, Effect>,
+        action: Option<&Action>,
+        route: Cow<'_, R>,
+        kind: NavigationKind,
+        reduce: ReduceFn<State, Action, Event, Effect>,
+        events: &mut Vec<Event>,
+    ) -> ReduceMiddlewareResult<Event, Effect> {
+        let resolved = self.resolve_browser_route(&route, events);
+
+        #[cfg(feature = "signing")]
+        if let Some(signed_routes) = &self.signed_routes {
+            let route = resolved.as_ref().unwrap_or(&route);
+            if let Some(result) = signed_routes.verify(route, &route.path()) {
+                events.extend(Event::route_signature_checked(result));
+                if result.is_err() && signed_routes.failure_policy() == SignatureFailure::Reject {
+                    self.replace_route(self.state_route(&store.state()).clone());
+                    return reduce(store, None);
+                }
+            }
+        }
+
+        #[cfg(feature = "oauth")]
+        if let Some(oauth_state) = &self.oauth_state {
+            let route = resolved.as_ref().unwrap_or(&route);
+            if let Some(Err(err)) = oauth_state.validate(route, &route.path()) {
+                error!("OAuth state validation failed: {}", err);
+                events.extend(Event::oauth_state_failed(err));
+                return match oauth_state.failure_route() {
+                    Some(failure_route) => {
+                        let failure_route = failure_route.clone();
+                        self.change_route(&failure_route, true, events);
+                        reduce(
+                            store,
+                            Some(&self.wrap(RouteAction::BrowserChangeRoute(failure_route, kind))),
+                        )
+                    }
+                    None => {
+                        self.replace_route(self.state_route(&store.state()).clone());
+                        reduce(store, None)
+                    }
+                };
+            }
+        }
+
+        let state = store.state();
+        let mut resolved = resolved;
+        if let Some(variant) = self.experiment_route(&state, resolved.as_ref().unwrap_or(&route)) {
+            self.change_route(&variant, true, events);
+            resolved = Some(variant);
+        }
+
+        let guarded = self.guard_route(
+            state.clone(),
+            self.state_route(&state),
+            resolved.as_ref().unwrap_or(&route),
+            kind,
+        );
+        let resolved = match guarded {
+            GuardOutcome::Allow => resolved,
+            GuardOutcome::Redirect(redirect) => {
+                self.change_route(&redirect, true, events);
+                Some(redirect)
+            }
+            GuardOutcome::Block => {
+                self.navigation_failed.set(true);
+                self.replace_route(self.state_route(&store.state()).clone());
+                events.extend(Event::navigation_blocked());
+                return reduce(store, None);
+            }
+        };
+
+        match (resolved, action) {
+            (None, Some(action)) => reduce(store, Some(action)),
+            (resolved, _) => {
+                let route = resolved.unwrap_or_else(|| route.into_owned());
+                reduce(
+                    store,
+                    Some(&self.wrap(RouteAction::BrowserChangeRoute(route, kind))),
+                )
+            }
+        }
+    }
+}
+
+impl<R, RS, State, Action, Event, Effect> Middleware<State, Action, Event, Effect>
+    for RouteMiddleware<R, RS, State, Action, Event, Effect>
+where
+    R: SwitchRoute + 'static,
+    RS: SwitchRouteService<Route = R> + 'static,
+    Action: Clone + Debug + 'static,
+    State: RouteState<R> + 'static,
+    Event: RouteEvent<R> + PartialEq + Clone + Hash + Eq + 'static,
+    Effect: 'static,
+{
+    fn on_reduce(
+        &self,
+        store: &Store<State, Action, Event, Effect>,
+        action: Option<&Action>,
+        reduce: ReduceFn<State, Action, Event, Effect>,
+    ) -> ReduceMiddlewareResult<Event, Effect> {
+        if let (Some((split, order)), Some(action)) = (&self.batches, action) {
+            if let Some(batch) = split(action) {
+                let mut result: Option<ReduceMiddlewareResult<Event, Effect>> = None;
+                let is_route_action = |action: &Action| self.actions.try_unwrap(action).is_some();
+                for action in order.apply(batch, is_route_action) {
+                    let reduced = self.on_reduce(store, Some(action), reduce);
+                    match &mut result {
+                        Some(result) => {
+                            result.events.extend(reduced.events);
+                            result.effects.extend(reduced.effects);
+                        }
+                        None => result = Some(reduced),
+                    }
+                }
+                return result.unwrap_or_else(|| reduce(store, None));
+            }
+        }
+        #[cfg(feature = "diagnostics")]
+        self.self_check();
+        let route_action = match action.and_then(|action| self.actions.try_unwrap(action)) {
+            Some(route_action) => route_action,
+            None => {
+                let previous_state = store.state();
+                let result = reduce(store, action);
+                #[cfg(feature = "devtools")]
+                if let (Some(is_time_travel), Some(action)) = (&self.time_travel, action) {
+                    if is_time_travel(action) {
+                        self.sync_url(&store.state());
+                    }
+                }
+                self.follow_maintenance(store, &previous_state);
+                return result;
+            }
+        };
+        let route_action = match route_action.routed() {
+            (router, route_action) if router == self.router_id.get() => route_action,
+            // The action is for another router.
+            _ => return reduce(store, action),
+        };
+        let confirmed = self.navigation_confirmed.replace(false);
+
+        if let RouteAction::Custom(command) = route_action {
+            let translated = self.catch_hook_panic("route command handler", || {
+                self.commands.translate(&store.state(), command)
+            });
+            return match translated {
+                None => {
+                    let mut result = reduce(store, None);
+                    self.hook_panicked.set(false);
+                    let path = self.state_route(&store.state()).path();
+                    result.events.extend(Event::navigation_failed(&path));
+                    result
+                }
+                Some(Some(Some(translated))) => {
+                    self.on_reduce(store, Some(&self.wrap(translated)), reduce)
+                }
+                Some(Some(None)) => reduce(store, action),
+                Some(None) => {
+                    self.internal_error(format_args!(
+                        "No handler registered for route command {:?}",
+                        command
+                    ));
+                    reduce(store, action)
+                }
+            };
+        }
+
+        #[cfg(feature = "tauri")]
+        if let (Some(routing), RouteAction::ChangeRoute(route)) =
+            (&self.window_routing, route_action)
+        {
+            if let Some(label) = routing.window_for(route) {
+                routing.bridge().open_window(label, &self.href(route));
+                return reduce(store, None);
+            }
+        }
+
+        if let RouteAction::BrowserChangeRoute(_, kind) = route_action {
+            let compaction = self.stack_compaction.take();
+            if let Some((remaining, route)) = compaction {
+                // Keeps the history keys in step with the traversal.
+                self.browser_navigation_kind(*kind);
+                return if remaining > 1 {
+                    self.stack_compaction.replace(Some((remaining - 1, route)));
+                    self.back();
+                    reduce(store, None)
+                } else {
+                    // The navigation was intercepted as a ReplaceStackTop.
+                    self.navigation_confirmed.set(true);
+                    self.on_reduce(
+                        store,
+                        Some(&self.wrap(RouteAction::ReplaceRoute(route))),
+                        reduce,
+                    )
+                };
+            }
+        }
+
+        if let RouteAction::ConfirmPendingNavigation(confirmed) = route_action {
+            let pending = self.pending_confirmation.take();
+            return match pending {
+                Some(pending) if *confirmed => {
+                    self.navigation_confirmed.set(true);
+                    self.on_reduce(store, Some(&self.wrap(pending)), reduce)
+                }
+                Some(pending) => {
+                    debug!("Cancelling {}", pending);
+                    if let RouteAction::BrowserChangeRoute(..) = pending {
+                        self.replace_route(self.state_route(&store.state()).clone());
+                    }
+                    reduce(store, None)
+                }
+                None => {
+                    debug!("No pending navigation to confirm");
+                    reduce(store, None)
+                }
+            };
+        }
+
+        if let RouteAction::RetryLastNavigation = route_action {
+            return match self.last_failed_navigation.take() {
+                Some(failed) => {
+                    // The URL of a blocked browser navigation was restored
+                    // to the previous route.
+                    if let RouteAction::BrowserChangeRoute(route, _) = &failed {
+                        self.replace_route(route.clone());
+                    }
+                    self.on_reduce(store, Some(&self.wrap(failed)), reduce)
+                }
+                None => {
+                    debug!("No failed navigation to retry");
+                    reduce(store, None)
+                }
+            };
+        }
+
+        if let RouteAction::ResolveInitialRoute = route_action {
+            let route = match (&self.initial_route, self.displayed_route()) {
+                (Some(route), _) => {
+                    if self.memory_history.borrow().is_none() {
+                        self.replace_route(route.clone());
+                    }
+                    route.clone()
+                }
+                (None, Ok(route)) => route,
+                (None, Err(err)) => {
+                    self.internal_error(format_args!(
+                        "Unable to borrow route_service to reduce {}: {}",
+                        route_action, err
+                    ));
+                    return reduce(store, action);
+                }
+            };
+            self.resolving_initial.set(true);
+            return self.on_reduce(
+                store,
+                Some(&self.wrap(RouteAction::BrowserChangeRoute(
+                    route,
+                    NavigationKind::Initial,
+                ))),
+                reduce,
+            );
+        }
+
+        if let Some((chunks, into_effect)) = &self.chunk_loading {
+            match route_action {
+                RouteAction::ChunkLoaded(chunk_id) => {
+                    return match chunks.loaded(chunk_id) {
+                        Some(waiting) => self.on_reduce(store, Some(&self.wrap(waiting)), reduce),
+                        None => reduce(store, action),
+                    };
+                }
+                RouteAction::ChunkLoadFailed(chunk_id) => {
+                    let mut result = reduce(store, action);
+                    if let Some(waiting) = chunks.failed(chunk_id) {
+                        error!(
+                            "Abandoning {}: chunk {:?} failed to load",
+                            waiting, chunk_id
+                        );
+                        if let RouteAction::BrowserChangeRoute(..) = waiting {
+                            self.replace_route(self.state_route(&store.state()).clone());
+                        }
+                        self.last_failed_navigation.replace(Some(waiting));
+                        result.events.extend(Event::chunk_load_failed(chunk_id));
+                        if self.resolving_initial.replace(false) {
+                            result.events.extend(Event::initial_navigation_complete());
+                        }
+                    }
+                    return result;
+                }
+                _ => {}
+            }
+
+            let target = match route_action {
+                RouteAction::ChangeRoute(route)
+                | RouteAction::ReplaceRoute(route)
+                | RouteAction::ChangeRouteMasked { route, .. }
+                | RouteAction::BrowserChangeRoute(route, _) => Some(route),
+                _ => None,
+            };
+            let chunk_id = target
+                .and_then(|route| self.registry.metadata(route))
+                .and_then(|metadata| metadata.chunk())
+                .filter(|chunk_id| !chunks.is_loaded(chunk_id));
+            if let Some(chunk_id) = chunk_id {
+                #[cfg(not(feature = "web"))]
+                chunks.begin(chunk_id, route_action.clone());
+                #[cfg(feature = "web")]
+                {
+                    let generation = chunks.begin(chunk_id, route_action.clone());
+                    let chunks = chunks.clone();
+                    let dispatch = self.dispatcher();
+                    web::set_timeout(self.chunk_timeout_ms, move || {
+                        if let Some(chunk_id) = chunks.expire(generation) {
+                            dispatch(RouteAction::ChunkLoadFailed(chunk_id));
+                        }
+                    });
+                }
+                #[cfg(feature = "audit")]
+                self.audit(route_action, || {
+                    AuditOutcome::AwaitingChunk(chunk_id.to_string())
+                });
+                let mut result = reduce(store, None);
+                result
+                    .effects
+                    .push(into_effect(RouteEffect::LoadChunk(chunk_id.to_string())));
+                return result;
+            }
+        }
 
-        // FIXME: there is multiple borrow error with this callback
-        match router.try_borrow_mut() {
-            Ok(mut router_mut) => {
-                router_mut.register_callback(&callback);
+        self.navigation_marker.begin();
+        let previous_state = store.state();
+        #[cfg(feature = "web")]
+        let performance_start = if self.performance_marks {
+            let target = match route_action {
+                RouteAction::ChangeRoute(route)
+                | RouteAction::ReplaceRoute(route)
+                | RouteAction::ChangeRouteMasked { route, .. }
+                | RouteAction::BrowserChangeRoute(route, _) => route,
+                _ => self.state_route(&previous_state),
+            };
+            let mark = format!("router:start:{}", target.path());
+            web::performance_mark(&mark);
+            Some(mark)
+        } else {
+            None
+        };
+        let mut events = Vec::new();
+        if let Some(interceptor) = &self.interceptor {
+            let outgoing = matches!(
+                route_action,
+                RouteAction::Back
+                    | RouteAction::Forward
+                    | RouteAction::ChangeRoute(_)
+                    | RouteAction::ReplaceRoute(_)
+                    | RouteAction::ReplaceStackTop(..)
+                    | RouteAction::UpdateQuery(_)
+                    | RouteAction::ChangePage(_)
+                    | RouteAction::LiveQuerySettled(_)
+                    | RouteAction::CommitQuery
+                    | RouteAction::NavigateByCommand(_)
+                    | RouteAction::ChangeRouteMasked { .. }
+                    | RouteAction::ExternalNavigate(_)
+            );
+            let browser = matches!(route_action, RouteAction::BrowserChangeRoute(..))
+                && !self.resolving_initial.get();
+            if (outgoing || browser) && !confirmed {
+                let interception = self
+                    .catch_hook_panic("navigation interceptor", || {
+                        interceptor.intercept(&previous_state, route_action)
+                    })
+                    .unwrap_or(Interception::Block);
+                // A navigation started while another one is parked
+                // supersedes it.
+                if let Some(pending) = self.pending_confirmation.take() {
+                    debug!("Discarding {}, superseded by {}", pending, route_action);
+                }
+                match interception {
+                    Interception::Proceed => {}
+                    Interception::Block => {
+                        if browser {
+                            self.replace_route(self.state_route(&previous_state).clone());
+                        }
+                        let mut result = reduce(store, None);
+                        if self.hook_panicked.replace(false) {
+                            #[cfg(feature = "audit")]
+                            self.audit(route_action, || AuditOutcome::Failed);
+                            let path = self.failed_path(&previous_state, route_action);
+                            result.events.extend(Event::navigation_failed(&path));
+                        } else {
+                            #[cfg(feature = "audit")]
+                            self.audit(route_action, || AuditOutcome::Blocked);
+                        }
+                        result.events.extend(Event::navigation_blocked());
+                        self.navigation_marker.end();
+                        return result;
+                    }
+                    Interception::NeedsConfirmation(payload) => {
+                        debug!("Parking {} until it is confirmed", route_action);
+                        self.pending_confirmation
+                            .replace(Some(route_action.clone()));
+                        #[cfg(feature = "audit")]
+                        self.audit(route_action, || AuditOutcome::AwaitingConfirmation);
+                        let mut result = reduce(store, None);
+                        result.events.extend(Event::confirmation_required(&payload));
+                        self.navigation_marker.end();
+                        return result;
+                    }
+                }
             }
-            Err(err) => {
-                error!("Unable to register callback {:?}: {}", callback, err);
+        }
+
+        let mut result = match route_action {
+            RouteAction::Back => {
+                self.back();
+                reduce(store, None)
+            }
+            RouteAction::Forward => {
+                #[cfg(feature = "web")]
+                web::history_forward();
+                #[cfg(not(feature = "web"))]
+                self.internal_error(format_args!(
+                    "Unable to go forward: requires the web feature"
+                ));
+                reduce(store, None)
+            }
+            RouteAction::ChangeRoute(route) => self.reduce_change_route(
+                store,
+                action,
+                Cow::Borrowed(route),
+                false,
+                reduce,
+                &mut events,
+            ),
+            RouteAction::ReplaceRoute(route) => self.reduce_change_route(
+                store,
+                None,
+                Cow::Borrowed(route),
+                true,
+                reduce,
+                &mut events,
+            ),
+            RouteAction::ReplaceStackTop(n, route) if self.stack_top_len(*n) > 1 => {
+                self.stack_compaction
+                    .replace(Some((self.stack_top_len(*n) - 1, route.clone())));
+                self.back();
+                reduce(store, None)
+            }
+            RouteAction::ReplaceStackTop(_, route) => self.reduce_change_route(
+                store,
+                None,
+                Cow::Borrowed(route),
+                true,
+                reduce,
+                &mut events,
+            ),
+            RouteAction::UpdateQuery(params) => {
+                let path = params.replace_in_path(&self.state_route(&store.state()).path());
+                self.reduce_change_route(
+                    store,
+                    None,
+                    Cow::Owned(R::switch(&path)),
+                    true,
+                    reduce,
+                    &mut events,
+                )
+            }
+            RouteAction::LiveQueryUpdate(params) => {
+                self.live_query.begin(self.state_route(&store.state()));
+                let generation = self.live_query.defer(params.clone());
+                #[cfg(feature = "web")]
+                {
+                    let dispatch = self.dispatcher();
+                    web::set_timeout(self.live_query.debounce_ms(), move || {
+                        dispatch(RouteAction::LiveQuerySettled(generation));
+                    });
+                    reduce(store, None)
+                }
+                #[cfg(not(feature = "web"))]
+                match self.live_query.settle(generation) {
+                    Some(params) => self.reduce_live_query(store, &params, reduce, &mut events),
+                    None => reduce(store, None),
+                }
+            }
+            RouteAction::LiveQuerySettled(generation) => {
+                match self.live_query.settle(*generation) {
+                    Some(params) => self.reduce_live_query(store, &params, reduce, &mut events),
+                    None => reduce(store, None),
+                }
+            }
+            RouteAction::CommitQuery => match self.live_query.commit() {
+                Some((origin, pending)) => {
+                    let current = self.state_route(&store.state()).clone();
+                    let target = match pending {
+                        Some(params) => R::switch(&params.replace_in_path(&current.path())),
+                        None => current.clone(),
+                    };
+                    if self.equality.same(&origin, &target) {
+                        self.reduce_change_route(
+                            store,
+                            None,
+                            Cow::Owned(target),
+                            true,
+                            reduce,
+                            &mut events,
+                        )
+                    } else {
+                        // Restore the entry from before typing, and push
+                        // a single entry for the committed query.
+                        self.replace_route(origin);
+                        if self.equality.same(&current, &target) {
+                            self.set_route(target);
+                            reduce(store, None)
+                        } else {
+                            self.reduce_change_route(
+                                store,
+                                None,
+                                Cow::Owned(target),
+                                false,
+                                reduce,
+                                &mut events,
+                            )
+                        }
+                    }
+                }
+                None => reduce(store, None),
+            },
+            RouteAction::ChangePage(delta) => {
+                let path = self.state_route(&store.state()).path();
+                match self.pagination.offset_path(&path, *delta) {
+                    Some(path) => self.reduce_change_route(
+                        store,
+                        None,
+                        Cow::Owned(R::switch(&path)),
+                        false,
+                        reduce,
+                        &mut events,
+                    ),
+                    None => {
+                        debug!("Ignoring ChangePage({}) from {}", delta, path);
+                        reduce(store, None)
+                    }
+                }
+            }
+            RouteAction::NavigateByCommand(id) => {
+                match self
+                    .registry
+                    .entry_by_id(id)
+                    .and_then(|entry| entry.route())
+                {
+                    Some(route) => self.reduce_change_route(
+                        store,
+                        None,
+                        Cow::Owned(route),
+                        false,
+                        reduce,
+                        &mut events,
+                    ),
+                    None => {
+                        error!("No route command registered with id {:?}", id);
+                        reduce(store, None)
+                    }
+                }
+            }
+            RouteAction::ChangeRouteMasked { route, mask } => {
+                let state = store.state();
+                match self.guard_route(
+                    state.clone(),
+                    self.state_route(&state),
+                    route,
+                    NavigationKind::Push,
+                ) {
+                    GuardOutcome::Allow => {
+                        self.masks.insert(mask, route);
+                        self.set_route(mask.clone());
+                        reduce(
+                            store,
+                            Some(&self.wrap(RouteAction::ChangeRoute(route.clone()))),
+                        )
+                    }
+                    GuardOutcome::Redirect(redirect) => {
+                        self.change_route(&redirect, false, &mut events);
+                        reduce(store, Some(&self.wrap(RouteAction::ChangeRoute(redirect))))
+                    }
+                    GuardOutcome::Block => {
+                        self.navigation_failed.set(true);
+                        events.extend(Event::navigation_blocked());
+                        reduce(store, None)
+                    }
+                }
+            }
+            RouteAction::BrowserChangeRoute(route, incoming_kind) => {
+                let kind = self.browser_navigation_kind(*incoming_kind);
+                #[cfg(feature = "web")]
+                if let NavigationKind::Pop { delta } = kind {
+                    events.extend(Event::history_traversed(delta));
+                }
+                self.reduce_browser_route(
+                    store,
+                    action.filter(|_| kind == *incoming_kind),
+                    Cow::Borrowed(route),
+                    kind,
+                    reduce,
+                    &mut events,
+                )
+            }
+            RouteAction::PollBrowserRoute => match self.displayed_route() {
+                Ok(route) => {
+                    if *self.state_route(&previous_state) == route {
+                        // Polling on an interval mostly finds the route
+                        // unchanged, there is nothing to reduce (or
+                        // allocate).
+                        reduce(store, None)
+                    } else {
+                        let kind = self.browser_navigation_kind(NavigationKind::External);
+                        self.reduce_browser_route(
+                            store,
+                            None,
+                            Cow::Owned(route),
+                            kind,
+                            reduce,
+                            &mut events,
+                        )
+                    }
+                }
+                Err(err) => {
+                    self.internal_error(format_args!(
+                        "Unable to borrow route_service to reduce {}: {}",
+                        route_action, err
+                    ));
+                    reduce(store, action)
+                }
+            },
+            RouteAction::Reload => {
+                let route = self.state_route(&store.state()).clone();
+                self.reduce_browser_route(
+                    store,
+                    None,
+                    Cow::Owned(route),
+                    NavigationKind::Reload,
+                    reduce,
+                    &mut events,
+                )
+            }
+            RouteAction::SyncUrl => {
+                self.sync_url(&store.state());
+                reduce(store, action)
+            }
+            RouteAction::SetUrlSync(sync) => {
+                self.set_url_sync(*sync, &store.state());
+                reduce(store, action)
+            }
+            RouteAction::SetUpdatePending(pending) => {
+                self.update_pending.set(*pending);
+                reduce(store, action)
+            }
+            RouteAction::ConnectivityChanged(online) => {
+                let current = self.state_route(&store.state()).clone();
+                match self
+                    .offline
+                    .as_ref()
+                    .and_then(|offline| offline.set_online(*online, &current))
+                {
+                    Some(intended) => {
+                        debug!("Back online, resuming {}", intended.path());
+                        self.reduce_change_route(
+                            store,
+                            None,
+                            Cow::Owned(intended),
+                            true,
+                            reduce,
+                            &mut events,
+                        )
+                    }
+                    None => reduce(store, action),
+                }
+            }
+            RouteAction::SessionIdle => {
+                let current = self.state_route(&store.state()).clone();
+                match self.idle.as_ref().and_then(|idle| idle.lock(&current)) {
+                    Some(lock_route) => {
+                        let result = self.reduce_change_route(
+                            store,
+                            None,
+                            Cow::Owned(lock_route),
+                            true,
+                            reduce,
+                            &mut events,
+                        );
+                        if let Some(action) = self.idle.as_ref().and_then(|idle| idle.action()) {
+                            store.dispatch(action.clone());
+                        }
+                        result
+                    }
+                    None => reduce(store, action),
+                }
+            }
+            RouteAction::UnlockSession => match self.idle.as_ref().and_then(|idle| idle.unlock()) {
+                Some(intended) => {
+                    debug!("Session unlocked, resuming {}", intended.path());
+                    self.reduce_change_route(
+                        store,
+                        None,
+                        Cow::Owned(intended),
+                        true,
+                        reduce,
+                        &mut events,
+                    )
+                }
+                None => reduce(store, action),
+            },
+            RouteAction::HardReload => {
+                #[cfg(feature = "web")]
+                web::reload_location();
+                #[cfg(not(feature = "web"))]
+                self.internal_error(format_args!(
+                    "Unable to reload the page: requires the web feature"
+                ));
+                reduce(store, action)
+            }
+            RouteAction::OpenInNewTab(route) => {
+                let href = self.href(route);
+                #[cfg(feature = "web")]
+                web::open_in_new_tab(&href);
+                #[cfg(not(feature = "web"))]
+                self.internal_error(format_args!(
+                    "Unable to open {:?} in a new tab: requires the web feature",
+                    href
+                ));
+                reduce(store, action)
+            }
+            RouteAction::ShareCurrentRoute => {
+                let route = self.state_route(&store.state()).clone();
+                #[cfg(feature = "web")]
+                match self.share_link.clone().or_else(|| {
+                    ShareLink::from_location().map(|share| share.with_href(self.href.clone()))
+                }) {
+                    Some(share) => {
+                        let title = self
+                            .registry
+                            .metadata(&route)
+                            .and_then(RouteMetadata::title)
+                            .unwrap_or_default();
+                        web::share(&share.link(&route), title);
+                    }
+                    None => self.internal_error(format_args!(
+                        "Unable to share {}: the origin is unknown",
+                        route.path()
+                    )),
+                }
+                #[cfg(not(feature = "web"))]
+                self.internal_error(format_args!(
+                    "Unable to share {}: requires the web feature",
+                    route.path()
+                ));
+                reduce(store, action)
+            }
+            RouteAction::NavigateToError(info) => match &self.error_route {
+                Some(error_route) => {
+                    error!("Navigating to the error route: {}", info);
+                    let failed = self.state_route(&store.state()).clone();
+                    self.last_failed_navigation
+                        .replace(Some(RouteAction::ReplaceRoute(failed.clone())));
+                    self.failed_route.replace(Some(failed));
+                    self.reduce_change_route(
+                        store,
+                        None,
+                        Cow::Owned(error_route(info)),
+                        true,
+                        reduce,
+                        &mut events,
+                    )
+                }
+                None => {
+                    self.internal_error(format_args!(
+                        "Unable to navigate to the error route for {:?}: no error route declared",
+                        info
+                    ));
+                    reduce(store, action)
+                }
+            },
+            RouteAction::RetryFailedRoute => match self.failed_route.take() {
+                Some(failed) => self.reduce_change_route(
+                    store,
+                    None,
+                    Cow::Owned(failed),
+                    true,
+                    reduce,
+                    &mut events,
+                ),
+                None => {
+                    debug!("No failed route to retry");
+                    reduce(store, None)
+                }
+            },
+            RouteAction::ExternalNavigate(url) => {
+                #[cfg(feature = "web")]
+                web::assign_location(url);
+                #[cfg(not(feature = "web"))]
+                self.internal_error(format_args!(
+                    "Unable to navigate to external url {:?}: requires the web feature",
+                    url
+                ));
+                reduce(store, action)
+            }
+            RouteAction::ChunkLoaded(_) | RouteAction::ChunkLoadFailed(_) => reduce(store, action),
+            RouteAction::RouteParseFailed(path) => {
+                if let Some(migrated) = self.migrations.apply(path) {
+                    debug!("Migrated legacy path {:?}", path);
+                    self.reduce_change_route(
+                        store,
+                        None,
+                        Cow::Owned(migrated),
+                        true,
+                        reduce,
+                        &mut events,
+                    )
+                } else if let Some(route) = self.route_table_route(path) {
+                    debug!("Resolved path {:?} with the route table", path);
+                    self.reduce_change_route(
+                        store,
+                        None,
+                        Cow::Owned(route),
+                        true,
+                        reduce,
+                        &mut events,
+                    )
+                } else {
+                    error!("Unable to parse the route for path {:?}", path);
+                    events.extend(Event::navigation_failed(path));
+                    match self
+                        .parse_fallback
+                        .as_ref()
+                        .and_then(|fallback| fallback(path))
+                    {
+                        Some(route) => self.reduce_change_route(
+                            store,
+                            None,
+                            Cow::Owned(route),
+                            true,
+                            reduce,
+                            &mut events,
+                        ),
+                        None => reduce(store, action),
+                    }
+                }
+            }
+            RouteAction::ResolveInitialRoute => {
+                unreachable!("the initial route is resolved before being reduced")
+            }
+            RouteAction::RetryLastNavigation => {
+                unreachable!("the failed navigation is retried before being reduced")
+            }
+            RouteAction::ConfirmPendingNavigation(_) => {
+                unreachable!("the pending navigation is confirmed before being reduced")
+            }
+            RouteAction::Custom(_) => {
+                unreachable!("custom route commands are translated before being reduced")
+            }
+            RouteAction::Routed { .. } => {
+                self.internal_error(format_args!(
+                    "Unable to reduce nested routed action {}",
+                    route_action
+                ));
+                reduce(store, action)
+            }
+        };
+        result.events.extend(events);
+
+        self.navigation_marker.end();
+
+        let current_state = store.state();
+        #[cfg(feature = "error-reporting")]
+        if let Some(hook) = &self.error_reporting {
+            hook.navigation(&NavigationReport {
+                action: route_action.name(),
+                from: reporting::redact_path(&self.state_route(&previous_state).path()),
+                to: reporting::redact_path(&self.state_route(&current_state).path()),
+            });
+        }
+        #[cfg(feature = "web")]
+        if let Some(start) = performance_start {
+            let path = self.state_route(&current_state).path();
+            let commit = format!("router:commit:{}", path);
+            web::performance_mark(&commit);
+            web::performance_measure(&format!("router:navigate:{}", path), &start, &commit);
+        }
+        if !self.lifecycle.is_empty() {
+            let transition = self.lifecycle.transition(
+                self.state_route(&previous_state),
+                self.state_route(&current_state),
+            );
+            for lifecycle_action in transition.actions {
+                store.dispatch(lifecycle_action);
+            }
+            for teardown in transition.deferred {
+                #[cfg(feature = "web")]
+                {
+                    let lifecycle = self.lifecycle.clone();
+                    let weak_store = self.store.clone();
+                    web::set_timeout(teardown.grace_period_ms, move || {
+                        if let (Some(store), Some(action)) =
+                            (weak_store.upgrade(), lifecycle.expire(&teardown))
+                        {
+                            store.dispatch(action);
+                        }
+                    });
+                }
+                #[cfg(not(feature = "web"))]
+                {
+                    debug!(
+                        "Tearing down the slice now rather than after {}ms: \
+                        grace periods require the web feature",
+                        teardown.grace_period_ms
+                    );
+                    if let Some(action) = self.lifecycle.expire(&teardown) {
+                        store.dispatch(action);
+                    }
+                }
             }
         }
 
-        Self {
-            route_service: router,
-            _callback: callback,
-            state_type: PhantomData,
-            action_type: PhantomData,
-            event_type: PhantomData,
-            effect_type: PhantomData,
+        if !self.subscription.is_empty() {
+            let previous_route = self.state_route(&previous_state);
+            let current_route = self.state_route(&current_state);
+            if !self.equality.same(previous_route, current_route) {
+                self.subscription.notify(previous_route, current_route);
+            }
         }
-    }
 
-    fn set_route<SRI: Into<R>>(&self, switch_route: SRI) {
-        match self.route_service.try_borrow_mut() {
-            Ok(mut router) => {
-                router.set_route(switch_route);
+        if let Some((in_scope, scopes)) = &self.route_scopes {
+            let previous_route = self.state_route(&previous_state);
+            let current_route = self.state_route(&current_state);
+            if !self.equality.same(previous_route, current_route) {
+                for scope in scopes {
+                    if let Some(transition) = ScopeTransition::between(
+                        in_scope(previous_route, scope),
+                        in_scope(current_route, scope),
+                    ) {
+                        result
+                            .events
+                            .extend(Event::scope_changed(scope, transition));
+                    }
+                }
             }
-            Err(err) => {
-                error!(
-                    "Unable to borrow route_service for RouteMiddleware: {}",
-                    err
-                );
+        }
+
+        #[cfg(feature = "federation")]
+        if !self.delegations.is_empty() {
+            let current_route = self.state_route(&current_state);
+            if !self
+                .equality
+                .same(self.state_route(&previous_state), current_route)
+            {
+                self.delegations.forward(&current_route.path());
             }
         }
-    }
 
-    fn back(&self) -> Option<R> {
-        match self.route_service.try_borrow_mut() {
-            Ok(mut router) => router.back(),
-            Err(err) => {
-                error!(
-                    "Unable to borrow route_service for RouteMiddleware: {}",
-                    err
+        #[cfg(feature = "web")]
+        if let Some((config, _)) = &self.embed {
+            let previous_route = self.state_route(&previous_state);
+            let current_route = self.state_route(&current_state);
+            if !self.equality.same(previous_route, current_route) {
+                web::post_to_parent(
+                    &EmbedMessage::RouteCommitted(current_route.path()),
+                    config.parent_origin(),
                 );
-                None
             }
         }
-    }
-}
 
-impl<R, RS, State, Action, Event, Effect> Middleware<State, Action, Event, Effect>
-    for RouteMiddleware<R, RS, State, Action, Event, Effect>
-where
-    R: SwitchRoute + 'static,
-    RS: SwitchRouteService<Route = R> + 'static,
-    Action: IsRouteAction<R> + Debug + 'static,
-    State: RouteState<R> + 'static,
-    Event: RouteEvent<R> + PartialEq + Clone + Hash + Eq + 'static,
-    Effect: 'static,
-{
-    fn on_reduce(
-        &self,
-        store: &Store<State, Action, Event, Effect>,
-        action: Option<&Action>,
-        reduce: ReduceFn<State, Action, Event, Effect>,
-    ) -> reactive_state::middleware::ReduceMiddlewareResult<Event, Effect> {
-        if let Some(action) = &action {
-            if let Some(route_action) = action.route_action() {
-                match route_action {
-                    RouteAction::Back => {
-                        self.back();
-                        return reduce(store, None);
-                    }
-                    RouteAction::ChangeRoute(route) => {
-                        self.set_route(route.clone());
-                    }
-                    RouteAction::PollBrowserRoute => match self.route_service.try_borrow_mut() {
-                        Ok(router_mut) => {
-                            let route = router_mut.get_route();
-                            return reduce(
-                                store,
-                                Some(&RouteAction::BrowserChangeRoute(route).into()),
-                            );
-                        }
-                        Err(err) => {
-                            error!("Cannot borrow mut self.router: {}", err);
-                        }
-                    },
-                    _ => {}
+        #[cfg(feature = "prefetch")]
+        if let Some(prefetch) = &self.prefetch {
+            let previous_route = self.state_route(&previous_state);
+            let current_route = self.state_route(&current_state);
+            // The route of a resolving state is a placeholder.
+            if !self.equality.same(previous_route, current_route) && !previous_state.is_resolving()
+            {
+                prefetch.record(previous_route, current_route);
+                if prefetch.is_enabled() {
+                    #[cfg(feature = "web")]
+                    {
+                        let prefetch = prefetch.clone();
+                        let route = current_route.clone();
+                        web::request_idle_callback(move || prefetch.prefetch_successors(&route));
+                    }
+                    #[cfg(not(feature = "web"))]
+                    prefetch.prefetch_successors(current_route);
+                }
+            }
+        }
+
+        let navigated = matches!(
+            route_action,
+            RouteAction::ChangeRoute(_)
+                | RouteAction::ReplaceRoute(_)
+                | RouteAction::UpdateQuery(_)
+                | RouteAction::ChangePage(_)
+                | RouteAction::NavigateByCommand(_)
+                | RouteAction::ChangeRouteMasked { .. }
+                | RouteAction::BrowserChangeRoute(..)
+                | RouteAction::PollBrowserRoute
+                | RouteAction::Reload
+                | RouteAction::SessionIdle
+                | RouteAction::UnlockSession
+        );
+        if navigated {
+            self.live_query.cancel();
+        }
+        #[cfg(feature = "oauth")]
+        if let Some(oauth_state) = &self.oauth_state {
+            oauth_state.observe(self.state_route(&current_state));
+        }
+        if let (true, Some(validation)) = (navigated, &self.query_validation) {
+            let route = self.state_route(&current_state);
+            if let Some(error_action) = validation.validate(route, &route.path()) {
+                store.dispatch(error_action);
+            }
+        }
+
+        #[cfg(feature = "web")]
+        if self.canonical_url_rewrite
+            && matches!(
+                route_action,
+                RouteAction::BrowserChangeRoute(..) | RouteAction::PollBrowserRoute
+            )
+        {
+            let route = self.state_route(&current_state);
+            let canonical = route.path();
+            match web::address_path(&self.href) {
+                Some(address)
+                    if address != canonical
+                        && self.last_canonical_rewrite.borrow().as_deref()
+                            != Some(address.as_str()) =>
+                {
+                    debug!("Rewriting address {:?} to {:?}", address, canonical);
+                    self.last_canonical_rewrite.replace(Some(canonical));
+                    self.replace_route(route.clone());
                 }
+                _ => {
+                    self.last_canonical_rewrite.replace(None);
+                }
+            }
+        }
+
+        let blocked = self.navigation_failed.replace(false);
+        if blocked {
+            self.last_failed_navigation
+                .replace(Some(route_action.clone()));
+        }
+        let panicked = self.hook_panicked.replace(false);
+        if panicked {
+            let path = self.failed_path(&current_state, route_action);
+            result.events.extend(Event::navigation_failed(&path));
+        }
+        #[cfg(feature = "audit")]
+        self.audit(route_action, || {
+            let current_route = self.state_route(&current_state);
+            if panicked {
+                AuditOutcome::Failed
+            } else if blocked {
+                AuditOutcome::Blocked
+            } else if self
+                .equality
+                .same(self.state_route(&previous_state), current_route)
+            {
+                AuditOutcome::Unchanged
+            } else {
+                AuditOutcome::Committed(current_route.path())
+            }
+        });
+
+        if let RouteAction::BrowserChangeRoute(..) = route_action {
+            if self.resolving_initial.replace(false) {
+                result.events.extend(Event::initial_navigation_complete());
             }
         }
-        reduce(store, action)
+        if self.update_pending.get() && !blocked && !panicked {
+            self.apply_pending_update(&previous_state, &current_state);
+        }
+        self.follow_maintenance(store, &previous_state);
+        result
     }
 }
 
 pub trait RouteState<SR> {
     fn get_route(&self) -> &SR;
+
+    /// Whether the store is still in its startup phase, waiting for the
+    /// initial route to be resolved with
+    /// [RouteAction::ResolveInitialRoute], during which the route
+    /// returned by [get_route()](Self::get_route) is a placeholder.
+    /// Applications can render a splash screen rather than the
+    /// placeholder route while this is true, and clear it when reducing
+    /// the first route. Defaults to `false`.
+    fn is_resolving(&self) -> bool {
+        false
+    }
 }
 
-pub trait RouteEvent<SR>
+pub trait RouteEvent<SR>: Sized
 where
     SR: SwitchRoute + 'static,
 {
     fn route_changed() -> Self;
+
+    /// Emitted when navigating to a route whose URL is `length` long,
+    /// exceeding the `max_length` of the configured
+    /// [UrlLengthGuard](url_length::UrlLengthGuard). Return `None` (the
+    /// default) to ignore.
+    fn url_too_long(_length: usize, _max_length: usize) -> Option<Self> {
+        None
+    }
+
+    /// Emitted when a navigation is blocked by the configured
+    /// [NavigationInterceptor], or by a [RouteGuard]. Return `None` (the
+    /// default) to ignore.
+    fn navigation_blocked() -> Option<Self> {
+        None
+    }
+
+    /// Emitted when the configured [NavigationInterceptor] parks a
+    /// navigation until it is confirmed, with the `payload` of
+    /// [Interception::NeedsConfirmation]. The application shows a dialog,
+    /// and passes the answer to
+    /// [RouteStore::confirm_pending_navigation()]. Return `None` (the
+    /// default) to ignore.
+    fn confirmation_required(_payload: &str) -> Option<Self> {
+        None
+    }
+
+    /// Emitted with the `result` of verifying a route arriving from the
+    /// browser which requires a signature, see
+    /// [signing::SignedRoutes]. Return `None` (the default) to ignore.
+    #[cfg(feature = "signing")]
+    fn route_signature_checked(_result: Result<(), signing::SignatureError>) -> Option<Self> {
+        None
+    }
+
+    /// Emitted when the OAuth state of a callback route arriving from
+    /// the browser fails validation, see [oauth::OAuthState]. The
+    /// callback route is not committed. Return `None` (the default) to
+    /// ignore.
+    #[cfg(feature = "oauth")]
+    fn oauth_state_failed(_error: oauth::OAuthStateError) -> Option<Self> {
+        None
+    }
+
+    /// Emitted when a navigation is abandoned because the code-split
+    /// chunk with the id `chunk_id` failed to load, see [chunks]. Return
+    /// `None` (the default) to ignore.
+    fn chunk_load_failed(_chunk_id: &str) -> Option<Self> {
+        None
+    }
+
+    /// Emitted when a navigation affects a `scope` registered with
+    /// [RouteMiddleware::with_route_scope()], see [scope]. Return `None`
+    /// (the default) to ignore.
+    fn scope_changed(_scope: &str, _transition: ScopeTransition) -> Option<Self> {
+        None
+    }
+
+    /// Emitted once the initial route has been resolved and committed,
+    /// see [RouteAction::ResolveInitialRoute]. Return `None` (the
+    /// default) to ignore.
+    fn initial_navigation_complete() -> Option<Self> {
+        None
+    }
+
+    /// Emitted when the route service fails to parse the `path` into a
+    /// route, see [RouteAction::RouteParseFailed], or when a guard,
+    /// interceptor or other hook panics while navigating to `path`.
+    /// Return `None` (the default) to ignore.
+    fn navigation_failed(_path: &str) -> Option<Self> {
+        None
+    }
+
+    /// Emitted when the route arrives from the browser by traversing
+    /// `delta` entries of the history (negative when going back), see
+    /// [RouteMiddleware::with_history_keys()]. Return `None` (the
+    /// default) to ignore.
+    #[cfg(feature = "web")]
+    fn history_traversed(_delta: isize) -> Option<Self> {
+        None
+    }
+}
+
+/// A marker for the `Event` types of applications which don't handle
+/// route events. They implement [RouteEvent] with every event ignored,
+/// and their `Default` value as [RouteEvent::route_changed()]. It is
+/// implemented for `()`, for stores without events:
+///
+/// ```ignore
+/// type AppStore = StoreRef<AppState, AppAction, (), ()>;
+///
+/// // Or, for an application with events of its own:
+/// #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+/// enum AppEvent {
+///     #[default]
+///     Changed,
+///     Saved,
+/// }
+/// impl NoRouteEvents for AppEvent {}
+/// ```
+pub trait NoRouteEvents: Default {}
+
+impl NoRouteEvents for () {}
+
+impl<SR, Event> RouteEvent<SR> for Event
+where
+    SR: SwitchRoute + 'static,
+    Event: NoRouteEvents,
+{
+    fn route_changed() -> Self {
+        Event::default()
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum RouteAction<SR> {
     Back,
+    /// Go forward in the browser's history (requires the `web`
+    /// feature).
+    Forward,
     ChangeRoute(SR),
-    BrowserChangeRoute(SR),
+    /// Navigate to the route, replacing the current history entry
+    /// rather than adding a new one. The store receives a
+    /// [RouteAction::ChangeRoute] for the route.
+    ReplaceRoute(SR),
+    /// Collapse the last `n` history entries (including the current one)
+    /// into a single entry for the route, e.g. so that the steps of a
+    /// completed wizard don't remain in the history. The middleware goes
+    /// back `n - 1` entries, ignoring the routes arriving from the
+    /// browser along the way, then replaces the entry it arrives at. The
+    /// store receives a [RouteAction::ChangeRoute] for the route. An `n`
+    /// of 0 replaces the current entry like 1, and with a
+    /// [MemoryRouteService](memory::MemoryRouteService) (or
+    /// [memory navigation](RouteMiddleware::with_memory_navigation)), an
+    /// `n` larger than the history collapses every entry up to the
+    /// current one. The browser's history doesn't tell how deep it is,
+    /// so there `n` must not exceed it.
+    ReplaceStackTop(usize, SR),
+    /// Replace the query parameters of the current route, replacing
+    /// the current history entry. The store receives a
+    /// [RouteAction::ChangeRoute] for the updated route.
+    UpdateQuery(QueryParams),
+    /// Move `n` pages forward (or back, if `n` is negative) in the
+    /// [Page](query::Page) bound to the query parameters of the current
+    /// route by the middleware's [Pagination], pushing a new history
+    /// entry. Ignored if that would move before the first page. The
+    /// store receives a [RouteAction::ChangeRoute] for the updated
+    /// route.
+    ChangePage(i32),
+    /// Replace the query parameters of the current route with those
+    /// typed so far in an input synced to the URL, see [live_query].
+    /// Once typing pauses, the store receives a
+    /// [RouteAction::ChangeRoute] for the updated route, replacing the
+    /// current history entry.
+    LiveQueryUpdate(QueryParams),
+    /// Typing paused after the [RouteAction::LiveQueryUpdate] of this
+    /// generation, dispatched by the middleware.
+    LiveQuerySettled(u64),
+    /// Commit the query typed with [RouteAction::LiveQueryUpdate],
+    /// pushing a single history entry for it (the entry of the route from
+    /// before typing started is restored). Ignored if nothing was typed.
+    CommitQuery,
+    /// Navigate to `route`, while the browser displays the URL of
+    /// `mask`. The store receives a [RouteAction::ChangeRoute] for
+    /// `route`. See [masking] for more details.
+    ChangeRouteMasked {
+        route: SR,
+        mask: SR,
+    },
+    /// The route arrived from the browser. The route service callback
+    /// dispatches it with [NavigationKind::External], which the
+    /// middleware refines (e.g. to [NavigationKind::Pop]) before it is
+    /// reduced, so reducers and guards can tell a back gesture from a
+    /// fresh deep link.
+    BrowserChangeRoute(SR, NavigationKind),
     PollBrowserRoute,
+    /// Dispatch once when starting the application, to read the initial
+    /// route from the route service, and run it through the guards,
+    /// redirects and chunk loading, before committing it as a
+    /// [RouteAction::BrowserChangeRoute].
+    /// [RouteEvent::initial_navigation_complete()] is emitted once it has
+    /// been committed. See [RouteState::is_resolving()].
+    ResolveInitialRoute,
+    /// The route service was unable to parse this path into a route, see
+    /// [RouteMiddleware::parse_failure_callback()]. Replaced by the
+    /// route of the first matching [migration](migrations), or the route
+    /// table's, or otherwise the fallback route, if one is configured
+    /// with [RouteMiddleware::with_parse_fallback()].
+    RouteParseFailed(String),
+    /// Replace the current route with the error route for the error,
+    /// stashing the current route. See [error_route] for more details.
+    NavigateToError(ErrorInfo),
+    /// Replace the error route with the route stashed by
+    /// [RouteAction::NavigateToError]. See [error_route] for more
+    /// details.
+    RetryFailedRoute,
+    /// Re-run the last navigation which failed, with the same route and
+    /// [NavigationKind], see [RouteMiddleware::last_failed_navigation()].
+    RetryLastNavigation,
+    /// Perform (`true`) or cancel (`false`) the navigation parked by
+    /// [Interception::NeedsConfirmation], see [interceptor].
+    ConfirmPendingNavigation(bool),
+    /// Navigate away from the application to the specified url, with a
+    /// full page load (requires the `web` feature).
+    ExternalNavigate(String),
+    /// Reduce the current route again as if it had just arrived from the
+    /// browser, without modifying the history.
+    Reload,
+    /// Replace the URL of the route service with the route of the state,
+    /// without navigating or modifying the history, e.g. after devtools
+    /// restored a previous state. See the `devtools` module for more
+    /// details.
+    SyncUrl,
+    /// Stop (`false`) or resume (`true`) updating the URL of the route
+    /// service. While stopped, navigations update the state and an
+    /// in-memory history (see [MemoryRouteService]), e.g. for preview
+    /// panes, multi-step modals or screenshot tests. When resumed, the URL
+    /// is synced to the route of the state (see [RouteAction::SyncUrl]).
+    /// Ignored with [RouteMiddleware::with_memory_navigation()].
+    SetUrlSync(bool),
+    /// Whether an update of the application is waiting to be applied
+    /// after the next navigation, see [update].
+    SetUpdatePending(bool),
+    /// The browser went online (`true`) or offline, see [offline]. If
+    /// the offline route is displayed when it goes online, it is
+    /// replaced with the route which was redirected to it, and the store
+    /// receives a [RouteAction::ChangeRoute] for that route.
+    ConnectivityChanged(bool),
+    /// The session idled out, see [idle]. The current route is replaced
+    /// with the lock route, and the configured action is dispatched.
+    SessionIdle,
+    /// The user unlocked the session, see [idle]. The lock route is
+    /// replaced with the route which was interrupted, and the store
+    /// receives a [RouteAction::ChangeRoute] for that route.
+    UnlockSession,
+    /// Reload the entire page (requires the `web` feature).
+    HardReload,
+    /// Open the route in a new browser tab (requires the `web` feature).
+    OpenInNewTab(SR),
+    /// Share the link to the route of the state with the Web Share API
+    /// (`navigator.share()`), titled with the route's
+    /// [title](RouteMetadata::title), falling back to copying the link
+    /// to the clipboard where it isn't available (requires the `web`
+    /// feature). See [RouteMiddleware::with_share_link()].
+    ShareCurrentRoute,
+    /// Navigate to the route of the command registered in the
+    /// [RouteRegistry] with this id, see
+    /// [RouteRegistry::register_command()]. The store receives a
+    /// [RouteAction::ChangeRoute] for the route.
+    NavigateByCommand(String),
+    /// The code-split chunk with this id has been loaded, see [chunks].
+    ChunkLoaded(String),
+    /// The code-split chunk with this id failed to load, see [chunks].
+    ChunkLoadFailed(String),
+    /// A custom router verb, translated by the handler registered with
+    /// [RouteMiddleware::with_command_handler()]. See [command] for more
+    /// details.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(CustomCommand<SR>),
+    /// An action for one of several routers in the store, see [router]
+    /// for more details.
+    Routed {
+        router: RouterId,
+        action: Box<RouteAction<SR>>,
+    },
 }
 
+impl<SR> RouteAction<SR> {
+    /// The name of the variant of this action, e.g. `"ChangeRoute"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RouteAction::Back => "Back",
+            RouteAction::Forward => "Forward",
+            RouteAction::ChangeRoute(_) => "ChangeRoute",
+            RouteAction::ReplaceRoute(_) => "ReplaceRoute",
+            RouteAction::ReplaceStackTop(..) => "ReplaceStackTop",
+            RouteAction::UpdateQuery(_) => "UpdateQuery",
+            RouteAction::ChangePage(_) => "ChangePage",
+            RouteAction::LiveQueryUpdate(_) => "LiveQueryUpdate",
+            RouteAction::LiveQuerySettled(_) => "LiveQuerySettled",
+            RouteAction::CommitQuery => "CommitQuery",
+            RouteAction::ChangeRouteMasked { .. } => "ChangeRouteMasked",
+            RouteAction::BrowserChangeRoute(..) => "BrowserChangeRoute",
+            RouteAction::PollBrowserRoute => "PollBrowserRoute",
+            RouteAction::ResolveInitialRoute => "ResolveInitialRoute",
+            RouteAction::RouteParseFailed(_) => "RouteParseFailed",
+            RouteAction::NavigateToError(_) => "NavigateToError",
+            RouteAction::RetryFailedRoute => "RetryFailedRoute",
+            RouteAction::RetryLastNavigation => "RetryLastNavigation",
+            RouteAction::ConfirmPendingNavigation(_) => "ConfirmPendingNavigation",
+            RouteAction::ExternalNavigate(_) => "ExternalNavigate",
+            RouteAction::Reload => "Reload",
+            RouteAction::SyncUrl => "SyncUrl",
+            RouteAction::SetUrlSync(_) => "SetUrlSync",
+            RouteAction::SetUpdatePending(_) => "SetUpdatePending",
+            RouteAction::ConnectivityChanged(_) => "ConnectivityChanged",
+            RouteAction::SessionIdle => "SessionIdle",
+            RouteAction::UnlockSession => "UnlockSession",
+            RouteAction::HardReload => "HardReload",
+            RouteAction::OpenInNewTab(_) => "OpenInNewTab",
+            RouteAction::ShareCurrentRoute => "ShareCurrentRoute",
+            RouteAction::NavigateByCommand(_) => "NavigateByCommand",
+            RouteAction::ChunkLoaded(_) => "ChunkLoaded",
+            RouteAction::ChunkLoadFailed(_) => "ChunkLoadFailed",
+            RouteAction::Custom(_) => "Custom",
+            RouteAction::Routed { .. } => "Routed",
+        }
+    }
+
+    /// A [RouteAction::Custom] action for the `command`.
+    pub fn custom<C: RouteCommand<SR>>(command: C) -> Self {
+        RouteAction::Custom(CustomCommand::new(command))
+    }
+}
+
+/// Displays routes by their paths, e.g. `ChangeRoute(/items/5)`, for
+/// readable logs.
 impl<SR> Display for RouteAction<SR>
 where
-    SR: Debug,
+    SR: SwitchRoute,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             RouteAction::Back => write!(f, "Back"),
-            RouteAction::ChangeRoute(route) => write!(f, "ChangeRoute({:?})", route),
-            RouteAction::BrowserChangeRoute(route) => write!(f, "BrowserChangeRoute({:?})", route),
+            RouteAction::Forward => write!(f, "Forward"),
+            RouteAction::ChangeRoute(route) => write!(f, "ChangeRoute({})", route.path()),
+            RouteAction::ReplaceRoute(route) => write!(f, "ReplaceRoute({})", route.path()),
+            RouteAction::ReplaceStackTop(n, route) => {
+                write!(f, "ReplaceStackTop({}, {})", n, route.path())
+            }
+            RouteAction::UpdateQuery(params) => write!(f, "UpdateQuery(?{})", params),
+            RouteAction::ChangePage(n) => write!(f, "ChangePage({})", n),
+            RouteAction::LiveQueryUpdate(params) => write!(f, "LiveQueryUpdate(?{})", params),
+            RouteAction::LiveQuerySettled(generation) => {
+                write!(f, "LiveQuerySettled({})", generation)
+            }
+            RouteAction::CommitQuery => write!(f, "CommitQuery"),
+            RouteAction::ChangeRouteMasked { route, mask } => {
+                write!(f, "ChangeRouteMasked({} as {})", route.path(), mask.path())
+            }
+            RouteAction::BrowserChangeRoute(route, kind) => {
+                write!(f, "BrowserChangeRoute({}, {:?})", route.path(), kind)
+            }
             RouteAction::PollBrowserRoute => write!(f, "PollBrowserRoute"),
+            RouteAction::ResolveInitialRoute => write!(f, "ResolveInitialRoute"),
+            RouteAction::RouteParseFailed(path) => write!(f, "RouteParseFailed({})", path),
+            RouteAction::NavigateToError(info) => write!(f, "NavigateToError({})", info),
+            RouteAction::RetryFailedRoute => write!(f, "RetryFailedRoute"),
+            RouteAction::RetryLastNavigation => write!(f, "RetryLastNavigation"),
+            RouteAction::ConfirmPendingNavigation(confirmed) => {
+                write!(f, "ConfirmPendingNavigation({})", confirmed)
+            }
+            RouteAction::ExternalNavigate(url) => write!(f, "ExternalNavigate({})", url),
+            RouteAction::Reload => write!(f, "Reload"),
+            RouteAction::SyncUrl => write!(f, "SyncUrl"),
+            RouteAction::SetUrlSync(sync) => write!(f, "SetUrlSync({})", sync),
+            RouteAction::SetUpdatePending(pending) => write!(f, "SetUpdatePending({})", pending),
+            RouteAction::ConnectivityChanged(online) => {
+                write!(f, "ConnectivityChanged({})", online)
+            }
+            RouteAction::SessionIdle => write!(f, "SessionIdle"),
+            RouteAction::UnlockSession => write!(f, "UnlockSession"),
+            RouteAction::HardReload => write!(f, "HardReload"),
+            RouteAction::OpenInNewTab(route) => write!(f, "OpenInNewTab({})", route.path()),
+            RouteAction::ShareCurrentRoute => write!(f, "ShareCurrentRoute"),
+            RouteAction::NavigateByCommand(id) => write!(f, "NavigateByCommand({})", id),
+            RouteAction::ChunkLoaded(chunk_id) => write!(f, "ChunkLoaded({})", chunk_id),
+            RouteAction::ChunkLoadFailed(chunk_id) => write!(f, "ChunkLoadFailed({})", chunk_id),
+            RouteAction::Custom(command) => write!(f, "Custom({:?})", command),
+            RouteAction::Routed { router, action } => write!(f, "{} for {}", action, router),
         }
     }
 }
@@ -173,10 +3457,102 @@ where
     SR: SwitchRoute + 'static,
 {
     fn route_action(&self) -> Option<&RouteAction<SR>>;
+
+    /// Take ownership of the inner [RouteAction], if this is a route
+    /// action. The default implementation clones it, implement this to
+    /// move it out instead.
+    fn into_route_action(self) -> Option<RouteAction<SR>> {
+        self.route_action().cloned()
+    }
+}
+
+impl<SR> IsRouteAction<SR> for RouteAction<SR>
+where
+    SR: SwitchRoute + 'static,
+{
+    fn route_action(&self) -> Option<&RouteAction<SR>> {
+        Some(self)
+    }
+
+    fn into_route_action(self) -> Option<RouteAction<SR>> {
+        Some(self)
+    }
 }
 
 pub trait RouteStore<SR> {
     fn change_route<R: Into<SR>>(&self, route: R);
+    /// Navigate to `route`, replacing the current history entry, see
+    /// [RouteAction::ReplaceRoute].
+    fn replace_route<R: Into<SR>>(&self, route: R);
+    /// Collapse the last `n` history entries into a single entry for
+    /// `route`, see [RouteAction::ReplaceStackTop].
+    fn replace_stack_top<R: Into<SR>>(&self, n: usize, route: R);
+    /// Replace the query parameters of the current route, see
+    /// [RouteAction::UpdateQuery].
+    fn update_query(&self, params: QueryParams);
+    /// Move to the next page of the current list route, see
+    /// [RouteAction::ChangePage].
+    fn next_page(&self);
+    /// Move to the previous page of the current list route, see
+    /// [RouteAction::ChangePage].
+    fn prev_page(&self);
+    /// Update the query parameters of the current route as the user
+    /// types, see [RouteAction::LiveQueryUpdate].
+    fn live_query_update(&self, params: QueryParams);
+    /// Commit the query typed with
+    /// [live_query_update()](RouteStore::live_query_update), see
+    /// [RouteAction::CommitQuery].
+    fn commit_query(&self);
+    /// Navigate to `route` while displaying the URL of `mask`, see
+    /// [RouteAction::ChangeRouteMasked].
+    fn change_route_masked<R: Into<SR>, M: Into<SR>>(&self, route: R, mask: M);
+    /// Navigate to the route of the command with the specified `id`, see
+    /// [RouteAction::NavigateByCommand].
+    fn navigate_by_command<S: Into<String>>(&self, id: S);
+    /// Navigate away from the application to the specified `url`, see
+    /// [RouteAction::ExternalNavigate].
+    fn external_navigate<S: Into<String>>(&self, url: S);
+    /// Open the `route` in a new browser tab, see
+    /// [RouteAction::OpenInNewTab].
+    fn open_in_new_tab<R: Into<SR>>(&self, route: R);
+    /// Replace the current route with the error route, see
+    /// [RouteAction::NavigateToError].
+    fn navigate_to_error(&self, info: ErrorInfo);
+    /// Navigate back to the route replaced by the error route, see
+    /// [RouteAction::RetryFailedRoute].
+    fn retry_failed_route(&self);
+    /// Re-run the last failed navigation, see
+    /// [RouteAction::RetryLastNavigation].
+    fn retry_last_navigation(&self);
+    /// Perform or cancel the navigation waiting for confirmation, see
+    /// [RouteAction::ConfirmPendingNavigation].
+    fn confirm_pending_navigation(&self, confirmed: bool);
+    /// Replace the URL with the route of the state, see
+    /// [RouteAction::SyncUrl].
+    fn sync_url(&self);
+    /// Stop or resume updating the URL, see [RouteAction::SetUrlSync].
+    fn set_url_sync(&self, sync: bool);
+    /// The absolute link to the route of the state, see [share].
+    fn share_link(&self, share: &ShareLink) -> String;
+    /// Share the link to the route of the state, see
+    /// [RouteAction::ShareCurrentRoute].
+    fn share_current_route(&self);
+    /// Record that the application went online (`true`) or offline, see
+    /// [RouteAction::ConnectivityChanged].
+    fn connectivity_changed(&self, online: bool);
+    /// Lock the session after a period of inactivity, see
+    /// [RouteAction::SessionIdle].
+    fn session_idle(&self);
+    /// Unlock the session, resuming the interrupted route, see
+    /// [RouteAction::UnlockSession].
+    fn unlock_session(&self);
+    /// Record whether an update is waiting to be applied, see
+    /// [RouteAction::SetUpdatePending].
+    fn set_update_pending(&self, pending: bool);
+    /// Navigate to the route of a link pasted by the user, if it is a
+    /// link to the application, returning the route. See
+    /// [ShareLink::parse_link()].
+    fn navigate_to_link(&self, share: &ShareLink, pasted: &str) -> Result<SR, LinkError>;
 }
 
 impl<SR, State, Action, Event, Effect> RouteStore<SR> for Store<State, Action, Event, Effect>
@@ -189,4 +3565,105 @@ where
     fn change_route<R: Into<SR>>(&self, route: R) {
         self.dispatch(RouteAction::ChangeRoute(route.into()));
     }
+
+    fn replace_route<R: Into<SR>>(&self, route: R) {
+        self.dispatch(RouteAction::ReplaceRoute(route.into()));
+    }
+
+    fn replace_stack_top<R: Into<SR>>(&self, n: usize, route: R) {
+        self.dispatch(RouteAction::ReplaceStackTop(n, route.into()));
+    }
+
+    fn update_query(&self, params: QueryParams) {
+        self.dispatch(RouteAction::UpdateQuery(params));
+    }
+
+    fn next_page(&self) {
+        self.dispatch(RouteAction::ChangePage(1));
+    }
+
+    fn prev_page(&self) {
+        self.dispatch(RouteAction::ChangePage(-1));
+    }
+
+    fn live_query_update(&self, params: QueryParams) {
+        self.dispatch(RouteAction::LiveQueryUpdate(params));
+    }
+
+    fn commit_query(&self) {
+        self.dispatch(RouteAction::CommitQuery);
+    }
+
+    fn change_route_masked<R: Into<SR>, M: Into<SR>>(&self, route: R, mask: M) {
+        self.dispatch(RouteAction::ChangeRouteMasked {
+            route: route.into(),
+            mask: mask.into(),
+        });
+    }
+
+    fn navigate_by_command<S: Into<String>>(&self, id: S) {
+        self.dispatch(RouteAction::NavigateByCommand(id.into()));
+    }
+
+    fn external_navigate<S: Into<String>>(&self, url: S) {
+        self.dispatch(RouteAction::ExternalNavigate(url.into()));
+    }
+
+    fn open_in_new_tab<R: Into<SR>>(&self, route: R) {
+        self.dispatch(RouteAction::OpenInNewTab(route.into()));
+    }
+
+    fn navigate_to_error(&self, info: ErrorInfo) {
+        self.dispatch(RouteAction::NavigateToError(info));
+    }
+
+    fn retry_failed_route(&self) {
+        self.dispatch(RouteAction::RetryFailedRoute);
+    }
+
+    fn retry_last_navigation(&self) {
+        self.dispatch(RouteAction::RetryLastNavigation);
+    }
+
+    fn confirm_pending_navigation(&self, confirmed: bool) {
+        self.dispatch(RouteAction::ConfirmPendingNavigation(confirmed));
+    }
+
+    fn sync_url(&self) {
+        self.dispatch(RouteAction::SyncUrl);
+    }
+
+    fn set_url_sync(&self, sync: bool) {
+        self.dispatch(RouteAction::SetUrlSync(sync));
+    }
+
+    fn share_link(&self, share: &ShareLink) -> String {
+        share.link(self.state().get_route())
+    }
+
+    fn share_current_route(&self) {
+        self.dispatch(RouteAction::ShareCurrentRoute);
+    }
+
+    fn connectivity_changed(&self, online: bool) {
+        self.dispatch(RouteAction::ConnectivityChanged(online));
+    }
+
+    fn session_idle(&self) {
+        self.dispatch(RouteAction::SessionIdle);
+    }
+
+    fn unlock_session(&self) {
+        self.dispatch(RouteAction::UnlockSession);
+    }
+
+    fn set_update_pending(&self, pending: bool) {
+        self.dispatch(RouteAction::SetUpdatePending(pending));
+    }
+
+    fn navigate_to_link(&self, share: &ShareLink, pasted: &str) -> Result<SR, LinkError> {
+        let route: SR = share.parse_link(pasted)?;
+        self.dispatch(RouteAction::ChangeRoute(route.clone()));
+        Ok(route)
+    }
 }
@@ -0,0 +1,236 @@
+//! Actions dispatched when entering or leaving routes.
+//!
+//! Unlike callbacks, the entry/exit behaviour is declared as plain
+//! actions, so it remains visible to logging middleware and can be
+//! replayed like any other action. After each navigation is committed
+//! to the store, the [RouteMiddleware](crate::RouteMiddleware)
+//! dispatches the exit actions for the routes which were left, followed
+//! by the entry actions for the routes which were entered.
+//!
+//! State slices can also be declared to belong to a route subtree with
+//! [RouteLifecycle::slice()], so that per-page state is initialized on
+//! entering the subtree, and torn down on leaving it rather than
+//! accumulating over long sessions. The teardown can be deferred by a
+//! grace period (requires the `web` feature, otherwise it is immediate),
+//! and is cancelled if the subtree is re-entered in the meantime.
+
+use std::cell::{Cell, RefCell};
+
+type MatcherFn<R> = dyn Fn(&R) -> bool;
+
+struct LifecycleEntry<R, Action> {
+    matcher: Box<MatcherFn<R>>,
+    on_enter: Vec<Action>,
+    on_exit: Vec<Action>,
+}
+
+struct Slice<R, Action> {
+    matcher: Box<MatcherFn<R>>,
+    init: Action,
+    teardown: Action,
+    grace_period_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SliceState {
+    Inactive,
+    Active,
+    /// Left, the teardown scheduled with the `generation` is pending.
+    TearingDown {
+        generation: u64,
+    },
+}
+
+/// A slice teardown to dispatch after its grace period, with
+/// [RouteLifecycle::expire()], unless the slice is re-entered.
+pub(crate) struct DeferredTeardown {
+    pub slice: usize,
+    pub generation: u64,
+    pub grace_period_ms: u32,
+}
+
+/// The result of [RouteLifecycle::transition()].
+pub(crate) struct Transition<Action> {
+    /// The actions to dispatch now.
+    pub actions: Vec<Action>,
+    pub deferred: Vec<DeferredTeardown>,
+}
+
+/// A declarative table of entry and exit actions.
+pub struct RouteLifecycle<R, Action> {
+    entries: Vec<LifecycleEntry<R, Action>>,
+    slices: Vec<Slice<R, Action>>,
+    slice_states: RefCell<Vec<SliceState>>,
+    teardown_generation: Cell<u64>,
+}
+
+impl<R, Action> RouteLifecycle<R, Action>
+where
+    Action: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            slices: Vec::new(),
+            slice_states: RefCell::new(Vec::new()),
+            teardown_generation: Cell::new(0),
+        }
+    }
+
+    /// Dispatch `on_enter` when navigating from a route not accepted by
+    /// `matcher` to one which is, and `on_exit` for the reverse.
+    pub fn route<F>(mut self, matcher: F, on_enter: Vec<Action>, on_exit: Vec<Action>) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.entries.push(LifecycleEntry {
+            matcher: Box::new(matcher),
+            on_enter,
+            on_exit,
+        });
+        self
+    }
+
+    /// Dispatch `action` when entering routes accepted by `matcher`.
+    pub fn on_enter<F>(self, matcher: F, action: Action) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.route(matcher, vec![action], Vec::new())
+    }
+
+    /// Dispatch `action` when leaving routes accepted by `matcher`.
+    pub fn on_exit<F>(self, matcher: F, action: Action) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.route(matcher, Vec::new(), vec![action])
+    }
+
+    /// Declare a state slice belonging to the routes accepted by
+    /// `matcher`: `init` is dispatched when entering them, and `teardown`
+    /// when leaving them.
+    pub fn slice<F>(self, matcher: F, init: Action, teardown: Action) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.add_slice(matcher, init, teardown, None)
+    }
+
+    /// Like [slice()](Self::slice), but `teardown` is only dispatched
+    /// once `grace_period_ms` has elapsed without the routes being
+    /// re-entered, so the state survives a quick round trip.
+    pub fn slice_with_grace_period<F>(
+        self,
+        matcher: F,
+        init: Action,
+        teardown: Action,
+        grace_period_ms: u32,
+    ) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.add_slice(matcher, init, teardown, Some(grace_period_ms))
+    }
+
+    fn add_slice<F>(
+        mut self,
+        matcher: F,
+        init: Action,
+        teardown: Action,
+        grace_period_ms: Option<u32>,
+    ) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.slices.push(Slice {
+            matcher: Box::new(matcher),
+            init,
+            teardown,
+            grace_period_ms,
+        });
+        self.slice_states.borrow_mut().push(SliceState::Inactive);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.slices.is_empty()
+    }
+
+    /// The actions to dispatch for a navigation from `previous` to
+    /// `current`: all exit actions and slice teardowns, followed by all
+    /// entry actions and slice inits.
+    pub(crate) fn transition(&self, previous: &R, current: &R) -> Transition<Action> {
+        let mut exits = Vec::new();
+        let mut enters = Vec::new();
+        for entry in &self.entries {
+            let was = (entry.matcher)(previous);
+            let is = (entry.matcher)(current);
+            if was && !is {
+                exits.extend(entry.on_exit.iter().cloned());
+            } else if !was && is {
+                enters.extend(entry.on_enter.iter().cloned());
+            }
+        }
+
+        let mut deferred = Vec::new();
+        let mut states = self.slice_states.borrow_mut();
+        for (index, slice) in self.slices.iter().enumerate() {
+            let is = (slice.matcher)(current);
+            match (states[index], is) {
+                (SliceState::Inactive, true) => {
+                    enters.push(slice.init.clone());
+                    states[index] = SliceState::Active;
+                }
+                (SliceState::TearingDown { .. }, true) => states[index] = SliceState::Active,
+                (SliceState::Active, false) => match slice.grace_period_ms {
+                    Some(grace_period_ms) => {
+                        let generation = self.teardown_generation.get() + 1;
+                        self.teardown_generation.set(generation);
+                        states[index] = SliceState::TearingDown { generation };
+                        deferred.push(DeferredTeardown {
+                            slice: index,
+                            generation,
+                            grace_period_ms,
+                        });
+                    }
+                    None => {
+                        exits.push(slice.teardown.clone());
+                        states[index] = SliceState::Inactive;
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        exits.extend(enters);
+        Transition {
+            actions: exits,
+            deferred,
+        }
+    }
+
+    /// The teardown action of a [DeferredTeardown] whose grace period
+    /// has elapsed, if the slice hasn't been re-entered since.
+    pub(crate) fn expire(&self, teardown: &DeferredTeardown) -> Option<Action> {
+        let mut states = self.slice_states.borrow_mut();
+        if states[teardown.slice]
+            != (SliceState::TearingDown {
+                generation: teardown.generation,
+            })
+        {
+            return None;
+        }
+        states[teardown.slice] = SliceState::Inactive;
+        Some(self.slices[teardown.slice].teardown.clone())
+    }
+}
+
+impl<R, Action> Default for RouteLifecycle<R, Action>
+where
+    Action: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,118 @@
+//! Query parameters which follow an input as the user types, such as a
+//! search box synced to `?q=`. Updating the query on every keystroke
+//! with [RouteAction::UpdateQuery](crate::RouteAction::UpdateQuery)
+//! would navigate (and run the search) once per keystroke, and pushing
+//! would leave a history entry per keystroke.
+//!
+//! Instead, the input dispatches
+//! [RouteAction::LiveQueryUpdate](crate::RouteAction::LiveQueryUpdate)
+//! (see [RouteStore::live_query_update()](crate::RouteStore::live_query_update))
+//! as the user types. The middleware debounces them (with the `web`
+//! feature, see
+//! [with_live_query_debounce()](crate::RouteMiddleware::with_live_query_debounce)),
+//! and reduces the latest as a replace navigation once typing pauses.
+//! When the user commits the query (on enter or blur),
+//! [RouteAction::CommitQuery](crate::RouteAction::CommitQuery) restores
+//! the history entry of the route from before typing started, and
+//! pushes a single entry for the committed query, so that going back
+//! returns to the route before the search:
+//!
+//! ```ignore
+//! // oninput
+//! let mut params = QueryParams::new();
+//! params.insert("q", input.value());
+//! store.live_query_update(params);
+//! // onkeydown (enter), onblur
+//! store.commit_query();
+//! ```
+//!
+//! Any other navigation while typing abandons the live query.
+
+use crate::query::QueryParams;
+use std::cell::{Cell, RefCell};
+
+/// How long typing has to pause before the query is navigated to, by
+/// default.
+pub const DEFAULT_DEBOUNCE_MS: u32 = 300;
+
+/// The state of the live query being typed, see the
+/// [module documentation](self).
+pub(crate) struct LiveQuery<R> {
+    #[cfg(feature = "web")]
+    debounce_ms: u32,
+    /// The route from before typing started.
+    origin: RefCell<Option<R>>,
+    /// The latest query, waiting for typing to pause.
+    pending: RefCell<Option<QueryParams>>,
+    generation: Cell<u64>,
+}
+
+impl<R> LiveQuery<R>
+where
+    R: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "web")]
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            origin: RefCell::new(None),
+            pending: RefCell::new(None),
+            generation: Cell::new(0),
+        }
+    }
+
+    #[cfg(feature = "web")]
+    pub fn with_debounce(mut self, debounce_ms: u32) -> Self {
+        self.debounce_ms = debounce_ms;
+        self
+    }
+
+    #[cfg(feature = "web")]
+    pub fn debounce_ms(&self) -> u32 {
+        self.debounce_ms
+    }
+
+    /// Remember `current` as the route from before typing, unless
+    /// typing has already started.
+    pub fn begin(&self, current: &R) {
+        let mut origin = self.origin.borrow_mut();
+        if origin.is_none() {
+            *origin = Some(current.clone());
+        }
+    }
+
+    /// Wait for typing to pause before navigating to `params`, returning
+    /// the generation which [settle()](Self::settle) expects.
+    pub fn defer(&self, params: QueryParams) -> u64 {
+        self.pending.replace(Some(params));
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        generation
+    }
+
+    /// The query to navigate to if typing has paused since the update of
+    /// `generation`.
+    pub fn settle(&self, generation: u64) -> Option<QueryParams> {
+        if generation == self.generation.get() {
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+
+    /// The route from before typing started, and the query still waiting
+    /// for typing to pause, if the user was typing.
+    pub fn commit(&self) -> Option<(R, Option<QueryParams>)> {
+        let origin = self.origin.take()?;
+        self.generation.set(self.generation.get() + 1);
+        Some((origin, self.pending.take()))
+    }
+
+    /// Abandon the live query.
+    pub fn cancel(&self) {
+        if self.origin.take().is_some() {
+            self.pending.replace(None);
+            self.generation.set(self.generation.get() + 1);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! The logging backend of the crate, selected with features:
+//!
+//! + `log` (the default): messages are logged with the
+//!   [log](https://crates.io/crates/log) crate.
+//! + `tracing`: messages are emitted as
+//!   [tracing](https://crates.io/crates/tracing) events instead (takes
+//!   precedence over `log`).
+//! + neither: messages are discarded, so that minimal WASM bundles don't
+//!   include any logging machinery.
+//!
+//! The internal failures of the [RouteMiddleware](crate::RouteMiddleware)
+//! are passed to its [ErrorSink], which logs them with the selected
+//! backend by default, and can be replaced with
+//! [with_error_sink()](crate::RouteMiddleware::with_error_sink) (e.g. to
+//! write them to the console in a build without logging).
+
+use std::fmt;
+
+#[cfg(feature = "tracing")]
+macro_rules! error {
+    ($($arg:tt)+) => { ::tracing::error!($($arg)+) };
+}
+#[cfg(all(feature = "log", not(feature = "tracing")))]
+macro_rules! error {
+    ($($arg:tt)+) => { ::log::error!($($arg)+) };
+}
+#[cfg(not(any(feature = "log", feature = "tracing")))]
+macro_rules! error {
+    ($($arg:tt)+) => {{ let _ = format_args!($($arg)+); }};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! warning {
+    ($($arg:tt)+) => { ::tracing::warn!($($arg)+) };
+}
+#[cfg(all(feature = "log", not(feature = "tracing")))]
+macro_rules! warning {
+    ($($arg:tt)+) => { ::log::warn!($($arg)+) };
+}
+#[cfg(not(any(feature = "log", feature = "tracing")))]
+macro_rules! warning {
+    ($($arg:tt)+) => {{ let _ = format_args!($($arg)+); }};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! debug {
+    ($($arg:tt)+) => { ::tracing::debug!($($arg)+) };
+}
+#[cfg(all(feature = "log", not(feature = "tracing")))]
+macro_rules! debug {
+    ($($arg:tt)+) => { ::log::debug!($($arg)+) };
+}
+#[cfg(not(any(feature = "log", feature = "tracing")))]
+macro_rules! debug {
+    ($($arg:tt)+) => {{ let _ = format_args!($($arg)+); }};
+}
+
+#[allow(unused_imports)]
+pub(crate) use {debug, error, warning};
+
+/// Receives the internal failures of the
+/// [RouteMiddleware](crate::RouteMiddleware), see the
+/// [module documentation](self).
+pub trait ErrorSink {
+    fn error(&self, message: fmt::Arguments<'_>);
+}
+
+impl<F> ErrorSink for F
+where
+    F: Fn(fmt::Arguments<'_>),
+{
+    fn error(&self, message: fmt::Arguments<'_>) {
+        (self)(message)
+    }
+}
+
+/// The default [ErrorSink], which logs with the backend selected by the
+/// features.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogErrors;
+
+impl ErrorSink for LogErrors {
+    fn error(&self, message: fmt::Arguments<'_>) {
+        error!("{}", message);
+    }
+}
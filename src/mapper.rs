@@ -0,0 +1,92 @@
+//! Mapping between the store's actions and [RouteAction]s, as an
+//! alternative to implementing [IsRouteAction] for the store's action
+//! type.
+//!
+//! In large applications the route actions are often nested several
+//! enum layers deep (e.g. `AppAction::Ui(UiAction::Nav(RouteAction))`),
+//! where `From<RouteAction>` for the outermost action is awkward to
+//! implement, or belongs to another crate. An [ActionMapper] is built
+//! from two closures instead, which wrap a [RouteAction] into the
+//! store's action, and unwrap it again, and is passed to
+//! [RouteMiddleware::new_with_action_mapper()](crate::RouteMiddleware::new_with_action_mapper):
+//!
+//! ```ignore
+//! let actions = ActionMapper::new(
+//!     |action| AppAction::Ui(UiAction::Nav(action)),
+//!     |action: &AppAction| match action {
+//!         AppAction::Ui(UiAction::Nav(action)) => Some(action),
+//!         _ => None,
+//!     },
+//! );
+//! let middleware =
+//!     RouteMiddleware::new_with_action_mapper(route_service, store.clone(), actions.clone());
+//!
+//! store.dispatch(actions.wrap(RouteAction::ChangeRoute(AppRoute::Home)));
+//! ```
+//!
+//! The [RouteStore](crate::RouteStore) helpers require
+//! [IsRouteAction], so with a mapper the route actions are dispatched
+//! wrapped with [wrap()](ActionMapper::wrap), as above.
+
+use crate::{IsRouteAction, RouteAction};
+use std::rc::Rc;
+use switch_router::SwitchRoute;
+
+type WrapFn<R, Action> = dyn Fn(RouteAction<R>) -> Action;
+type TryUnwrapFn<R, Action> = dyn for<'a> Fn(&'a Action) -> Option<&'a RouteAction<R>>;
+
+/// Wraps [RouteAction]s into the store's actions and unwraps them, see
+/// the [module documentation](self). Clones share the closures.
+pub struct ActionMapper<R, Action> {
+    wrap: Rc<WrapFn<R, Action>>,
+    try_unwrap: Rc<TryUnwrapFn<R, Action>>,
+}
+
+impl<R, Action> Clone for ActionMapper<R, Action> {
+    fn clone(&self) -> Self {
+        Self {
+            wrap: self.wrap.clone(),
+            try_unwrap: self.try_unwrap.clone(),
+        }
+    }
+}
+
+impl<R, Action> ActionMapper<R, Action> {
+    /// Create a new [ActionMapper], where `wrap` wraps a route action
+    /// into the store's action, and `try_unwrap` returns the route
+    /// action wrapped in one of the store's actions, or `None` if it is
+    /// another action.
+    pub fn new<W, U>(wrap: W, try_unwrap: U) -> Self
+    where
+        W: Fn(RouteAction<R>) -> Action + 'static,
+        U: Fn(&Action) -> Option<&RouteAction<R>> + 'static,
+    {
+        Self {
+            wrap: Rc::new(wrap),
+            try_unwrap: Rc::new(try_unwrap),
+        }
+    }
+
+    /// The mapper for an action type implementing [IsRouteAction].
+    pub fn from_trait() -> Self
+    where
+        R: SwitchRoute + 'static,
+        Action: IsRouteAction<R> + 'static,
+    {
+        Self::new(Action::from, Action::route_action)
+    }
+
+    pub fn wrap(&self, action: RouteAction<R>) -> Action {
+        (self.wrap)(action)
+    }
+
+    pub fn try_unwrap<'a>(&self, action: &'a Action) -> Option<&'a RouteAction<R>> {
+        (self.try_unwrap)(action)
+    }
+
+    /// The `wrap` closure, for dispatching route actions from callbacks.
+    pub(crate) fn wrapper(&self) -> impl Fn(RouteAction<R>) -> Action {
+        let wrap = self.wrap.clone();
+        move |action| wrap(action)
+    }
+}
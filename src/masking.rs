@@ -0,0 +1,123 @@
+//! Route masking: navigating to a route while the browser displays the
+//! URL of a different route (the mask). For example a photo opened in a
+//! modal over a feed can display `/photo/5`, while the store keeps the
+//! feed route.
+//!
+//! The pairs of masks and routes are tracked by the
+//! [RouteMiddleware](crate::RouteMiddleware) so that when the mask URL
+//! arrives from the browser again (back/forward or reload), the masked
+//! route is restored. To survive a reload, the pairs are also written to
+//! a [KeyValueStorage], with the [version](crate::storage) of their
+//! format.
+
+use crate::storage::{self, KeyValueStorage, MemoryStorage};
+use std::{cell::RefCell, collections::HashMap};
+use switch_router::SwitchRoute;
+
+const KEY_PREFIX: &str = "switch-router-middleware:mask:";
+
+/// The format versions of the persisted routes which can be read. Version
+/// `0` (an unversioned path) is read like version `1`.
+const SUPPORTED_VERSIONS: &[u32] = &[0, 1];
+
+/// The tracked pairs of mask and masked route.
+pub struct RouteMasks<R> {
+    masks: RefCell<HashMap<String, R>>,
+    storage: Box<dyn KeyValueStorage>,
+}
+
+impl<R> RouteMasks<R>
+where
+    R: SwitchRoute,
+{
+    pub fn new() -> Self {
+        Self::with_storage(MemoryStorage::new())
+    }
+
+    /// Persist the masks in `storage`, e.g.
+    /// [SessionStorage](crate::web::SessionStorage).
+    pub fn with_storage<S: KeyValueStorage + 'static>(storage: S) -> Self {
+        Self {
+            masks: RefCell::new(HashMap::new()),
+            storage: Box::new(storage),
+        }
+    }
+
+    /// Track that `mask` is being displayed for `route`.
+    pub(crate) fn insert(&self, mask: &R, route: &R) {
+        let mask_path = mask.path();
+        self.storage.set(
+            &format!("{}{}", KEY_PREFIX, mask_path),
+            &storage::versioned(&route.path()),
+        );
+        self.masks.borrow_mut().insert(mask_path, route.clone());
+    }
+
+    /// Stop tracking `route` as a mask, because it has been navigated to
+    /// directly.
+    pub(crate) fn remove(&self, route: &R) {
+        // Avoid serializing the path on every navigation while nothing is
+        // masked.
+        if self.masks.borrow().is_empty() {
+            return;
+        }
+        let path = route.path();
+        if self.masks.borrow_mut().remove(&path).is_some() {
+            self.storage.remove(&format!("{}{}", KEY_PREFIX, path));
+        }
+    }
+
+    /// Find the masked route for the `mask` arriving from the browser.
+    pub(crate) fn resolve(&self, mask: &R) -> Option<R> {
+        let mask_path = mask.path();
+        if let Some(route) = self.masks.borrow().get(&mask_path) {
+            return Some(route.clone());
+        }
+        let key = format!("{}{}", KEY_PREFIX, mask_path);
+        let value = self.storage.get(&key)?;
+        let route = match storage::supported_payload(&value, SUPPORTED_VERSIONS) {
+            Some(path) => R::switch(path),
+            None => {
+                self.storage.remove(&key);
+                return None;
+            }
+        };
+        self.masks.borrow_mut().insert(mask_path, route.clone());
+        Some(route)
+    }
+
+    /// The tracked pairs of mask path and masked route.
+    pub(crate) fn pairs(&self) -> Vec<(String, R)> {
+        self.masks
+            .borrow()
+            .iter()
+            .map(|(mask_path, route)| (mask_path.clone(), route.clone()))
+            .collect()
+    }
+
+    /// Replace the tracked pairs with `pairs` (of mask path and masked
+    /// route).
+    pub(crate) fn restore(&self, pairs: Vec<(String, R)>) {
+        let mut masks = self.masks.borrow_mut();
+        for mask_path in masks.keys() {
+            self.storage.remove(&format!("{}{}", KEY_PREFIX, mask_path));
+        }
+        masks.clear();
+        for (mask_path, route) in pairs {
+            self.storage.set(
+                &format!("{}{}", KEY_PREFIX, mask_path),
+                &storage::versioned(&route.path()),
+            );
+            masks.insert(mask_path, route);
+        }
+    }
+}
+
+impl<R> Default for RouteMasks<R>
+where
+    R: SwitchRoute,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,207 @@
+//! A route service which keeps its history in memory, for tests and
+//! environments without a browser.
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use switch_router::{Callback, SwitchRoute, SwitchRouteService};
+
+struct MemoryHistory<R> {
+    entries: Vec<R>,
+    index: usize,
+    callbacks: Vec<Callback<R>>,
+    /// Routes popped by [SwitchRouteService::back()], whose popstate
+    /// events have not been delivered yet.
+    pending: VecDeque<R>,
+}
+
+/// A [SwitchRouteService] with an in-memory history stack, behaving like
+/// the browser's history: setting a route discards the entries forward
+/// of the current entry, and going back delivers the route to the
+/// registered callbacks as a popstate event.
+///
+/// Like in a browser, popstate events caused by
+/// [back()](SwitchRouteService::back) are not delivered immediately,
+/// but when [deliver_pending()](MemoryRouteService::deliver_pending) is
+/// called. Clones share the same history, so a clone can be kept to
+/// drive the service once it has been moved into the
+/// [RouteMiddleware](crate::RouteMiddleware), see the `testing` module
+/// (requires the `test-util` feature).
+pub struct MemoryRouteService<R> {
+    history: Rc<RefCell<MemoryHistory<R>>>,
+}
+
+impl<R> MemoryRouteService<R>
+where
+    R: SwitchRoute,
+{
+    pub fn new(initial: R) -> Self {
+        Self {
+            history: Rc::new(RefCell::new(MemoryHistory {
+                entries: vec![initial],
+                index: 0,
+                callbacks: Vec::new(),
+                pending: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// The entries of the history stack, oldest first.
+    pub fn entries(&self) -> Vec<R> {
+        self.history.borrow().entries.clone()
+    }
+
+    /// The index of the current entry in [entries()](Self::entries).
+    pub fn index(&self) -> usize {
+        self.history.borrow().index
+    }
+
+    /// Move `delta` entries through the history without notifying the
+    /// callbacks, returns the new current route, or `None` if there is
+    /// no such entry.
+    pub fn go(&self, delta: isize) -> Option<R> {
+        let mut history = self.history.borrow_mut();
+        let index = history.index as isize + delta;
+        if index < 0 || index as usize >= history.entries.len() {
+            return None;
+        }
+        history.index = index as usize;
+        Some(history.entries[history.index].clone())
+    }
+
+    /// Replace the history stack with `entries`, with the entry at
+    /// `index` as the current entry, without notifying the callbacks.
+    /// Popstate events which have not been delivered are discarded.
+    ///
+    /// Panics if `index` is out of bounds of `entries`.
+    pub fn set_history(&self, entries: Vec<R>, index: usize) {
+        assert!(
+            index < entries.len(),
+            "history index {} out of bounds of {} entries",
+            index,
+            entries.len()
+        );
+        let mut history = self.history.borrow_mut();
+        history.entries = entries;
+        history.index = index;
+        history.pending.clear();
+    }
+
+    /// Add an entry for `route` without notifying the callbacks, as when
+    /// the user navigates to a URL.
+    pub fn push(&self, route: R) {
+        let mut history = self.history.borrow_mut();
+        let index = history.index + 1;
+        history.entries.truncate(index);
+        history.entries.push(route);
+        history.index = index;
+    }
+
+    /// Notify the registered callbacks of `route`, as the browser does
+    /// with a popstate event.
+    pub fn emit(&self, route: R) {
+        // The callbacks are invoked outside of the borrow, they are
+        // likely to dispatch actions which use the service.
+        let callbacks = self.history.borrow().callbacks.clone();
+        for callback in callbacks {
+            callback.emit(route.clone());
+        }
+    }
+
+    /// Deliver the popstate events caused by calls to
+    /// [back()](SwitchRouteService::back), in order.
+    pub fn deliver_pending(&self) {
+        loop {
+            let route = match self.history.borrow_mut().pending.pop_front() {
+                Some(route) => route,
+                None => break,
+            };
+            self.emit(route);
+        }
+    }
+}
+
+impl<R> Clone for MemoryRouteService<R> {
+    fn clone(&self) -> Self {
+        Self {
+            history: self.history.clone(),
+        }
+    }
+}
+
+impl<R> SwitchRouteService for MemoryRouteService<R>
+where
+    R: SwitchRoute,
+{
+    type Route = R;
+
+    fn set_route<SRI: Into<R>>(&mut self, switch_route: SRI) {
+        self.push(switch_route.into());
+    }
+
+    fn replace_route<SRI: Into<R>>(&mut self, switch_route: SRI) -> R {
+        let mut history = self.history.borrow_mut();
+        let index = history.index;
+        std::mem::replace(&mut history.entries[index], switch_route.into())
+    }
+
+    fn register_callback(&mut self, callback: &Callback<R>) {
+        self.history.borrow_mut().callbacks.push(callback.clone());
+    }
+
+    fn back(&mut self) -> Option<R> {
+        let route = self.go(-1)?;
+        self.history.borrow_mut().pending.push_back(route.clone());
+        Some(route)
+    }
+
+    fn get_route(&self) -> R {
+        let history = self.history.borrow();
+        history.entries[history.index].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_kit::{TestKit, TestRoute},
+        RouteStore,
+    };
+
+    /// A kit which navigated through `/a`, `/b` and `/c`.
+    fn kit() -> TestKit {
+        let kit = TestKit::new("/");
+        for path in ["/a", "/b", "/c"] {
+            kit.navigate(path);
+        }
+        kit
+    }
+
+    /// Collapse the last `n` entries into `/done`, returning the entries
+    /// up to the current one.
+    fn replace_stack_top(kit: &TestKit, n: usize) -> Vec<String> {
+        kit.store.replace_stack_top(n, TestRoute::new("/done"));
+        kit.flush_popstate();
+        kit.assert_route("/done");
+        kit.history()[..=kit.service.index()].to_vec()
+    }
+
+    #[test]
+    fn replace_stack_top_replaces_the_current_entry() {
+        assert_eq!(replace_stack_top(&kit(), 0), vec!["/", "/a", "/b", "/done"]);
+        assert_eq!(replace_stack_top(&kit(), 1), vec!["/", "/a", "/b", "/done"]);
+    }
+
+    #[test]
+    fn replace_stack_top_collapses_the_last_entries() {
+        let kit = kit();
+        assert_eq!(replace_stack_top(&kit, 3), vec!["/", "/done"]);
+        // Like in the browser, the collapsed entries remain ahead of the
+        // current one until the next navigation.
+        kit.navigate("/e");
+        assert_eq!(kit.history(), vec!["/", "/done", "/e"]);
+    }
+
+    #[test]
+    fn replace_stack_top_is_limited_to_the_history() {
+        assert_eq!(replace_stack_top(&kit(), 10), vec!["/done"]);
+    }
+}
@@ -0,0 +1,243 @@
+//! A registry of metadata about the application's routes, consulted by
+//! guards and other features of the
+//! [RouteMiddleware](crate::RouteMiddleware).
+
+/// Metadata describing a route (or a family of routes).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteMetadata {
+    title: Option<String>,
+    keywords: Vec<String>,
+    feature_flags: Vec<String>,
+    chunk: Option<String>,
+    replace_only: bool,
+    requires_network: bool,
+}
+
+impl RouteMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A human readable title for the route.
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// An additional search term for the route, see
+    /// [RouteRegistry::search()].
+    pub fn with_keyword<S: Into<String>>(mut self, keyword: S) -> Self {
+        self.keywords.push(keyword.into());
+        self
+    }
+
+    /// Require the feature `flag` to be enabled in order to navigate to
+    /// the route, see [FeatureFlagGuard](crate::guard::FeatureFlagGuard).
+    pub fn requires_feature_flag<S: Into<String>>(mut self, flag: S) -> Self {
+        self.feature_flags.push(flag.into());
+        self
+    }
+
+    /// The id of the code-split chunk which must be loaded before
+    /// navigating to the route, see [chunks](crate::chunks).
+    pub fn with_chunk<S: Into<String>>(mut self, chunk_id: S) -> Self {
+        self.chunk = Some(chunk_id.into());
+        self
+    }
+
+    /// Never add a history entry for the route (e.g. a transient share
+    /// sheet), navigations to it always replace the current entry so it
+    /// can't be navigated back to.
+    pub fn replace_only(mut self) -> Self {
+        self.replace_only = true;
+        self
+    }
+
+    /// The route can't be displayed without the network, see
+    /// [offline](crate::offline).
+    pub fn requires_network(mut self) -> Self {
+        self.requires_network = true;
+        self
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+
+    pub fn feature_flags(&self) -> &[String] {
+        &self.feature_flags
+    }
+
+    pub fn chunk(&self) -> Option<&str> {
+        self.chunk.as_deref()
+    }
+
+    pub fn is_replace_only(&self) -> bool {
+        self.replace_only
+    }
+
+    pub fn is_network_required(&self) -> bool {
+        self.requires_network
+    }
+}
+
+type MatcherFn<R> = dyn Fn(&R) -> bool;
+type ConstructorFn<R> = dyn Fn() -> R;
+
+/// An entry in the [RouteRegistry].
+pub struct RouteEntry<R> {
+    id: String,
+    matcher: Box<MatcherFn<R>>,
+    metadata: RouteMetadata,
+    constructor: Option<Box<ConstructorFn<R>>>,
+}
+
+impl<R> RouteEntry<R> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn metadata(&self) -> &RouteMetadata {
+        &self.metadata
+    }
+
+    pub fn matches(&self, route: &R) -> bool {
+        (self.matcher)(route)
+    }
+
+    /// Construct the route to navigate to for this entry, if it was
+    /// registered with
+    /// [register_command()](RouteRegistry::register_command).
+    pub fn route(&self) -> Option<R> {
+        self.constructor.as_ref().map(|constructor| constructor())
+    }
+
+    /// How well this entry matches the lowercase search `query`, higher
+    /// is better, `0` is no match.
+    fn search_score(&self, query: &str) -> u32 {
+        let title = self.metadata.title().unwrap_or_default().to_lowercase();
+        if title.starts_with(query) {
+            4
+        } else if title.contains(query) {
+            3
+        } else if self
+            .metadata
+            .keywords()
+            .iter()
+            .any(|keyword| keyword.to_lowercase().contains(query))
+        {
+            2
+        } else if self.id.to_lowercase().contains(query) {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// An ordered registry of [RouteMetadata]. The first entry whose matcher
+/// accepts a route provides the metadata for that route.
+pub struct RouteRegistry<R> {
+    entries: Vec<RouteEntry<R>>,
+}
+
+impl<R> RouteRegistry<R> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register `metadata` under the unique `id` for all the routes
+    /// accepted by `matcher`.
+    pub fn register<S, F>(mut self, id: S, matcher: F, metadata: RouteMetadata) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.entries.push(RouteEntry {
+            id: id.into(),
+            matcher: Box::new(matcher),
+            metadata,
+            constructor: None,
+        });
+        self
+    }
+
+    /// Register an entry like [register()](Self::register), which can
+    /// also be navigated to from a command palette, constructing its
+    /// route with `route`. See
+    /// [RouteStore::navigate_by_command()](crate::RouteStore::navigate_by_command).
+    pub fn register_command<S, F, C>(
+        mut self,
+        id: S,
+        matcher: F,
+        metadata: RouteMetadata,
+        route: C,
+    ) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&R) -> bool + 'static,
+        C: Fn() -> R + 'static,
+    {
+        self.entries.push(RouteEntry {
+            id: id.into(),
+            matcher: Box::new(matcher),
+            metadata,
+            constructor: Some(Box::new(route)),
+        });
+        self
+    }
+
+    /// Find the entry for the specified `route`.
+    pub fn entry(&self, route: &R) -> Option<&RouteEntry<R>> {
+        self.entries.iter().find(|entry| entry.matches(route))
+    }
+
+    /// Find the metadata for the specified `route`.
+    pub fn metadata(&self, route: &R) -> Option<&RouteMetadata> {
+        self.entry(route).map(RouteEntry::metadata)
+    }
+
+    /// Find the entry with the specified `id`.
+    pub fn entry_by_id(&self, id: &str) -> Option<&RouteEntry<R>> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &RouteEntry<R>> {
+        self.entries.iter()
+    }
+
+    /// The entries which can be navigated to from a command palette,
+    /// see [register_command()](Self::register_command).
+    pub fn commands(&self) -> impl Iterator<Item = &RouteEntry<R>> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.constructor.is_some())
+    }
+
+    /// Search the [commands()](Self::commands) for `query`, matching
+    /// (case insensitively) the title, then the keywords, then the id.
+    /// Results are ordered by relevance, titles starting with the query
+    /// first. An empty query returns all the commands.
+    pub fn search(&self, query: &str) -> Vec<&RouteEntry<R>> {
+        let query = query.trim().to_lowercase();
+        let mut results: Vec<(u32, &RouteEntry<R>)> = self
+            .commands()
+            .map(|entry| (entry.search_score(&query), entry))
+            .filter(|(score, _)| *score > 0)
+            .collect();
+        results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        results.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+impl<R> Default for RouteRegistry<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
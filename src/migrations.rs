@@ -0,0 +1,170 @@
+//! Mapping of old URL shapes onto current routes, similar to schema
+//! migrations. Long lived applications accumulate bookmarked URLs from
+//! previous versions, [Migrations] provide one central place to keep
+//! them working.
+//!
+//! Migrations only run on paths which aren't recognised as a current
+//! route, before they are handled as not found: paths the route service
+//! is unable to parse (see
+//! [RouteAction::RouteParseFailed](crate::RouteAction::RouteParseFailed),
+//! they run before the route table (with the `route-table` feature)
+//! and the [parse fallback](crate::RouteMiddleware::with_parse_fallback)),
+//! and routes arriving from the browser which the route type itself
+//! resolves to its not found route, as recognised by
+//! [Migrations::with_not_found()]. They are run in order, the first one
+//! to match wins. When a migration matches, the browser URL is corrected
+//! using a replace navigation.
+
+use switch_router::SwitchRoute;
+
+type MigrationFn<R> = dyn Fn(&str) -> Option<R>;
+type IsNotFoundFn<R> = dyn Fn(&R) -> bool;
+
+/// An ordered list of migrations from legacy paths to current routes.
+pub struct Migrations<R> {
+    migrations: Vec<Box<MigrationFn<R>>>,
+    is_not_found: Option<Box<IsNotFoundFn<R>>>,
+}
+
+impl<R> Migrations<R>
+where
+    R: SwitchRoute + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            migrations: Vec::new(),
+            is_not_found: None,
+        }
+    }
+
+    /// Add a migration which maps a legacy `path` to a current route,
+    /// returning `None` if the path doesn't match.
+    pub fn migrate<F>(mut self, migration: F) -> Self
+    where
+        F: Fn(&str) -> Option<R> + 'static,
+    {
+        self.migrations.push(Box::new(migration));
+        self
+    }
+
+    /// Add a migration which rewrites paths starting with the segments
+    /// of the prefix `from` to start with `to` instead. The prefix only
+    /// matches whole segments: `/user` matches `/user` and `/user/5`,
+    /// but not `/users/5`.
+    pub fn rename_prefix<F, T>(self, from: F, to: T) -> Self
+    where
+        F: Into<String>,
+        T: Into<String>,
+    {
+        let from = from.into();
+        let to = to.into();
+        self.migrate(move |path| {
+            let rest = path.strip_prefix(from.as_str())?;
+            match rest.chars().next() {
+                None | Some('/') | Some('?') | Some('#') => {
+                    Some(R::switch(&format!("{}{}", to, rest)))
+                }
+                Some(_) => None,
+            }
+        })
+    }
+
+    /// Recognise the route which the route type resolves unrecognised
+    /// paths to (e.g. `AppRoute::NotFound(path)`), so that the
+    /// migrations are run on its path. The route must keep the path
+    /// which wasn't recognised. Without this, migrations only run on
+    /// paths the route service is unable to parse.
+    pub fn with_not_found<F>(mut self, is_not_found: F) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.is_not_found = Some(Box::new(is_not_found));
+        self
+    }
+
+    /// Run the migrations on `path`, returning the current route for the
+    /// first migration which matches.
+    pub fn apply(&self, path: &str) -> Option<R> {
+        self.migrations.iter().find_map(|migration| migration(path))
+    }
+
+    /// Run the migrations on the path of `route` if it is the not found
+    /// route, see [Migrations::with_not_found()].
+    pub fn apply_not_found(&self, route: &R) -> Option<R> {
+        match &self.is_not_found {
+            Some(is_not_found) if !self.migrations.is_empty() && is_not_found(route) => {
+                self.apply(&route.path())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.migrations.is_empty()
+    }
+}
+
+impl<R> Default for Migrations<R>
+where
+    R: SwitchRoute + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_kit::TestRoute;
+
+    fn migrated(migrations: &Migrations<TestRoute>, path: &str) -> Option<String> {
+        migrations.apply(path).map(|route| route.path())
+    }
+
+    #[test]
+    fn rename_prefix_matches_whole_segments() {
+        let migrations = Migrations::new().rename_prefix("/user", String::from("/users"));
+        assert_eq!(migrated(&migrations, "/user"), Some("/users".to_string()));
+        assert_eq!(
+            migrated(&migrations, "/user/5"),
+            Some("/users/5".to_string())
+        );
+        assert_eq!(
+            migrated(&migrations, "/user?tab=1"),
+            Some("/users?tab=1".to_string())
+        );
+        assert_eq!(
+            migrated(&migrations, "/user#top"),
+            Some("/users#top".to_string())
+        );
+        assert_eq!(migrated(&migrations, "/users/5"), None);
+        assert_eq!(migrated(&migrations, "/username"), None);
+    }
+
+    #[test]
+    fn first_matching_migration_wins() {
+        let migrations = Migrations::new()
+            .migrate(|path| (path == "/old").then(|| TestRoute::new("/first")))
+            .rename_prefix("/old", "/second");
+        assert_eq!(migrated(&migrations, "/old"), Some("/first".to_string()));
+        assert_eq!(
+            migrated(&migrations, "/old/1"),
+            Some("/second/1".to_string())
+        );
+    }
+
+    #[test]
+    fn only_not_found_routes_are_migrated() {
+        let migrations = Migrations::new().rename_prefix("/user", "/users");
+        assert_eq!(migrations.apply_not_found(&TestRoute::new("/user/5")), None);
+
+        let migrations =
+            migrations.with_not_found(|route: &TestRoute| route.0.starts_with("/user/"));
+        assert_eq!(
+            migrations.apply_not_found(&TestRoute::new("/user/5")),
+            Some(TestRoute::new("/users/5"))
+        );
+        assert_eq!(migrations.apply_not_found(&TestRoute::new("/user")), None);
+    }
+}
@@ -0,0 +1,27 @@
+//! Descriptions of the navigations performed by the
+//! [RouteMiddleware](crate::RouteMiddleware).
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How a navigation was initiated.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationKind {
+    /// Initiated by the application, e.g. `ChangeRoute`.
+    Push,
+    /// The route arrived from the browser by traversing `delta` entries
+    /// of the history (negative when going back), e.g. the back gesture.
+    /// Only detected with
+    /// [with_history_keys()](crate::RouteMiddleware::with_history_keys).
+    Pop { delta: isize },
+    /// The initial route, see
+    /// [RouteAction::ResolveInitialRoute](crate::RouteAction::ResolveInitialRoute).
+    Initial,
+    /// The current route is being reduced again, see
+    /// [RouteAction::Reload](crate::RouteAction::Reload).
+    Reload,
+    /// Any other route arriving from the browser, e.g. the user entered a
+    /// URL or followed a deep link.
+    External,
+}
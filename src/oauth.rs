@@ -0,0 +1,318 @@
+//! Generation and validation of the `state` parameter for OAuth-style
+//! redirects, enabled with the `oauth` feature.
+//!
+//! Before redirecting to an external identity provider,
+//! [OAuthState::authorize_url()] generates a random `state` value, stashes
+//! it (see [StateStash]) and appends it to the redirect URL. When the
+//! callback route arrives back from the browser, the
+//! [RouteMiddleware](crate::RouteMiddleware) compares the returned value
+//! with the stashed one (in constant time). If they don't match, it
+//! emits
+//! [RouteEvent::oauth_state_failed()](crate::RouteEvent::oauth_state_failed),
+//! and the callback route is not committed: the navigation is redirected
+//! to the [failure route](OAuthState::with_failure_route), or otherwise
+//! blocked. This protects the callback against CSRF.
+//!
+//! The state is validated once, when the callback route is entered: as
+//! the stashed state can only be used once, reducing the callback route
+//! again (e.g. with [RouteAction::Reload](crate::RouteAction::Reload) or
+//! [RouteAction::PollBrowserRoute](crate::RouteAction::PollBrowserRoute))
+//! reuses the outcome of the validation. Once another route has been
+//! navigated to, returning to the callback route validates it again
+//! (and fails, the state having been used).
+
+use crate::query::QueryParams;
+use std::{cell::RefCell, fmt::Display};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The default name of the query parameter carrying the state.
+pub const DEFAULT_STATE_PARAM: &str = "state";
+
+/// Somewhere to keep the generated state until the callback arrives.
+pub trait StateStash {
+    /// Stash the `state`, replacing any previously stashed state.
+    fn stash(&self, state: &str);
+    /// Remove and return the stashed state. A state can only be used
+    /// once.
+    fn take(&self) -> Option<String>;
+}
+
+/// A [StateStash] which keeps the state in memory. This does not survive
+/// the full page load of an external redirect, so it is mostly useful
+/// for popup based flows and testing. See
+/// [SessionStorageStateStash](crate::web::SessionStorageStateStash).
+#[derive(Debug, Default)]
+pub struct MemoryStateStash {
+    state: RefCell<Option<String>>,
+}
+
+impl MemoryStateStash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateStash for MemoryStateStash {
+    fn stash(&self, state: &str) {
+        *self.state.borrow_mut() = Some(state.to_string());
+    }
+
+    fn take(&self) -> Option<String> {
+        self.state.borrow_mut().take()
+    }
+}
+
+/// The reason why validating the state of a callback route failed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthStateError {
+    /// The callback route has no state parameter.
+    Missing,
+    /// No state was stashed, the callback was not initiated by this
+    /// application (or has already been used).
+    NotStashed,
+    /// The state in the callback route doesn't match the stashed state.
+    Mismatch,
+}
+
+impl Display for OAuthStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OAuthStateError::Missing => write!(f, "callback route has no state parameter"),
+            OAuthStateError::NotStashed => write!(f, "no state was stashed for the callback"),
+            OAuthStateError::Mismatch => write!(f, "callback state does not match"),
+        }
+    }
+}
+
+impl std::error::Error for OAuthStateError {}
+
+/// Compare `a` and `b` in time independent of their contents, so that
+/// the stashed state can't be guessed one character at a time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .fold(0u8, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Generate a random state value (128 bits, hex encoded).
+pub fn generate_state() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+type IsCallbackFn<R> = dyn Fn(&R) -> bool;
+
+/// Configuration for generating and validating the OAuth state.
+pub struct OAuthState<R> {
+    stash: Box<dyn StateStash>,
+    param: String,
+    is_callback: Box<IsCallbackFn<R>>,
+    failure_route: Option<R>,
+    /// The path of the callback route which was last validated, and the
+    /// outcome, while it is the current route.
+    validated: RefCell<Option<(String, Result<(), OAuthStateError>)>>,
+}
+
+impl<R> OAuthState<R> {
+    /// Create a new [OAuthState], validating routes for which
+    /// `is_callback` returns `true`.
+    pub fn new<S, F>(stash: S, is_callback: F) -> Self
+    where
+        S: StateStash + 'static,
+        F: Fn(&R) -> bool + 'static,
+    {
+        Self {
+            stash: Box::new(stash),
+            param: DEFAULT_STATE_PARAM.to_string(),
+            is_callback: Box::new(is_callback),
+            failure_route: None,
+            validated: RefCell::new(None),
+        }
+    }
+
+    /// Use the query parameter `param` to carry the state, instead of
+    /// [DEFAULT_STATE_PARAM].
+    pub fn with_param<S: Into<String>>(mut self, param: S) -> Self {
+        self.param = param.into();
+        self
+    }
+
+    /// Redirect to `route` (e.g. the login page) when the state of a
+    /// callback route fails validation, rather than blocking the
+    /// navigation.
+    pub fn with_failure_route(mut self, route: R) -> Self {
+        self.failure_route = Some(route);
+        self
+    }
+
+    pub fn failure_route(&self) -> Option<&R> {
+        self.failure_route.as_ref()
+    }
+
+    /// Generate and stash a new state, returning `url` with the state
+    /// appended as a query parameter.
+    pub fn authorize_url(&self, url: &str) -> Result<String, getrandom::Error> {
+        let state = generate_state()?;
+        self.stash.stash(&state);
+        let mut params = QueryParams::from_path(url);
+        params.insert(self.param.as_str(), state);
+        Ok(params.replace_in_path(url))
+    }
+
+    /// Validate the state of the `route` with the specified `path`,
+    /// returns `None` if the route is not a callback route. If the
+    /// callback route with this `path` was already validated and is
+    /// still the current route, the outcome is reused.
+    pub(crate) fn validate(&self, route: &R, path: &str) -> Option<Result<(), OAuthStateError>> {
+        if !(self.is_callback)(route) {
+            return None;
+        }
+        if let Some((validated, result)) = &*self.validated.borrow() {
+            if validated == path {
+                return Some(*result);
+            }
+        }
+        let params = QueryParams::from_path(path);
+        let result = match (params.get(&self.param), self.stash.take()) {
+            (None, _) => Err(OAuthStateError::Missing),
+            (Some(_), None) => Err(OAuthStateError::NotStashed),
+            (Some(state), Some(stashed))
+                if constant_time_eq(state.as_bytes(), stashed.as_bytes()) =>
+            {
+                Ok(())
+            }
+            (Some(_), Some(_)) => Err(OAuthStateError::Mismatch),
+        };
+        self.validated.replace(Some((path.to_string(), result)));
+        Some(result)
+    }
+
+    /// Forget the outcome of the last validation once `current` (the
+    /// route of the state after an action was reduced) is no longer a
+    /// callback route.
+    pub(crate) fn observe(&self, current: &R) {
+        if !(self.is_callback)(current) {
+            self.validated.replace(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_kit::{TestKit, TestRoute};
+    use std::rc::Rc;
+
+    /// A [MemoryStateStash] which the test keeps a handle to.
+    #[derive(Clone, Default)]
+    struct SharedStash(Rc<MemoryStateStash>);
+
+    impl StateStash for SharedStash {
+        fn stash(&self, state: &str) {
+            self.0.stash(state)
+        }
+
+        fn take(&self) -> Option<String> {
+            self.0.take()
+        }
+    }
+
+    fn is_callback(route: &TestRoute) -> bool {
+        route.0.starts_with("/callback")
+    }
+
+    fn oauth_state(stash: &SharedStash) -> OAuthState<TestRoute> {
+        OAuthState::new(stash.clone(), is_callback)
+    }
+
+    #[test]
+    fn state_is_validated_once_per_entry() {
+        let stash = SharedStash::default();
+        let oauth = oauth_state(&stash);
+        let callback = TestRoute::new("/callback?state=abc");
+        stash.stash("abc");
+
+        assert_eq!(
+            oauth.validate(&callback, "/callback?state=abc"),
+            Some(Ok(()))
+        );
+        // Re-reducing the callback route doesn't consume the stash again.
+        oauth.observe(&callback);
+        assert_eq!(
+            oauth.validate(&callback, "/callback?state=abc"),
+            Some(Ok(()))
+        );
+
+        // Returning to the callback route after leaving it is a replay.
+        oauth.observe(&TestRoute::new("/home"));
+        assert_eq!(
+            oauth.validate(&callback, "/callback?state=abc"),
+            Some(Err(OAuthStateError::NotStashed))
+        );
+    }
+
+    #[test]
+    fn mismatched_and_missing_states_fail() {
+        let stash = SharedStash::default();
+        let oauth = oauth_state(&stash);
+        stash.stash("abc");
+        assert_eq!(
+            oauth.validate(&TestRoute::new("/callback"), "/callback?state=abd"),
+            Some(Err(OAuthStateError::Mismatch))
+        );
+        assert_eq!(
+            oauth.validate(&TestRoute::new("/callback"), "/callback"),
+            Some(Err(OAuthStateError::Missing))
+        );
+        assert_eq!(oauth.validate(&TestRoute::new("/home"), "/home"), None);
+    }
+
+    #[test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    /// A [TestKit] at `/` validating the callback routes, with the
+    /// state `abc` stashed.
+    fn kit_with_state(configure: fn(OAuthState<TestRoute>) -> OAuthState<TestRoute>) -> TestKit {
+        let stash = SharedStash::default();
+        let kit = TestKit::with_middleware("/", |middleware| {
+            middleware.with_oauth_state(configure(oauth_state(&stash)))
+        });
+        stash.stash("abc");
+        kit
+    }
+
+    #[test]
+    fn mismatched_callback_is_blocked() {
+        let kit = kit_with_state(|oauth| oauth);
+        kit.browser_navigate("/callback?state=forged");
+        kit.assert_route("/");
+    }
+
+    #[test]
+    fn mismatched_callback_is_redirected_to_failure_route() {
+        let kit = kit_with_state(|oauth| oauth.with_failure_route(TestRoute::new("/login")));
+        kit.browser_navigate("/callback?state=forged");
+        kit.assert_route("/login");
+    }
+
+    #[test]
+    fn reloading_valid_callback_does_not_fail() {
+        let kit = kit_with_state(|oauth| oauth.with_failure_route(TestRoute::new("/login")));
+        kit.browser_navigate("/callback?state=abc");
+        kit.assert_route("/callback?state=abc");
+        kit.dispatch(crate::RouteAction::Reload);
+        kit.dispatch(crate::RouteAction::PollBrowserRoute);
+        kit.assert_route("/callback?state=abc");
+    }
+}
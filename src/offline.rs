@@ -0,0 +1,96 @@
+//! Offline-aware routing: while the browser is offline, navigations to
+//! routes which [require the network](crate::metadata::RouteMetadata::requires_network)
+//! are redirected to an offline route, rather than to a page which
+//! fails to load its data. When connectivity returns while the offline
+//! route is displayed, the route which was redirected (the intended
+//! route) replaces it.
+//!
+//! Configured with
+//! [with_offline_routing()](crate::RouteMiddleware::with_offline_routing),
+//! connectivity changes are reduced as
+//! [RouteAction::ConnectivityChanged](crate::RouteAction::ConnectivityChanged).
+//! With the `web` feature, the middleware dispatches them when the
+//! window receives the `online` and `offline` events, elsewhere (e.g. in
+//! a desktop shell) the application dispatches them with
+//! [RouteStore::connectivity_changed()](crate::RouteStore::connectivity_changed).
+//!
+//! ```ignore
+//! let registry = RouteRegistry::new()
+//!     .register("reports", is_reports, RouteMetadata::new().requires_network());
+//! let middleware = RouteMiddleware::new(route_service, store.clone())
+//!     .with_registry(registry)
+//!     .with_offline_routing(OfflineRouting::new(AppRoute::Offline));
+//! ```
+
+use crate::{logging::debug, metadata::RouteMetadata};
+use std::cell::{Cell, RefCell};
+use switch_router::SwitchRoute;
+
+/// The built-in guard for offline-aware routing, see the
+/// [module documentation](self).
+pub struct OfflineRouting<R> {
+    offline_route: R,
+    online: Cell<bool>,
+    intended: RefCell<Option<R>>,
+}
+
+impl<R> OfflineRouting<R>
+where
+    R: SwitchRoute,
+{
+    /// Create a new [OfflineRouting], redirecting to `offline_route`
+    /// while offline. Assumes the browser is online until told
+    /// otherwise.
+    pub fn new(offline_route: R) -> Self {
+        Self {
+            offline_route,
+            online: Cell::new(true),
+            intended: RefCell::new(None),
+        }
+    }
+
+    /// Whether the browser is online initially, e.g. in a desktop shell
+    /// which knows it. With the `web` feature, the middleware reads it
+    /// from `navigator.onLine`.
+    pub fn with_online(self, online: bool) -> Self {
+        self.online.set(online);
+        self
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online.get()
+    }
+
+    /// The offline route, if navigating to `target` (with `metadata`) is
+    /// to be redirected to it, remembering `target` as the intended
+    /// route.
+    pub(crate) fn redirect(&self, target: &R, metadata: Option<&RouteMetadata>) -> Option<R> {
+        let requires_network = metadata
+            .map(RouteMetadata::is_network_required)
+            .unwrap_or(false);
+        if self.online.get() || !requires_network {
+            return None;
+        }
+        debug!(
+            "Offline, redirecting {} to the offline route",
+            target.path()
+        );
+        self.intended.replace(Some(target.clone()));
+        Some(self.offline_route.clone())
+    }
+
+    /// Record the connectivity, returning the intended route to resume
+    /// if connectivity has returned while the `current` route is the
+    /// offline route.
+    pub(crate) fn set_online(&self, online: bool, current: &R) -> Option<R> {
+        if self.online.replace(online) || !online {
+            return None;
+        }
+        if current.path() == self.offline_route.path() {
+            self.intended.take()
+        } else {
+            self.intended.replace(None);
+            None
+        }
+    }
+}
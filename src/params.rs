@@ -0,0 +1,331 @@
+//! Typed access to the dynamic segments of routes.
+//!
+//! A [RouteParams] struct holds the parameters of one variant of the
+//! application's route enum, so that guards and other hooks can work
+//! with e.g. `UserPageParams { user_id: 5 }` rather than pattern matching
+//! the route enum everywhere. With the `derive` feature,
+//! `#[derive(RouteParams)]` implements it for a struct with the fields of
+//! the variant named by the `route_params` attribute: by name for a
+//! variant with named fields, by position for a tuple variant, and none
+//! for a unit variant. It also implements `From<Struct>` for the route:
+//!
+//! ```ignore
+//! #[derive(RouteParams, Debug, Clone, PartialEq)]
+//! #[route_params(AppRoute::UserPage)]
+//! pub struct UserPageParams {
+//!     pub user_id: u64,
+//! }
+//!
+//! #[derive(RouteParams, Debug, Clone, PartialEq)]
+//! #[route_params(AppRoute::Post)]
+//! pub struct PostParams(pub u64, pub String);
+//! ```
+//!
+//! Without the feature, the [route_params!](crate::route_params) macro
+//! declares the struct and derives its implementation for a variant with
+//! named or unnamed fields:
+//!
+//! ```ignore
+//! route_params! {
+//!     /// The parameters of `AppRoute::UserPage { user_id: u64 }`.
+//!     pub struct UserPageParams for AppRoute::UserPage { user_id: u64 }
+//! }
+//! route_params! {
+//!     /// The parameters of `AppRoute::Post(u64, String)`.
+//!     pub struct PostParams for AppRoute::Post(id: u64, slug: String)
+//! }
+//!
+//! let guard = |context: &GuardContext<'_, AppRoute, State>| match context.target_params::<UserPageParams>() {
+//!     Some(params) if !context.state.can_view_user(params.user_id) => GuardOutcome::Block,
+//!     _ => GuardOutcome::Allow,
+//! };
+//! ```
+//!
+//! A [ParamValidation] guard validates the parameters of a route before
+//! it is committed, so that half-valid routes don't reach the page
+//! components. Validators either reject the parameters, or coerce them
+//! into valid ones (in which case the guard redirects to the route with
+//! the coerced parameters):
+//!
+//! ```ignore
+//! let validation = ParamValidation::<AppRoute, ListParams>::redirect_to(AppRoute::NotFound)
+//!     .clamp(|params| &mut params.page, 1..=1000)
+//!     .check("sort", |params| ["name", "date"].contains(&params.sort.as_str()));
+//! let middleware = middleware.with_guard(validation);
+//! ```
+
+use crate::guard::{GuardContext, GuardOutcome, RouteGuard};
+use std::{
+    fmt::{self, Debug, Display},
+    ops::RangeInclusive,
+};
+#[cfg(feature = "derive")]
+pub use switch_router_middleware_derive::RouteParams;
+
+/// The parameters of a route, see the [module documentation](self).
+pub trait RouteParams<R>: Sized {
+    /// The parameters of `route`, or `None` if it is another route.
+    fn from_route(route: &R) -> Option<Self>;
+
+    /// The route with these parameters.
+    fn into_route(self) -> R;
+}
+
+/// Derive a [RouteParams] struct for a variant of a route enum, see the
+/// [module documentation](crate::params).
+///
+/// The struct has a public field for each field of the variant (given
+/// names for a variant with unnamed fields), and implements
+/// [RouteParams], `From<Struct>` for the route, `Debug`, `Clone` and
+/// `PartialEq`. The field types need to implement these traits too.
+#[macro_export]
+macro_rules! route_params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident for $route:ident :: $variant:ident {
+            $($(#[$field_meta:meta])* $field:ident : $ty:ty),* $(,)?
+        }
+    ) => {
+        $crate::route_params!(@struct $(#[$meta])* $vis $name { $($(#[$field_meta])* $field: $ty),* });
+
+        impl $crate::params::RouteParams<$route> for $name {
+            fn from_route(route: &$route) -> ::std::option::Option<Self> {
+                match route {
+                    $route::$variant { $($field),* } => ::std::option::Option::Some($name {
+                        $($field: ::std::clone::Clone::clone($field)),*
+                    }),
+                    #[allow(unreachable_patterns)]
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn into_route(self) -> $route {
+                $route::$variant { $($field: self.$field),* }
+            }
+        }
+
+        $crate::route_params!(@from $name $route);
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident for $route:ident :: $variant:ident (
+            $($(#[$field_meta:meta])* $field:ident : $ty:ty),* $(,)?
+        )
+    ) => {
+        $crate::route_params!(@struct $(#[$meta])* $vis $name { $($(#[$field_meta])* $field: $ty),* });
+
+        impl $crate::params::RouteParams<$route> for $name {
+            fn from_route(route: &$route) -> ::std::option::Option<Self> {
+                match route {
+                    $route::$variant($($field),*) => ::std::option::Option::Some($name {
+                        $($field: ::std::clone::Clone::clone($field)),*
+                    }),
+                    #[allow(unreachable_patterns)]
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn into_route(self) -> $route {
+                $route::$variant($(self.$field),*)
+            }
+        }
+
+        $crate::route_params!(@from $name $route);
+    };
+    (@struct $(#[$meta:meta])* $vis:vis $name:ident { $($(#[$field_meta:meta])* $field:ident : $ty:ty),* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        $vis struct $name {
+            $($(#[$field_meta])* pub $field: $ty),*
+        }
+    };
+    (@from $name:ident $route:ident) => {
+        impl ::std::convert::From<$name> for $route {
+            fn from(params: $name) -> Self {
+                $crate::params::RouteParams::into_route(params)
+            }
+        }
+    };
+}
+
+/// A parameter which failed validation, see [ParamValidation].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamError {
+    /// The name of the parameter.
+    pub param: &'static str,
+    pub reason: String,
+}
+
+impl Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid route parameter {:?}: {}",
+            self.param, self.reason
+        )
+    }
+}
+
+/// Validates (and possibly coerces) the parameters, see
+/// [ParamValidation].
+type ValidatorFn<P> = dyn Fn(&mut P) -> Result<(), ParamError>;
+
+/// What to do with a route whose parameters are invalid.
+enum OnInvalid<R> {
+    Redirect(R),
+    Handle(Box<dyn Fn(&ParamError)>),
+}
+
+/// A [RouteGuard] validating the [RouteParams] `P` of the routes being
+/// navigated to, see the [module documentation](self).
+///
+/// Routes whose parameters fail validation are redirected to the
+/// configured invalid-params route, or blocked after passing the error to
+/// a handler (which can dispatch an error action to the store). Routes
+/// whose parameters were coerced are redirected to the route with the
+/// coerced parameters. Other routes are allowed.
+pub struct ParamValidation<R, P> {
+    validators: Vec<Box<ValidatorFn<P>>>,
+    on_invalid: OnInvalid<R>,
+}
+
+impl<R, P> ParamValidation<R, P>
+where
+    P: RouteParams<R> + Clone + PartialEq + 'static,
+{
+    /// Redirect routes with invalid parameters to `route`.
+    pub fn redirect_to(route: R) -> Self {
+        Self {
+            validators: Vec::new(),
+            on_invalid: OnInvalid::Redirect(route),
+        }
+    }
+
+    /// Block routes with invalid parameters, passing the error to
+    /// `handler`, e.g. to dispatch an error action with a weak reference
+    /// to the store.
+    pub fn on_invalid<F>(handler: F) -> Self
+    where
+        F: Fn(&ParamError) + 'static,
+    {
+        Self {
+            validators: Vec::new(),
+            on_invalid: OnInvalid::Handle(Box::new(handler)),
+        }
+    }
+
+    /// Validate the parameters with `validator`, which may also coerce
+    /// them by modifying them. Validators are run in the order they were
+    /// added.
+    pub fn validate<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&mut P) -> Result<(), ParamError> + 'static,
+    {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Reject parameters for which `is_valid` returns `false`.
+    pub fn check<F>(self, param: &'static str, is_valid: F) -> Self
+    where
+        F: Fn(&P) -> bool + 'static,
+    {
+        self.validate(move |params| {
+            if is_valid(params) {
+                Ok(())
+            } else {
+                Err(ParamError {
+                    param,
+                    reason: "rejected by validator".to_string(),
+                })
+            }
+        })
+    }
+
+    /// Reject the parameter returned by `field` if it's outside of
+    /// `range`.
+    pub fn range<T, F>(self, param: &'static str, field: F, range: RangeInclusive<T>) -> Self
+    where
+        T: PartialOrd + Debug + 'static,
+        F: Fn(&mut P) -> &mut T + 'static,
+    {
+        self.validate(move |params| {
+            let value = field(params);
+            if range.contains(value) {
+                Ok(())
+            } else {
+                Err(ParamError {
+                    param,
+                    reason: format!("{:?} is outside of {:?}", value, range),
+                })
+            }
+        })
+    }
+
+    /// Coerce the parameter returned by `field` into `range`, by clamping
+    /// it to the nearest bound.
+    pub fn clamp<T, F>(self, field: F, range: RangeInclusive<T>) -> Self
+    where
+        T: PartialOrd + Clone + 'static,
+        F: Fn(&mut P) -> &mut T + 'static,
+    {
+        self.validate(move |params| {
+            let value = field(params);
+            if *value < *range.start() {
+                *value = range.start().clone();
+            } else if *value > *range.end() {
+                *value = range.end().clone();
+            }
+            Ok(())
+        })
+    }
+
+    /// Reject the parameter returned by `field` unless it matches
+    /// `pattern` (requires the `regex` feature).
+    #[cfg(feature = "regex")]
+    pub fn pattern<F>(self, param: &'static str, field: F, pattern: regex::Regex) -> Self
+    where
+        F: Fn(&P) -> &str + 'static,
+    {
+        self.validate(move |params| {
+            let value = field(params);
+            if pattern.is_match(value) {
+                Ok(())
+            } else {
+                Err(ParamError {
+                    param,
+                    reason: format!("{:?} doesn't match {:?}", value, pattern.as_str()),
+                })
+            }
+        })
+    }
+}
+
+impl<R, P, State> RouteGuard<R, State> for ParamValidation<R, P>
+where
+    R: Clone,
+    P: RouteParams<R> + Clone + PartialEq + 'static,
+{
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R> {
+        let params = match context.target_params::<P>() {
+            Some(params) => params,
+            None => return GuardOutcome::Allow,
+        };
+        let mut coerced = params.clone();
+        let result = self
+            .validators
+            .iter()
+            .try_for_each(|validator| validator(&mut coerced));
+        match result {
+            Err(error) => match &self.on_invalid {
+                OnInvalid::Redirect(route) => GuardOutcome::Redirect(route.clone()),
+                OnInvalid::Handle(handler) => {
+                    handler(&error);
+                    GuardOutcome::Block
+                }
+            },
+            Ok(()) if coerced != params => GuardOutcome::Redirect(coerced.into_route()),
+            Ok(()) => GuardOutcome::Allow,
+        }
+    }
+}
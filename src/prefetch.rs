@@ -0,0 +1,120 @@
+//! Predictive prefetching. The [RouteMiddleware](crate::RouteMiddleware)
+//! records the transitions between routes, and after each navigation
+//! (when the browser is idle, with the `web` feature) prefetches the most
+//! frequent successors of the current route, e.g. by warming the cache
+//! of their data loaders. Requires the `prefetch` feature.
+//!
+//! This trades bandwidth for latency, so only routes accepted by the
+//! allowlist are prefetched, and prefetching can be toggled at runtime
+//! (e.g. when the user enables data saving).
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+use switch_router::SwitchRoute;
+
+type MatcherFn<R> = dyn Fn(&R) -> bool;
+type PrefetchFn<R> = dyn Fn(&R);
+
+/// The default number of successor routes to prefetch.
+pub const DEFAULT_MAX_CANDIDATES: usize = 2;
+
+/// Prefetching of the most likely next routes, see the
+/// [module documentation](self).
+pub struct Prefetch<R> {
+    enabled: Cell<bool>,
+    allowlist: Vec<Box<MatcherFn<R>>>,
+    prefetch: Box<PrefetchFn<R>>,
+    max_candidates: usize,
+    /// Successors of each route path, with the number of times they
+    /// were navigated to.
+    successors: RefCell<HashMap<String, Vec<(R, u32)>>>,
+}
+
+impl<R> Prefetch<R>
+where
+    R: SwitchRoute,
+{
+    /// Create a new [Prefetch], which prefetches routes using
+    /// `prefetch`. Nothing is prefetched until routes are added to the
+    /// allowlist with [allow()](Self::allow).
+    pub fn new<F>(prefetch: F) -> Self
+    where
+        F: Fn(&R) + 'static,
+    {
+        Self {
+            enabled: Cell::new(true),
+            allowlist: Vec::new(),
+            prefetch: Box::new(prefetch),
+            max_candidates: DEFAULT_MAX_CANDIDATES,
+            successors: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Allow the routes accepted by `matcher` to be prefetched.
+    pub fn allow<F>(mut self, matcher: F) -> Self
+    where
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.allowlist.push(Box::new(matcher));
+        self
+    }
+
+    /// Prefetch at most `max_candidates` routes after each navigation,
+    /// defaults to [DEFAULT_MAX_CANDIDATES].
+    pub fn with_max_candidates(mut self, max_candidates: usize) -> Self {
+        self.max_candidates = max_candidates;
+        self
+    }
+
+    /// Enable or disable prefetching, transitions are still recorded
+    /// while disabled.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    /// Record a navigation from the route `from` to the route `to`.
+    pub(crate) fn record(&self, from: &R, to: &R) {
+        let mut successors = self.successors.borrow_mut();
+        let counts = successors.entry(from.path()).or_default();
+        match counts.iter_mut().find(|(route, _)| route == to) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((to.clone(), 1)),
+        }
+    }
+
+    /// The allowed routes most likely to be navigated to from `route`,
+    /// most likely first.
+    pub fn predict(&self, route: &R) -> Vec<R> {
+        let successors = self.successors.borrow();
+        let mut counts: Vec<&(R, u32)> = match successors.get(&route.path()) {
+            Some(counts) => counts
+                .iter()
+                .filter(|(successor, _)| self.allowlist.iter().any(|allow| allow(successor)))
+                .collect(),
+            None => return Vec::new(),
+        };
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts
+            .into_iter()
+            .take(self.max_candidates)
+            .map(|(successor, _)| successor.clone())
+            .collect()
+    }
+
+    /// Prefetch the [predicted](Self::predict) successors of `route`
+    /// now, if prefetching is enabled.
+    pub fn prefetch_successors(&self, route: &R) {
+        if !self.is_enabled() {
+            return;
+        }
+        for successor in self.predict(route) {
+            (self.prefetch)(&successor);
+        }
+    }
+}
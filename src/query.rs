@@ -0,0 +1,522 @@
+//! Parsing and serialization of the query portion of route paths.
+
+use crate::{
+    canonical::split_path,
+    encoding::{decode_query_component, encode_query_component},
+};
+use std::{fmt::Display, iter::FromIterator, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The decoded key/value pairs of a query string, in their original
+/// order. Keys may appear more than once.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a query string (without the leading `?`).
+    pub fn parse(query: &str) -> Self {
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let mut key_value = pair.splitn(2, '=');
+                let key = decode_query_component(key_value.next().unwrap_or(""));
+                let value = decode_query_component(key_value.next().unwrap_or(""));
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// Parse the query portion of a route `path`.
+    pub fn from_path(path: &str) -> Self {
+        let (_, remainder) = split_path(path);
+        let query = remainder
+            .strip_prefix('?')
+            .map(|query| query.split('#').next().unwrap_or(""))
+            .unwrap_or("");
+        Self::parse(query)
+    }
+
+    /// Get the first value for `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get all the values for `key`, in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.pairs
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.pairs.iter().any(|(k, _)| k == key)
+    }
+
+    /// Set the value for `key`, replacing any existing values.
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        let key = key.into();
+        let mut value = Some(value.into());
+        self.pairs.retain_mut(|(k, v)| {
+            if *k != key {
+                return true;
+            }
+            match value.take() {
+                Some(value) => {
+                    *v = value;
+                    true
+                }
+                None => false,
+            }
+        });
+        if let Some(value) = value {
+            self.pairs.push((key, value));
+        }
+    }
+
+    /// Add a value for `key`, keeping any existing values.
+    pub fn append<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.pairs.push((key.into(), value.into()));
+    }
+
+    /// Remove all values for `key`, returning the first one.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let first = self.get(key).map(ToString::to_string);
+        self.pairs.retain(|(k, _)| k != key);
+        first
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Parse the value for the required `key`.
+    pub fn require<T>(&self, key: &str) -> Result<T, QueryError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.optional(key)?.ok_or_else(|| QueryError::Missing {
+            key: key.to_string(),
+        })
+    }
+
+    /// Parse the value for the optional `key`, returning `Ok(None)` if
+    /// the key is not present.
+    pub fn optional<T>(&self, key: &str) -> Result<Option<T>, QueryError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(key)
+            .map(|value| {
+                value.parse().map_err(|err: T::Err| QueryError::Invalid {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    message: err.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    /// Serialize into an encoded query string (without the leading `?`).
+    pub fn to_query_string(&self) -> String {
+        self.pairs
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}={}",
+                    encode_query_component(key),
+                    encode_query_component(value)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("&")
+    }
+
+    /// Replace the query portion of `path` with these parameters,
+    /// preserving the path portion and any fragment.
+    pub fn replace_in_path(&self, path: &str) -> String {
+        let (path_portion, remainder) = split_path(path);
+        let fragment = remainder.find('#').map(|index| &remainder[index..]);
+        let mut replaced = path_portion.to_string();
+        if !self.is_empty() {
+            replaced.push('?');
+            replaced.push_str(&self.to_query_string());
+        }
+        if let Some(fragment) = fragment {
+            replaced.push_str(fragment);
+        }
+        replaced
+    }
+}
+
+impl Display for QueryParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_query_string())
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for QueryParams {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            pairs: iter
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        }
+    }
+}
+
+/// An error while extracting typed values from [QueryParams].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// A required parameter is missing.
+    Missing { key: String },
+    /// A parameter could not be parsed.
+    Invalid {
+        key: String,
+        value: String,
+        message: String,
+    },
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::Missing { key } => write!(f, "missing query parameter {:?}", key),
+            QueryError::Invalid {
+                key,
+                value,
+                message,
+            } => write!(
+                f,
+                "invalid value {:?} for query parameter {:?}: {}",
+                value, key, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Typed extraction of values from [QueryParams].
+pub trait FromQuery: Sized {
+    fn from_query(params: &QueryParams) -> Result<Self, QueryError>;
+}
+
+type ExtractFn<R> = dyn Fn(&R, &QueryParams) -> Option<Result<(), QueryError>>;
+type OnErrorFn<Action> = dyn Fn(QueryError, QueryParams) -> Action;
+
+/// Validation of the query parameters of committed routes. When typed
+/// extraction fails, the configured action is dispatched carrying the
+/// error and the raw parameters, rather than the application silently
+/// falling back to defaults.
+pub struct QueryValidation<R, Action> {
+    extractors: Vec<Box<ExtractFn<R>>>,
+    on_error: Box<OnErrorFn<Action>>,
+}
+
+impl<R, Action> QueryValidation<R, Action>
+where
+    R: 'static,
+{
+    /// Create a new [QueryValidation], where `on_error` constructs the
+    /// action to dispatch when extraction fails.
+    pub fn new<F>(on_error: F) -> Self
+    where
+        F: Fn(QueryError, QueryParams) -> Action + 'static,
+    {
+        Self {
+            extractors: Vec::new(),
+            on_error: Box::new(on_error),
+        }
+    }
+
+    /// Extract `T` from the query parameters of routes accepted by
+    /// `matcher`.
+    pub fn extract<T, F>(mut self, matcher: F) -> Self
+    where
+        T: FromQuery,
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.extractors.push(Box::new(move |route, params| {
+            if matcher(route) {
+                Some(T::from_query(params).map(|_| ()))
+            } else {
+                None
+            }
+        }));
+        self
+    }
+
+    /// Validate the query parameters of the `route` with the specified
+    /// `path`, returning the action to dispatch for the first failure.
+    pub(crate) fn validate(&self, route: &R, path: &str) -> Option<Action> {
+        if self.extractors.is_empty() {
+            return None;
+        }
+        let params = QueryParams::from_path(path);
+        let error = self
+            .extractors
+            .iter()
+            .filter_map(|extractor| extractor(route, &params))
+            .find_map(Result::err)?;
+        Some((self.on_error)(error, params))
+    }
+}
+
+/// A page of a paginated list, as bound to the query parameters by
+/// [Pagination].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Page {
+    /// The number of the page, counting from `1` (`0` is treated as `1`).
+    pub page: u32,
+    /// The number of items per page (`0` is treated as `1`).
+    pub per_page: u32,
+}
+
+impl Page {
+    /// The index of the first item of the page, saturating at
+    /// `usize::MAX`.
+    pub fn offset(&self) -> usize {
+        (self.page.max(1) as usize - 1).saturating_mul(self.per_page.max(1) as usize)
+    }
+
+    /// The number of pages needed for `total` items, at least `1`.
+    pub fn page_count(&self, total: usize) -> u32 {
+        let pages = total.div_ceil(self.per_page.max(1) as usize);
+        pages.max(1).min(u32::MAX as usize) as u32
+    }
+}
+
+/// The binding of a [Page] to the query parameters of list routes
+/// (`?page=2&per_page=50` by default).
+///
+/// Reading never fails: missing or invalid parameters fall back to the
+/// defaults, and values out of range are clamped. Parameters with their
+/// default values are left out when writing, so that every page has a
+/// single URL. Configure the middleware's binding with
+/// [with_pagination()](crate::RouteMiddleware::with_pagination) for
+/// [RouteStore::next_page()](crate::RouteStore::next_page) and
+/// [RouteStore::prev_page()](crate::RouteStore::prev_page).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pagination {
+    page_key: String,
+    per_page_key: String,
+    default_per_page: u32,
+    max_per_page: u32,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            page_key: "page".to_string(),
+            per_page_key: "per_page".to_string(),
+            default_per_page: 20,
+            max_per_page: 100,
+        }
+    }
+}
+
+impl Pagination {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `page_key` and `per_page_key` for the query parameters.
+    pub fn with_keys<P: Into<String>, N: Into<String>>(
+        mut self,
+        page_key: P,
+        per_page_key: N,
+    ) -> Self {
+        self.page_key = page_key.into();
+        self.per_page_key = per_page_key.into();
+        self
+    }
+
+    /// The number of items per page when it isn't specified (`20` by
+    /// default), clamped to `1..=max_per_page`.
+    pub fn with_default_per_page(mut self, per_page: u32) -> Self {
+        self.default_per_page = per_page;
+        self
+    }
+
+    /// The maximum number of items per page (`100` by default).
+    pub fn with_max_per_page(mut self, max_per_page: u32) -> Self {
+        self.max_per_page = max_per_page.max(1);
+        self
+    }
+
+    fn default_per_page(&self) -> u32 {
+        self.default_per_page.clamp(1, self.max_per_page)
+    }
+
+    /// Read the [Page] from the query `params`.
+    pub fn read(&self, params: &QueryParams) -> Page {
+        let page = params
+            .optional::<u32>(&self.page_key)
+            .ok()
+            .flatten()
+            .unwrap_or(1)
+            .max(1);
+        let per_page = params
+            .optional::<u32>(&self.per_page_key)
+            .ok()
+            .flatten()
+            .map(|per_page| per_page.clamp(1, self.max_per_page))
+            .unwrap_or_else(|| self.default_per_page());
+        Page { page, per_page }
+    }
+
+    /// Write the `page` to the query `params`, leaving out the default
+    /// values.
+    pub fn write(&self, params: &mut QueryParams, page: Page) {
+        let page_number = page.page.max(1);
+        let per_page = page.per_page.clamp(1, self.max_per_page);
+        if page_number == 1 {
+            params.remove(&self.page_key);
+        } else {
+            params.insert(self.page_key.as_str(), page_number.to_string());
+        }
+        if per_page == self.default_per_page() {
+            params.remove(&self.per_page_key);
+        } else {
+            params.insert(self.per_page_key.as_str(), per_page.to_string());
+        }
+    }
+
+    /// The route `path` moved `delta` pages forward (or back, if it is
+    /// negative), or `None` if that would move before the first page.
+    pub fn offset_path(&self, path: &str, delta: i32) -> Option<String> {
+        let mut params = QueryParams::from_path(path);
+        let page = self.read(&params);
+        let target = i64::from(page.page) + i64::from(delta);
+        if target < 1 || target == i64::from(page.page) {
+            return None;
+        }
+        let target = Page {
+            page: target.min(i64::from(u32::MAX)) as u32,
+            ..page
+        };
+        self.write(&mut params, target);
+        Some(params.replace_in_path(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_kit::TestKit, RouteStore};
+
+    fn read(pagination: &Pagination, query: &str) -> Page {
+        pagination.read(&QueryParams::parse(query))
+    }
+
+    fn page(page: u32, per_page: u32) -> Page {
+        Page { page, per_page }
+    }
+
+    #[test]
+    fn missing_and_invalid_params_fall_back_to_the_defaults() {
+        let pagination = Pagination::new();
+        assert_eq!(read(&pagination, ""), page(1, 20));
+        assert_eq!(read(&pagination, "page=3&per_page=50"), page(3, 50));
+        assert_eq!(read(&pagination, "page=0&per_page=0"), page(1, 1));
+        assert_eq!(read(&pagination, "page=-2&per_page=ten"), page(1, 20));
+        assert_eq!(read(&pagination, "page=2.5"), page(1, 20));
+    }
+
+    #[test]
+    fn per_page_is_clamped_to_the_maximum() {
+        let pagination = Pagination::new().with_max_per_page(50);
+        assert_eq!(read(&pagination, "per_page=51"), page(1, 50));
+        assert_eq!(read(&pagination, "per_page=50"), page(1, 50));
+
+        // The default is clamped too.
+        let pagination = Pagination::new()
+            .with_default_per_page(80)
+            .with_max_per_page(50);
+        assert_eq!(read(&pagination, ""), page(1, 50));
+    }
+
+    #[test]
+    fn write_leaves_out_the_defaults() {
+        let pagination = Pagination::new().with_keys("p", "n");
+        let mut params = QueryParams::parse("q=rust&p=4&n=50");
+        pagination.write(&mut params, page(1, 20));
+        assert_eq!(params, QueryParams::parse("q=rust"));
+
+        pagination.write(&mut params, page(2, 10));
+        assert_eq!(pagination.read(&params), page(2, 10));
+        assert_eq!(params.get("q"), Some("rust"));
+    }
+
+    #[test]
+    fn offset_path_stays_on_or_after_the_first_page() {
+        let pagination = Pagination::new();
+        assert_eq!(
+            pagination.offset_path("/users", 1).as_deref(),
+            Some("/users?page=2")
+        );
+        assert_eq!(
+            pagination.offset_path("/users?page=2", -1).as_deref(),
+            Some("/users")
+        );
+        assert_eq!(pagination.offset_path("/users", -1), None);
+        assert_eq!(pagination.offset_path("/users?page=3", -3), None);
+        assert_eq!(pagination.offset_path("/users?page=3", 0), None);
+    }
+
+    #[test]
+    fn page_arithmetic_clamps_invalid_pages() {
+        assert_eq!(page(1, 20).offset(), 0);
+        assert_eq!(page(3, 20).offset(), 40);
+        assert_eq!(page(0, 20).offset(), 0);
+        assert_eq!(page(u32::MAX, 1).offset(), u32::MAX as usize - 1);
+
+        assert_eq!(page(1, 20).page_count(0), 1);
+        assert_eq!(page(1, 20).page_count(41), 3);
+        assert_eq!(page(1, 0).page_count(5), 5);
+    }
+
+    #[test]
+    fn next_and_prev_page_navigate() {
+        let kit = TestKit::new("/users?per_page=50");
+        kit.store.next_page();
+        kit.store.next_page();
+        kit.assert_route("/users?per_page=50&page=3");
+        kit.store.prev_page();
+        kit.store.prev_page();
+        kit.assert_route("/users?per_page=50");
+        assert_eq!(kit.history().len(), 5);
+
+        // There is no page before the first one.
+        kit.store.prev_page();
+        kit.assert_route("/users?per_page=50");
+        assert_eq!(kit.history().len(), 5);
+    }
+}
@@ -0,0 +1,87 @@
+//! Reporting the navigation trail and router errors to an error
+//! reporting service (requires the `error-reporting` feature), so that
+//! crash reports show the navigations which led to them. With the
+//! `sentry` feature, [SentryBreadcrumbs] records them as Sentry
+//! breadcrumbs.
+//!
+//! Paths are [redacted](redact_path) before they are reported: query
+//! values and fragments, which may contain personal data or tokens, are
+//! removed.
+
+use crate::canonical::split_path;
+
+/// A compact description of a committed navigation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavigationReport {
+    /// The name of the route action, e.g. `ChangeRoute`.
+    pub action: &'static str,
+    /// The redacted path of the previous route.
+    pub from: String,
+    /// The redacted path of the current route.
+    pub to: String,
+}
+
+/// Receives the navigations and errors of the
+/// [RouteMiddleware](crate::RouteMiddleware), see
+/// [with_error_reporting()](crate::RouteMiddleware::with_error_reporting).
+pub trait ErrorReportingHook {
+    /// Invoked after every route action has been reduced.
+    fn navigation(&self, report: &NavigationReport);
+    /// Invoked with the description of every internal router error.
+    fn router_error(&self, message: &str);
+}
+
+/// Redact the `path` for reporting, keeping the path portion and the
+/// query keys, e.g. `/search?q=secret#top` becomes `/search?q`.
+pub fn redact_path(path: &str) -> String {
+    let (path_portion, remainder) = split_path(path);
+    let query = remainder
+        .strip_prefix('?')
+        .map(|query| query.split('#').next().unwrap_or_default())
+        .unwrap_or_default();
+    let keys: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split('=').next().unwrap_or_default())
+        .collect();
+    if keys.is_empty() {
+        path_portion.to_string()
+    } else {
+        format!("{}?{}", path_portion, keys.join("&"))
+    }
+}
+
+/// An [ErrorReportingHook] which records navigations and router errors
+/// as Sentry breadcrumbs (requires the `sentry` feature).
+#[cfg(feature = "sentry")]
+#[derive(Debug, Clone, Default)]
+pub struct SentryBreadcrumbs;
+
+#[cfg(feature = "sentry")]
+impl ErrorReportingHook for SentryBreadcrumbs {
+    fn navigation(&self, report: &NavigationReport) {
+        use sentry_core::protocol::{Breadcrumb, Map};
+
+        let mut data = Map::new();
+        data.insert("from".to_string(), report.from.clone().into());
+        data.insert("to".to_string(), report.to.clone().into());
+        sentry_core::add_breadcrumb(Breadcrumb {
+            ty: "navigation".to_string(),
+            category: Some("navigation".to_string()),
+            message: Some(report.action.to_string()),
+            data,
+            ..Breadcrumb::default()
+        });
+    }
+
+    fn router_error(&self, message: &str) {
+        use sentry_core::protocol::{Breadcrumb, Level};
+
+        sentry_core::add_breadcrumb(Breadcrumb {
+            category: Some("router".to_string()),
+            level: Level::Error,
+            message: Some(message.to_string()),
+            ..Breadcrumb::default()
+        });
+    }
+}
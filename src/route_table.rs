@@ -0,0 +1,207 @@
+//! A declarative table of path patterns, used by the
+//! [RouteMiddleware](crate::RouteMiddleware) to resolve the browser paths
+//! which the route service is unable to parse, before they are handled
+//! as not found (see
+//! [with_route_table()](crate::RouteMiddleware::with_route_table)).
+//! Requires the `route-table` feature.
+//!
+//! Patterns are made of `/` separated segments, which are either
+//! literals, `:name` parameters matching any one segment, or a final `*`
+//! matching the remaining segments:
+//!
+//! ```ignore
+//! let table = RouteTable::new()
+//!     .route("/users/:id", 0, |m| Some(AppRoute::User(m.param("id")?.parse().ok()?)))
+//!     .route("/users/new", 1, |_| Some(AppRoute::NewUser))
+//!     .route("/docs/*", 0, |m| Some(AppRoute::Docs(m.rest().to_string())))
+//!     .catch_all(|_| AppRoute::NotFound);
+//! ```
+//!
+//! When several patterns match a path, the one with the highest priority
+//! wins, and among those, the one declared first. In debug builds, a
+//! warning is logged when a pattern overlaps with a pattern of the same
+//! priority declared before it, as the order of declaration then decides
+//! which one is used.
+
+use crate::canonical::split_path;
+
+type ConstructorFn<R> = dyn Fn(&PatternMatch) -> Option<R>;
+type CatchAllFn<R> = dyn Fn(&str) -> R;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest,
+}
+
+fn parse_pattern(pattern: &str) -> Vec<Segment> {
+    pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if segment == "*" {
+                Segment::Rest
+            } else if let Some(name) = segment.strip_prefix(':') {
+                Segment::Param(name.to_string())
+            } else {
+                Segment::Literal(segment.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Whether some path could match both patterns.
+#[cfg(debug_assertions)]
+fn overlaps(a: &[Segment], b: &[Segment]) -> bool {
+    let mut a = a.iter();
+    let mut b = b.iter();
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return true,
+            (Some(Segment::Rest), _) | (_, Some(Segment::Rest)) => return true,
+            (None, Some(_)) | (Some(_), None) => return false,
+            (Some(Segment::Literal(a)), Some(Segment::Literal(b))) if a != b => return false,
+            _ => {}
+        }
+    }
+}
+
+/// The parameters captured by matching a path against a pattern of the
+/// [RouteTable].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternMatch {
+    params: Vec<(String, String)>,
+    rest: String,
+}
+
+impl PatternMatch {
+    /// The value of the `:name` segment.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The segments matched by a final `*`, joined with `/`.
+    pub fn rest(&self) -> &str {
+        &self.rest
+    }
+}
+
+struct TableEntry<R> {
+    pattern: String,
+    segments: Vec<Segment>,
+    priority: i32,
+    constructor: Box<ConstructorFn<R>>,
+}
+
+impl<R> TableEntry<R> {
+    fn match_path(&self, path: &str) -> Option<PatternMatch> {
+        let mut path_segments = path.split('/').filter(|segment| !segment.is_empty());
+        let mut matched = PatternMatch::default();
+        for segment in &self.segments {
+            match segment {
+                Segment::Rest => {
+                    matched.rest = path_segments.collect::<Vec<_>>().join("/");
+                    return Some(matched);
+                }
+                Segment::Literal(literal) => {
+                    if path_segments.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    let value = path_segments.next()?;
+                    matched.params.push((name.clone(), value.to_string()));
+                }
+            }
+        }
+        match path_segments.next() {
+            Some(_) => None,
+            None => Some(matched),
+        }
+    }
+}
+
+/// A table of path patterns and the constructors of their routes, see
+/// the [module documentation](self).
+pub struct RouteTable<R> {
+    entries: Vec<TableEntry<R>>,
+    catch_all: Option<Box<CatchAllFn<R>>>,
+}
+
+impl<R> RouteTable<R> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            catch_all: None,
+        }
+    }
+
+    /// Add a `pattern` with a `priority`, whose route is created by
+    /// `constructor` from the captured parameters. If the constructor
+    /// returns `None` (e.g. a parameter doesn't parse), the next matching
+    /// pattern is tried.
+    pub fn route<S, F>(mut self, pattern: S, priority: i32, constructor: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&PatternMatch) -> Option<R> + 'static,
+    {
+        let pattern = pattern.into();
+        let segments = parse_pattern(&pattern);
+        #[cfg(debug_assertions)]
+        for entry in &self.entries {
+            if entry.priority == priority && overlaps(&entry.segments, &segments) {
+                crate::logging::warning!(
+                    "Route table pattern {:?} overlaps with {:?} of the same priority {}, \
+                     the pattern declared first is used",
+                    pattern,
+                    entry.pattern,
+                    priority
+                );
+            }
+        }
+        self.entries.push(TableEntry {
+            pattern,
+            segments,
+            priority,
+            constructor: Box::new(constructor),
+        });
+        // A stable sort keeps the order of declaration within a priority.
+        self.entries
+            .sort_by_key(|entry| std::cmp::Reverse(entry.priority));
+        self
+    }
+
+    /// The route for the paths which don't match any pattern.
+    pub fn catch_all<F>(mut self, constructor: F) -> Self
+    where
+        F: Fn(&str) -> R + 'static,
+    {
+        self.catch_all = Some(Box::new(constructor));
+        self
+    }
+
+    /// The route for `path`, ignoring its query and fragment.
+    pub fn resolve(&self, path: &str) -> Option<R> {
+        let (path, _) = split_path(path);
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.match_path(path).map(|matched| (entry, matched)))
+            .find_map(|(entry, matched)| (entry.constructor)(&matched))
+            .or_else(|| self.catch_all.as_ref().map(|catch_all| catch_all(path)))
+    }
+
+    /// The patterns in the table, in the order they are tried.
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.pattern.as_str())
+    }
+}
+
+impl<R> Default for RouteTable<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,60 @@
+//! Multiple independent routers in one store, e.g. a primary router
+//! synced with the browser's URL, and a secondary router for an embedded
+//! surface (a preview pane, or a wizard with its own URL-less history)
+//! backed by a [MemoryRouteService](crate::memory::MemoryRouteService).
+//!
+//! Each additional [RouteMiddleware](crate::RouteMiddleware) is given a
+//! [RouterId] with
+//! [with_router_id()](crate::RouteMiddleware::with_router_id). Actions
+//! for that router are wrapped in [RouteAction::Routed], using
+//! [RouteAction::for_router()], and the middleware wraps the actions it
+//! reduces and dispatches in the same way, so the reducer can tell which
+//! router they belong to. The middleware without an id handles the
+//! actions which are not wrapped.
+//!
+//! The state provides the route of each router with an id by
+//! implementing [NamedRouteState].
+
+use crate::RouteAction;
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Identifies one of several routers in a store, see the
+/// [module documentation](self).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RouterId(pub u32);
+
+impl Display for RouterId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "router {}", self.0)
+    }
+}
+
+/// The state of a store with several routers. The route of the router
+/// without an id is provided by [RouteState](crate::RouteState), and the
+/// routes of the routers with ids by this trait.
+pub trait NamedRouteState<SR> {
+    fn get_route_for(&self, id: RouterId) -> &SR;
+}
+
+impl<SR> RouteAction<SR> {
+    /// Wrap this action in a [RouteAction::Routed] for the `router`.
+    pub fn for_router(self, router: RouterId) -> Self {
+        RouteAction::Routed {
+            router,
+            action: Box::new(self),
+        }
+    }
+
+    /// The router this action is for (`None` for the router without an
+    /// id), and the unwrapped action.
+    pub fn routed(&self) -> (Option<RouterId>, &RouteAction<SR>) {
+        match self {
+            RouteAction::Routed { router, action } => (Some(*router), action),
+            action => (None, action),
+        }
+    }
+}
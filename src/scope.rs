@@ -0,0 +1,99 @@
+//! Scopes of routes, such as "any route under `/settings/*`", so that
+//! subscribers interested in a section of the application (e.g. to
+//! highlight it in a sidebar, or to switch layouts) are notified only
+//! when entering, leaving or moving within it.
+//!
+//! Scopes are registered with
+//! [with_route_scope()](crate::RouteMiddleware::with_route_scope), for
+//! routes implementing [RouteScope]. After each navigation is committed,
+//! the [RouteMiddleware](crate::RouteMiddleware) emits
+//! [RouteEvent::scope_changed()](crate::RouteEvent::scope_changed) for
+//! every scope it has affected.
+//!
+//! The same matching decides whether a link is active, see [is_active()]
+//! and [is_active_prefix()].
+
+use crate::{canonical::split_path, RouteState};
+use switch_router::SwitchRoute;
+
+/// Decides which routes belong to a scope.
+pub trait RouteScope: SwitchRoute {
+    /// Whether the route belongs to `scope`. By default, a scope ending
+    /// with `/*` (e.g. `/settings/*`) contains the route at its prefix
+    /// and all the routes beneath it, otherwise it contains only the
+    /// route with that path. The query and fragment are ignored.
+    fn in_scope(&self, scope: &str) -> bool {
+        let path = self.path();
+        let (path, _) = split_path(&path);
+        let path = path.trim_end_matches('/');
+        match scope.strip_suffix("/*") {
+            Some(prefix) => {
+                let prefix = prefix.trim_end_matches('/');
+                path == prefix
+                    || path
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+            None => path == scope.trim_end_matches('/'),
+        }
+    }
+}
+
+/// How a navigation has affected a scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScopeTransition {
+    /// Navigated from a route outside the scope to a route inside it.
+    Entered,
+    /// Navigated from a route inside the scope to a route outside it.
+    Left,
+    /// Navigated between two routes inside the scope.
+    MovedWithin,
+}
+
+impl ScopeTransition {
+    /// The transition of a navigation, given whether the previous and
+    /// current routes are in the scope, or `None` if the scope wasn't
+    /// affected.
+    pub(crate) fn between(previous: bool, current: bool) -> Option<Self> {
+        match (previous, current) {
+            (false, true) => Some(ScopeTransition::Entered),
+            (true, false) => Some(ScopeTransition::Left),
+            (true, true) => Some(ScopeTransition::MovedWithin),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Whether `route` is the current route of `state`, ignoring the query
+/// and fragment, e.g. to highlight the link to the current page.
+pub fn is_active<State, R>(state: &State, route: &R) -> bool
+where
+    State: RouteState<R>,
+    R: RouteScope,
+{
+    route_is_active(state.get_route(), route)
+}
+
+/// Whether the current route of `state` is `route` or one of the routes
+/// beneath it, e.g. to highlight the section of a navigation menu
+/// containing the current page.
+pub fn is_active_prefix<State, R>(state: &State, route: &R) -> bool
+where
+    State: RouteState<R>,
+    R: RouteScope,
+{
+    route_is_active_prefix(state.get_route(), route)
+}
+
+/// [is_active()] for the `current` route.
+pub(crate) fn route_is_active<R: RouteScope>(current: &R, route: &R) -> bool {
+    let path = route.path();
+    current.in_scope(split_path(&path).0)
+}
+
+/// [is_active_prefix()] for the `current` route.
+pub(crate) fn route_is_active_prefix<R: RouteScope>(current: &R, route: &R) -> bool {
+    let path = route.path();
+    let prefix = split_path(&path).0.trim_end_matches('/');
+    current.in_scope(&format!("{}/*", prefix))
+}
@@ -0,0 +1,194 @@
+//! Scroll positions of the visited routes, so that the application can
+//! restore the position of a page when the user returns to it (e.g. with
+//! the back button or after a reload). Requires the `scroll` feature.
+//!
+//! The application saves the position of the current route with
+//! [RouteMiddleware::save_scroll_position()](crate::RouteMiddleware::save_scroll_position),
+//! typically before navigating away or while scrolling, and reads it
+//! back with
+//! [RouteMiddleware::scroll_position()](crate::RouteMiddleware::scroll_position)
+//! once the route has been rendered. Positions are tracked by path. To
+//! survive a reload, they are also written to a [KeyValueStorage], with
+//! the [version](crate::storage) of their format.
+
+use crate::storage::{self, KeyValueStorage, MemoryStorage};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashMap};
+
+const KEY_PREFIX: &str = "switch-router-middleware:scroll:";
+
+/// The format versions of the persisted positions which can be read.
+const SUPPORTED_VERSIONS: &[u32] = &[1];
+
+/// The scroll offsets of a page, in CSS pixels.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScrollPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl ScrollPosition {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (x, y) = value.split_once(',')?;
+        Some(Self::new(x.parse().ok()?, y.parse().ok()?))
+    }
+}
+
+/// The tracked scroll positions, by path.
+pub struct ScrollPositions {
+    positions: RefCell<HashMap<String, ScrollPosition>>,
+    storage: Box<dyn KeyValueStorage>,
+}
+
+impl ScrollPositions {
+    pub fn new() -> Self {
+        Self::with_storage(MemoryStorage::new())
+    }
+
+    /// Persist the positions in `storage`, e.g.
+    /// [SessionStorage](crate::web::SessionStorage).
+    pub fn with_storage<S: KeyValueStorage + 'static>(storage: S) -> Self {
+        Self {
+            positions: RefCell::new(HashMap::new()),
+            storage: Box::new(storage),
+        }
+    }
+
+    fn persist(&self, path: &str, position: ScrollPosition) {
+        self.storage.set(
+            &format!("{}{}", KEY_PREFIX, path),
+            &storage::versioned(&format!("{},{}", position.x, position.y)),
+        );
+    }
+
+    /// Track `position` as the scroll position of the route with `path`.
+    pub(crate) fn save(&self, path: String, position: ScrollPosition) {
+        self.persist(&path, position);
+        self.positions.borrow_mut().insert(path, position);
+    }
+
+    /// The scroll position of the route with `path`, if one was saved.
+    pub(crate) fn get(&self, path: &str) -> Option<ScrollPosition> {
+        if let Some(position) = self.positions.borrow().get(path) {
+            return Some(*position);
+        }
+        let key = format!("{}{}", KEY_PREFIX, path);
+        let value = self.storage.get(&key)?;
+        let position = match storage::supported_payload(&value, SUPPORTED_VERSIONS)
+            .and_then(ScrollPosition::parse)
+        {
+            Some(position) => position,
+            None => {
+                self.storage.remove(&key);
+                return None;
+            }
+        };
+        self.positions
+            .borrow_mut()
+            .insert(path.to_string(), position);
+        Some(position)
+    }
+
+    /// The tracked pairs of path and scroll position, ordered by path.
+    pub(crate) fn pairs(&self) -> Vec<(String, ScrollPosition)> {
+        let mut pairs: Vec<_> = self
+            .positions
+            .borrow()
+            .iter()
+            .map(|(path, position)| (path.clone(), *position))
+            .collect();
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        pairs
+    }
+
+    /// Replace the tracked pairs with `pairs` (of path and scroll
+    /// position).
+    pub(crate) fn restore(&self, pairs: Vec<(String, ScrollPosition)>) {
+        let mut positions = self.positions.borrow_mut();
+        for path in positions.keys() {
+            self.storage.remove(&format!("{}{}", KEY_PREFIX, path));
+        }
+        positions.clear();
+        for (path, position) in pairs {
+            self.persist(&path, position);
+            positions.insert(path, position);
+        }
+    }
+}
+
+impl Default for ScrollPositions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    /// Shares a [MemoryStorage] between [ScrollPositions], as the
+    /// session storage is shared across a reload.
+    #[derive(Clone)]
+    struct SharedStorage(Rc<MemoryStorage>);
+
+    impl KeyValueStorage for SharedStorage {
+        fn get(&self, key: &str) -> Option<String> {
+            self.0.get(key)
+        }
+
+        fn set(&self, key: &str, value: &str) {
+            self.0.set(key, value)
+        }
+
+        fn remove(&self, key: &str) {
+            self.0.remove(key)
+        }
+    }
+
+    #[test]
+    fn positions_survive_a_reload() {
+        let storage = SharedStorage(Rc::new(MemoryStorage::new()));
+        let positions = ScrollPositions::with_storage(storage.clone());
+        positions.save("/feed".to_string(), ScrollPosition::new(0.0, 1250.5));
+
+        let reloaded = ScrollPositions::with_storage(storage);
+        assert_eq!(
+            reloaded.get("/feed"),
+            Some(ScrollPosition::new(0.0, 1250.5))
+        );
+        assert_eq!(reloaded.get("/settings"), None);
+    }
+
+    #[test]
+    fn malformed_positions_are_discarded() {
+        let storage = SharedStorage(Rc::new(MemoryStorage::new()));
+        storage.set(
+            &format!("{}/feed", KEY_PREFIX),
+            &storage::versioned("twelve"),
+        );
+        let positions = ScrollPositions::with_storage(storage.clone());
+        assert_eq!(positions.get("/feed"), None);
+        assert_eq!(storage.get(&format!("{}/feed", KEY_PREFIX)), None);
+    }
+
+    #[test]
+    fn restore_replaces_the_tracked_positions() {
+        let storage = SharedStorage(Rc::new(MemoryStorage::new()));
+        let positions = ScrollPositions::with_storage(storage.clone());
+        positions.save("/feed".to_string(), ScrollPosition::new(0.0, 10.0));
+        positions.restore(vec![("/users".to_string(), ScrollPosition::new(0.0, 20.0))]);
+
+        assert_eq!(
+            positions.pairs(),
+            vec![("/users".to_string(), ScrollPosition::new(0.0, 20.0))]
+        );
+        assert_eq!(storage.get(&format!("{}/feed", KEY_PREFIX)), None);
+    }
+}
@@ -0,0 +1,91 @@
+//! Choosing the route service at runtime. [SwitchRouteService] has
+//! generic methods, so it can't be used as a trait object.
+//! [RouteServiceObject] wraps any route service behind the object safe
+//! [DynRouteService] trait, so that the backend (browser, hash, memory,
+//! server) can be chosen at runtime, e.g. switching to a
+//! [MemoryRouteService](crate::memory::MemoryRouteService) when running
+//! inside a test harness.
+
+use switch_router::{Callback, SwitchRoute, SwitchRouteService};
+
+/// The object safe subset of [SwitchRouteService], implemented for all
+/// route services.
+pub trait DynRouteService<R> {
+    fn set_route(&mut self, route: R);
+    fn replace_route(&mut self, route: R) -> R;
+    fn register_callback(&mut self, callback: &Callback<R>);
+    fn back(&mut self) -> Option<R>;
+    fn get_route(&self) -> R;
+}
+
+impl<R, RS> DynRouteService<R> for RS
+where
+    R: SwitchRoute,
+    RS: SwitchRouteService<Route = R>,
+{
+    fn set_route(&mut self, route: R) {
+        SwitchRouteService::set_route(self, route)
+    }
+
+    fn replace_route(&mut self, route: R) -> R {
+        SwitchRouteService::replace_route(self, route)
+    }
+
+    fn register_callback(&mut self, callback: &Callback<R>) {
+        SwitchRouteService::register_callback(self, callback)
+    }
+
+    fn back(&mut self) -> Option<R> {
+        SwitchRouteService::back(self)
+    }
+
+    fn get_route(&self) -> R {
+        SwitchRouteService::get_route(self)
+    }
+}
+
+/// A boxed route service, whose implementation is chosen at runtime.
+pub struct RouteServiceObject<R> {
+    service: Box<dyn DynRouteService<R>>,
+}
+
+impl<R> RouteServiceObject<R>
+where
+    R: SwitchRoute,
+{
+    pub fn new<RS>(service: RS) -> Self
+    where
+        RS: SwitchRouteService<Route = R> + 'static,
+    {
+        Self {
+            service: Box::new(service),
+        }
+    }
+}
+
+impl<R> SwitchRouteService for RouteServiceObject<R>
+where
+    R: SwitchRoute,
+{
+    type Route = R;
+
+    fn set_route<SRI: Into<R>>(&mut self, switch_route: SRI) {
+        self.service.set_route(switch_route.into())
+    }
+
+    fn replace_route<SRI: Into<R>>(&mut self, switch_route: SRI) -> R {
+        self.service.replace_route(switch_route.into())
+    }
+
+    fn register_callback(&mut self, callback: &Callback<R>) {
+        self.service.register_callback(callback)
+    }
+
+    fn back(&mut self) -> Option<R> {
+        self.service.back()
+    }
+
+    fn get_route(&self) -> R {
+        self.service.get_route()
+    }
+}
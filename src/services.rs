@@ -0,0 +1,43 @@
+//! A typemap of application provided services, made available to guards
+//! via the [GuardContext](crate::guard::GuardContext).
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+};
+
+/// A collection of services, with at most one service of each type.
+#[derive(Default)]
+pub struct Services {
+    services: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Services {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a `service`, replacing any existing service of the same
+    /// type.
+    pub fn insert<T: 'static>(&mut self, service: T) {
+        self.services.insert(TypeId::of::<T>(), Box::new(service));
+    }
+
+    /// Get the service of type `T`.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.services
+            .get(&TypeId::of::<T>())
+            .and_then(|service| service.downcast_ref::<T>())
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.services.contains_key(&TypeId::of::<T>())
+    }
+}
+
+impl Debug for Services {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Services({} services)", self.services.len())
+    }
+}
@@ -0,0 +1,176 @@
+//! Absolute links to routes, for "copy link" buttons and sharing.
+//!
+//! A [ShareLink] turns a route into an absolute URL (the origin, the
+//! [Href] base path and the route's path), stripping the volatile query
+//! parameters which shouldn't be passed on to whoever receives the link
+//! (e.g. tracking or session parameters).
+//! [RouteStore::share_link()](crate::RouteStore::share_link) generates
+//! the link for the current route:
+//!
+//! ```ignore
+//! let share = ShareLink::new("https://example.com")
+//!     .with_href(Href::new().with_base_path("/app"))
+//!     .with_volatile_param("session")
+//!     .with_volatile_param("utm_*");
+//! // https://example.com/app/items/5?tab=details
+//! let link = store.share_link(&share);
+//! ```
+//!
+//! In the other direction, [ShareLink::parse_link()] turns a link
+//! pasted by the user (e.g. when support asks for "the link you were
+//! on") back into a route, if it is a link to this application.
+//! [RouteStore::navigate_to_link()](crate::RouteStore::navigate_to_link)
+//! navigates to it directly, or the parsed route can be shown for
+//! confirmation first.
+
+use crate::{canonical::split_path, href::Href, query::QueryParams};
+use std::fmt::{self, Display};
+use switch_router::SwitchRoute;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Configuration for generating absolute links to routes, see the
+/// [module documentation](self).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShareLink {
+    origin: String,
+    href: Href,
+    volatile_params: Vec<String>,
+}
+
+impl ShareLink {
+    /// Create a new [ShareLink] for the application served at `origin`,
+    /// e.g. `https://example.com`.
+    pub fn new<S: Into<String>>(origin: S) -> Self {
+        Self {
+            origin: origin.into().trim_end_matches('/').to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Create a new [ShareLink] for the origin of the current page, or
+    /// `None` if it isn't available.
+    #[cfg(feature = "web")]
+    pub fn from_location() -> Option<Self> {
+        crate::web::location_origin().map(Self::new)
+    }
+
+    /// Generate the links with `href`, e.g. to use the same base path as
+    /// [RouteMiddleware::with_href()](crate::RouteMiddleware::with_href).
+    pub fn with_href(mut self, href: Href) -> Self {
+        self.href = href;
+        self
+    }
+
+    /// Strip the query parameter `key` from the links. A `key` ending in
+    /// `*` strips all the parameters starting with the rest of it, e.g.
+    /// `utm_*`.
+    pub fn with_volatile_param<S: Into<String>>(mut self, key: S) -> Self {
+        self.volatile_params.push(key.into());
+        self
+    }
+
+    fn is_volatile(&self, key: &str) -> bool {
+        self.volatile_params
+            .iter()
+            .any(|volatile| match volatile.strip_suffix('*') {
+                Some(prefix) => key.starts_with(prefix),
+                None => key == volatile,
+            })
+    }
+
+    /// The route `path` with the volatile query parameters stripped.
+    pub fn strip_volatile(&self, path: &str) -> String {
+        if self.volatile_params.is_empty() {
+            return path.to_string();
+        }
+        let (_, remainder) = split_path(path);
+        if !remainder.starts_with('?') {
+            return path.to_string();
+        }
+        let params: QueryParams = QueryParams::from_path(path)
+            .iter()
+            .filter(|(key, _)| !self.is_volatile(key))
+            .collect();
+        params.replace_in_path(path)
+    }
+
+    /// The absolute link to the route with the specified `path`.
+    pub fn link_for_path(&self, path: &str) -> String {
+        format!(
+            "{}{}",
+            self.origin,
+            self.href.href_for_path(&self.strip_volatile(path))
+        )
+    }
+
+    /// The absolute link to `route`.
+    pub fn link<R: SwitchRoute>(&self, route: &R) -> String {
+        self.link_for_path(&route.path())
+    }
+
+    /// The route path of a `pasted` link to this application: an
+    /// absolute link with the configured origin, or a link without an
+    /// origin (starting with `/`), under the base path.
+    pub fn parse_link_path(&self, pasted: &str) -> Result<String, LinkError> {
+        let pasted = pasted.trim();
+        if pasted.is_empty() {
+            return Err(LinkError::Empty);
+        }
+        // `//host/path` is a link to another origin.
+        let href = if pasted.starts_with('/') && !pasted.starts_with("//") {
+            pasted
+        } else {
+            match pasted.strip_prefix(self.origin.as_str()) {
+                Some(href)
+                    if !self.origin.is_empty() && (href.is_empty() || href.starts_with('/')) =>
+                {
+                    href
+                }
+                _ => return Err(LinkError::OtherOrigin(pasted.to_string())),
+            }
+        };
+        let href = if href.is_empty() { "/" } else { href };
+        self.href
+            .path_for_href(href)
+            .ok_or_else(|| LinkError::OutsideBasePath(pasted.to_string()))
+    }
+
+    /// The route of a `pasted` link to this application, see
+    /// [parse_link_path()](Self::parse_link_path).
+    pub fn parse_link<R: SwitchRoute>(&self, pasted: &str) -> Result<R, LinkError> {
+        self.parse_link_path(pasted).map(|path| R::switch(&path))
+    }
+}
+
+/// Why a pasted link isn't a link to the application, see
+/// [ShareLink::parse_link()].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    /// Nothing was pasted.
+    Empty,
+    /// The link is to another origin (or isn't a link at all).
+    OtherOrigin(String),
+    /// The link is to this origin, but outside of the application's base
+    /// path.
+    OutsideBasePath(String),
+}
+
+impl Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkError::Empty => write!(f, "the link is empty"),
+            LinkError::OtherOrigin(link) => {
+                write!(f, "{:?} isn't a link to this application", link)
+            }
+            LinkError::OutsideBasePath(link) => {
+                write!(f, "{:?} is outside of this application", link)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
@@ -0,0 +1,107 @@
+//! Routes which are expensive to clone. The middleware and
+//! [RouteAction](crate::RouteAction) clone routes freely (into the
+//! store's state, the route service and dispatched actions), which is
+//! only cheap for small route types.
+//!
+//! Route types containing large structures (e.g. a parsed filter tree)
+//! can be wrapped in a [SharedRoute], which is reference counted, and
+//! use `SharedRoute<MyRoute>` as the route type of the middleware and
+//! the route service.
+
+use std::{fmt::Debug, ops::Deref, rc::Rc};
+use switch_router::SwitchRoute;
+
+/// A reference counted route, which is cheap to clone. See the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct SharedRoute<R>(Rc<R>);
+
+impl<R> SharedRoute<R> {
+    pub fn new(route: R) -> Self {
+        Self(Rc::new(route))
+    }
+
+    /// The shared route.
+    pub fn get(&self) -> &R {
+        &self.0
+    }
+
+    /// Take the route if this is the only reference to it, otherwise
+    /// clone it.
+    pub fn into_inner(self) -> R
+    where
+        R: Clone,
+    {
+        Rc::try_unwrap(self.0).unwrap_or_else(|route| (*route).clone())
+    }
+}
+
+impl<R> Clone for SharedRoute<R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<R> PartialEq for SharedRoute<R>
+where
+    R: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl<R> Eq for SharedRoute<R> where R: Eq {}
+
+impl<R> Deref for SharedRoute<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.0
+    }
+}
+
+impl<R> From<R> for SharedRoute<R> {
+    fn from(route: R) -> Self {
+        Self::new(route)
+    }
+}
+
+impl<R> From<Rc<R>> for SharedRoute<R> {
+    fn from(route: Rc<R>) -> Self {
+        Self(route)
+    }
+}
+
+impl<R> SwitchRoute for SharedRoute<R>
+where
+    R: SwitchRoute,
+{
+    fn path(&self) -> String {
+        self.0.path()
+    }
+
+    fn switch(route: &str) -> Self {
+        Self::new(R::switch(route))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<R> serde::Serialize for SharedRoute<R>
+where
+    R: serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, R> serde::Deserialize<'de> for SharedRoute<R>
+where
+    R: serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        R::deserialize(deserializer).map(Self::new)
+    }
+}
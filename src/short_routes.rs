@@ -0,0 +1,145 @@
+//! Short, opaque tokens standing in for long routes, for URLs which are
+//! typed in (kiosks) or printed (QR codes), e.g. `/r/K3M9Q2XT` in place
+//! of `/reports/2023/q4?region=emea&view=v1;s:-c`. Requires the
+//! `short-routes` feature.
+//!
+//! [ShortRoutes::shorten()] stores the route's path in a
+//! [KeyValueStorage] (such as
+//! [LocalStorage](crate::web::LocalStorage), or a cache of a table kept
+//! by the application's server) under a token, and returns the token
+//! route. When the token route arrives from the browser, the middleware
+//! (configured with
+//! [with_short_routes()](crate::RouteMiddleware::with_short_routes))
+//! resolves it, and replaces the URL with the full route.
+//!
+//! ```ignore
+//! let short_routes = ShortRoutes::new(
+//!     LocalStorage::new(),
+//!     AppRoute::Short,
+//!     |route: &AppRoute| match route {
+//!         AppRoute::Short(token) => Some(token.clone()),
+//!         _ => None,
+//!     },
+//! );
+//! let qr_link = share.link(&short_routes.shorten(&route));
+//! ```
+
+use crate::{
+    hash::fnv1a,
+    logging::{debug, error},
+    storage::{self, KeyValueStorage},
+};
+use switch_router::SwitchRoute;
+
+const KEY_PREFIX: &str = "switch-router-middleware:short:";
+
+/// The format versions of the stored paths which can be read.
+const SUPPORTED_VERSIONS: &[u32] = &[1];
+
+/// The length of the tokens by default, 40 bits of the hash.
+pub const DEFAULT_TOKEN_LENGTH: usize = 8;
+
+/// Crockford's base 32 alphabet, without the letters which are easily
+/// confused with digits. Upper case letters and digits are encoded
+/// compactly by QR codes.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Generate a token of `length` characters (at most 12) for the
+/// specified `path`. The same path always produces the same token.
+pub fn short_token(path: &str, length: usize) -> String {
+    let hash = fnv1a(path);
+    (0..length.min(12))
+        .map(|index| ALPHABET[((hash >> (index * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+type RouteTokenFn<R> = dyn Fn(&R) -> Option<String>;
+
+/// Maps routes to short token routes, see the
+/// [module documentation](self).
+pub struct ShortRoutes<R> {
+    storage: Box<dyn KeyValueStorage>,
+    token_route: Box<dyn Fn(String) -> R>,
+    route_token: Box<RouteTokenFn<R>>,
+    token_length: usize,
+}
+
+impl<R> ShortRoutes<R>
+where
+    R: SwitchRoute,
+{
+    /// Create a new [ShortRoutes].
+    ///
+    /// + `storage` stores the paths of the shortened routes by token.
+    /// + `token_route` constructs the route which carries the token in
+    ///   the URL (e.g. `/r/<token>`).
+    /// + `route_token` extracts the token from such a route, returning
+    ///   `None` for every other route.
+    pub fn new<S, TR, RT>(storage: S, token_route: TR, route_token: RT) -> Self
+    where
+        S: KeyValueStorage + 'static,
+        TR: Fn(String) -> R + 'static,
+        RT: Fn(&R) -> Option<String> + 'static,
+    {
+        Self {
+            storage: Box::new(storage),
+            token_route: Box::new(token_route),
+            route_token: Box::new(route_token),
+            token_length: DEFAULT_TOKEN_LENGTH,
+        }
+    }
+
+    /// The length of the tokens, [DEFAULT_TOKEN_LENGTH] by default, and
+    /// at most 12. Shorter tokens are more likely to collide, in which
+    /// case another token is generated.
+    pub fn with_token_length(mut self, length: usize) -> Self {
+        self.token_length = length.clamp(1, 12);
+        self
+    }
+
+    fn key(token: &str) -> String {
+        format!("{}{}", KEY_PREFIX, token)
+    }
+
+    fn load(&self, token: &str) -> Option<String> {
+        let value = self.storage.get(&Self::key(token))?;
+        storage::supported_payload(&value, SUPPORTED_VERSIONS).map(ToString::to_string)
+    }
+
+    /// Store the path of `route`, and return the token route to place
+    /// in the URL instead. Shortening the same route again returns the
+    /// same token route.
+    pub fn shorten(&self, route: &R) -> R {
+        let path = route.path();
+        let mut salted = path.clone();
+        loop {
+            let token = short_token(&salted, self.token_length);
+            match self.load(&token) {
+                Some(stored) if stored != path => {
+                    debug!("Short route token {:?} collides, generating another", token);
+                    salted.push('#');
+                }
+                Some(_) => return (self.token_route)(token),
+                None => {
+                    self.storage
+                        .set(&Self::key(&token), &storage::versioned(&path));
+                    return (self.token_route)(token);
+                }
+            }
+        }
+    }
+
+    /// If `route` is a token route, return the route stored for it. Token
+    /// routes with an unknown token are logged as an error, and left for
+    /// the application to display.
+    pub(crate) fn resolve(&self, route: &R) -> Option<R> {
+        let token = (self.route_token)(route)?;
+        match self.load(&token) {
+            Some(path) => Some(R::switch(&path)),
+            None => {
+                error!("Unable to resolve the short route token {:?}", token);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Keyboard shortcuts which dispatch route actions, e.g. `Alt+ArrowLeft`
+//! to go back, or the sequence `g h` to go to the home route.
+//!
+//! The [KeyBindings] are installed with
+//! [RouteMiddleware::with_key_bindings()](crate::RouteMiddleware::with_key_bindings)
+//! (requires the `web` feature), which listens for `keydown` events on
+//! the window for as long as the middleware is alive. Key presses in
+//! text inputs are ignored.
+
+use crate::RouteAction;
+use std::cell::RefCell;
+
+/// A key press, with the modifiers held down. `key` is the value of
+/// [KeyboardEvent.key](https://developer.mozilla.org/en-US/docs/Web/API/KeyboardEvent/key),
+/// e.g. `"ArrowLeft"` or `"g"`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeyPress {
+    pub key: String,
+    pub alt: bool,
+    pub ctrl: bool,
+    pub meta: bool,
+    pub shift: bool,
+}
+
+impl KeyPress {
+    /// Parse a key combination such as `Alt+ArrowLeft` or `Ctrl+Shift+k`.
+    /// Modifiers are case insensitive, the key is compared case
+    /// insensitively for single characters.
+    pub fn parse(combination: &str) -> Self {
+        let mut press = KeyPress::default();
+        let mut parts = combination.rsplitn(2, '+');
+        press.key = normalize_key(parts.next().unwrap_or_default());
+        for modifier in parts.next().unwrap_or_default().split('+') {
+            match modifier.to_ascii_lowercase().as_str() {
+                "alt" | "option" => press.alt = true,
+                "ctrl" | "control" => press.ctrl = true,
+                "meta" | "cmd" | "super" => press.meta = true,
+                "shift" => press.shift = true,
+                _ => {}
+            }
+        }
+        press
+    }
+
+    pub(crate) fn normalized(mut self) -> Self {
+        self.key = normalize_key(&self.key);
+        self
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+struct KeyBinding<R> {
+    sequence: Vec<KeyPress>,
+    action: RouteAction<R>,
+}
+
+/// Presses further apart than this (in milliseconds) don't form a
+/// sequence.
+pub const DEFAULT_SEQUENCE_TIMEOUT_MS: f64 = 1000.0;
+
+/// A table of keyboard shortcuts and the route actions they dispatch.
+pub struct KeyBindings<R> {
+    bindings: Vec<KeyBinding<R>>,
+    sequence_timeout_ms: f64,
+    /// The recent presses, with their timestamps.
+    pressed: RefCell<Vec<(KeyPress, f64)>>,
+}
+
+impl<R> KeyBindings<R>
+where
+    R: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            sequence_timeout_ms: DEFAULT_SEQUENCE_TIMEOUT_MS,
+            pressed: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// `Alt+ArrowLeft` for [RouteAction::Back] and `Alt+ArrowRight` for
+    /// [RouteAction::Forward].
+    pub fn with_history_defaults(self) -> Self {
+        self.bind("Alt+ArrowLeft", RouteAction::Back)
+            .bind("Alt+ArrowRight", RouteAction::Forward)
+    }
+
+    /// Dispatch `action` for the `shortcut`, a space separated sequence
+    /// of key combinations (see [KeyPress::parse()]), e.g. `"g h"` or
+    /// `"Alt+ArrowLeft"`.
+    pub fn bind(mut self, shortcut: &str, action: RouteAction<R>) -> Self {
+        self.bindings.push(KeyBinding {
+            sequence: shortcut.split_whitespace().map(KeyPress::parse).collect(),
+            action,
+        });
+        self
+    }
+
+    /// Set the maximum time between the presses of a sequence, defaults
+    /// to [DEFAULT_SEQUENCE_TIMEOUT_MS].
+    pub fn with_sequence_timeout_ms(mut self, timeout_ms: f64) -> Self {
+        self.sequence_timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Handle a key `press` which occurred at `time_ms`, returns the
+    /// action to dispatch if it completes a shortcut.
+    pub fn handle(&self, press: KeyPress, time_ms: f64) -> Option<RouteAction<R>> {
+        let press = press.normalized();
+        let mut pressed = self.pressed.borrow_mut();
+        if let Some((_, last)) = pressed.last() {
+            if time_ms - last > self.sequence_timeout_ms {
+                pressed.clear();
+            }
+        }
+        pressed.push((press, time_ms));
+
+        let longest = self.bindings.iter().map(|b| b.sequence.len()).max()?;
+        if pressed.len() > longest {
+            let excess = pressed.len() - longest;
+            pressed.drain(..excess);
+        }
+
+        let binding = self.bindings.iter().find(|binding| {
+            binding.sequence.len() <= pressed.len()
+                && pressed[pressed.len() - binding.sequence.len()..]
+                    .iter()
+                    .map(|(press, _)| press)
+                    .eq(binding.sequence.iter())
+        })?;
+        pressed.clear();
+        Some(binding.action.clone())
+    }
+}
+
+impl<R> Default for KeyBindings<R>
+where
+    R: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,265 @@
+//! Tamper-evident route parameters, enabled with the `signing` feature.
+//!
+//! For flows such as email unsubscribe links or invite tokens, the query
+//! parameters of a route can be signed using HMAC-SHA256 with
+//! [sign_params()] (or [sign_path()]), and checked with
+//! [verify_params()]. The signature is stored in the [SIGNATURE_PARAM]
+//! query parameter. The path of the route is signed along with its
+//! parameters, so a signature minted for `/unsubscribe?user=5` doesn't
+//! verify on `/delete-account?user=5`.
+//!
+//! Paths are those of the route ([SwitchRoute::path()](switch_router::SwitchRoute::path)),
+//! without the base path the application is deployed under (see
+//! [Href](crate::href::Href)), so links stay valid when it moves. Their
+//! percent-encoding is normalized (see [normalize_path()]) before
+//! signing, as browsers may escape the path differently.
+//!
+//! The [RouteMiddleware](crate::RouteMiddleware) can verify incoming
+//! routes automatically when configured
+//! [with_signed_routes()](crate::RouteMiddleware::with_signed_routes).
+
+use crate::{
+    canonical::split_path,
+    encoding::{encode_query_component, normalize_path},
+    query::QueryParams,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The query parameter which holds the signature.
+pub const SIGNATURE_PARAM: &str = "sig";
+
+/// The reason why [verify_params()] failed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// There is no [SIGNATURE_PARAM] in the parameters.
+    Missing,
+    /// The signature is not valid hex.
+    Malformed,
+    /// The signature does not match the parameters.
+    Invalid,
+}
+
+impl Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::Missing => write!(f, "route parameters are not signed"),
+            SignatureError::Malformed => write!(f, "route parameter signature is malformed"),
+            SignatureError::Invalid => write!(f, "route parameter signature is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// The message which gets signed: the normalized path portion of
+/// `path`, followed by the parameters (excluding the signature) sorted,
+/// so that reordering them doesn't invalidate the signature.
+fn signing_message(path: &str, params: &QueryParams) -> String {
+    let (path, _) = split_path(path);
+    let mut pairs: Vec<(&str, &str)> = params
+        .iter()
+        .filter(|(key, _)| *key != SIGNATURE_PARAM)
+        .collect();
+    pairs.sort_unstable();
+    let query = pairs
+        .into_iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                encode_query_component(key),
+                encode_query_component(value)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&");
+    // The normalized path portion never contains a `?`.
+    format!("{}?{}", normalize_path(path), query)
+}
+
+fn mac(path: &str, params: &QueryParams, key: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take a key of any size");
+    mac.update(signing_message(path, params).as_bytes());
+    mac
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Sign `params` for the route with the specified `path` (whose query
+/// and fragment are ignored) with the secret `key`, returning the
+/// parameters with the [SIGNATURE_PARAM] added (replacing any previous
+/// signature).
+pub fn sign_params(path: &str, params: &QueryParams, key: &[u8]) -> QueryParams {
+    let signature = to_hex(&mac(path, params, key).finalize().into_bytes());
+    let mut signed = params.clone();
+    signed.insert(SIGNATURE_PARAM, signature);
+    signed
+}
+
+/// Sign the route `path` and its query parameters with the secret `key`,
+/// returning the path with the [SIGNATURE_PARAM] added to its query.
+pub fn sign_path(path: &str, key: &[u8]) -> String {
+    sign_params(path, &QueryParams::from_path(path), key).replace_in_path(path)
+}
+
+/// Verify that `params` were signed for the route with the specified
+/// `path` with the secret `key` using [sign_params()], returning the
+/// parameters without the signature.
+pub fn verify_params(
+    path: &str,
+    params: &QueryParams,
+    key: &[u8],
+) -> Result<QueryParams, SignatureError> {
+    let signature = params.get(SIGNATURE_PARAM).ok_or(SignatureError::Missing)?;
+    let signature = from_hex(signature).ok_or(SignatureError::Malformed)?;
+    mac(path, params, key)
+        .verify_slice(&signature)
+        .map_err(|_| SignatureError::Invalid)?;
+
+    let mut verified = params.clone();
+    verified.remove(SIGNATURE_PARAM);
+    Ok(verified)
+}
+
+/// What to do with a route arriving from the browser which fails
+/// verification.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFailure {
+    /// Reduce the route anyway, the failure is only reported via
+    /// [RouteEvent::route_signature_checked()](crate::RouteEvent::route_signature_checked).
+    Mark,
+    /// Don't reduce the route, and restore the browser URL to the
+    /// current route in the store.
+    Reject,
+}
+
+type RequiresSignatureFn<R> = dyn Fn(&R) -> bool;
+
+/// Configuration for verifying the signature of routes arriving from
+/// the browser.
+pub struct SignedRoutes<R> {
+    key: Vec<u8>,
+    requires_signature: Box<RequiresSignatureFn<R>>,
+    on_failure: SignatureFailure,
+}
+
+impl<R> SignedRoutes<R> {
+    /// Create a new [SignedRoutes], verifying routes for which
+    /// `requires_signature` returns `true` using the secret `key`.
+    pub fn new<K, F>(key: K, requires_signature: F) -> Self
+    where
+        K: Into<Vec<u8>>,
+        F: Fn(&R) -> bool + 'static,
+    {
+        Self {
+            key: key.into(),
+            requires_signature: Box::new(requires_signature),
+            on_failure: SignatureFailure::Reject,
+        }
+    }
+
+    /// Set what to do when verification fails. Defaults to
+    /// [SignatureFailure::Reject].
+    pub fn on_failure(mut self, on_failure: SignatureFailure) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+
+    pub(crate) fn failure_policy(&self) -> SignatureFailure {
+        self.on_failure
+    }
+
+    /// Verify the `route` with the specified `path`, returns `None` if
+    /// the route doesn't require a signature.
+    pub(crate) fn verify(&self, route: &R, path: &str) -> Option<Result<(), SignatureError>> {
+        if !(self.requires_signature)(route) {
+            return None;
+        }
+        Some(verify_params(path, &QueryParams::from_path(path), &self.key).map(|_| ()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"secret";
+
+    fn verify(path: &str) -> Result<(), SignatureError> {
+        verify_params(path, &QueryParams::from_path(path), KEY).map(|_| ())
+    }
+
+    #[test]
+    fn signed_path_verifies() {
+        let signed = sign_path("/unsubscribe?user=5", KEY);
+        assert!(signed.starts_with("/unsubscribe?user=5&sig="));
+        assert_eq!(verify(&signed), Ok(()));
+    }
+
+    #[test]
+    fn signature_does_not_carry_over_to_another_route() {
+        let signed = sign_path("/unsubscribe?user=5", KEY);
+        let query = signed.split_once('?').unwrap().1;
+        assert_eq!(
+            verify(&format!("/delete-account?{}", query)),
+            Err(SignatureError::Invalid)
+        );
+        assert_eq!(
+            verify(&format!("/unsubscribe/all?{}", query)),
+            Err(SignatureError::Invalid)
+        );
+    }
+
+    #[test]
+    fn tampered_params_are_invalid() {
+        let signed = sign_path("/unsubscribe?user=5", KEY);
+        assert_eq!(
+            verify(&signed.replace("user=5", "user=6")),
+            Err(SignatureError::Invalid)
+        );
+        let other_key = sign_path("/unsubscribe?user=5", b"other");
+        assert_eq!(verify(&other_key), Err(SignatureError::Invalid));
+    }
+
+    #[test]
+    fn reordered_params_and_reencoded_path_verify() {
+        let params: QueryParams = vec![("a", "1"), ("b", "2")].into_iter().collect();
+        let signed = sign_params("/caf%C3%A9", &params, KEY);
+        let signature = signed.get(SIGNATURE_PARAM).unwrap();
+        let path = format!("/café?{}={}&b=2&a=1#top", SIGNATURE_PARAM, signature);
+        assert_eq!(verify(&path), Ok(()));
+    }
+
+    #[test]
+    fn missing_and_malformed_signatures() {
+        assert_eq!(verify("/unsubscribe?user=5"), Err(SignatureError::Missing));
+        assert_eq!(
+            verify("/unsubscribe?user=5&sig=abc"),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            verify("/unsubscribe?user=5&sig=zz"),
+            Err(SignatureError::Malformed)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Canonical slugs for content routes, such as
+//! `/articles/42-some-title`, where the id identifies the content and the
+//! slug is only there for readability. When the title changes, or a link
+//! has a truncated or misspelled slug, the route still resolves, but the
+//! URL should be fixed with a replace navigation so that every piece of
+//! content has one canonical URL (the standard SEO pattern of CMS-like
+//! applications).
+//!
+//! A [SlugCanonicalization] compares the slug of a route against the
+//! canonical slug known in the state. As a [RouteGuard] it redirects
+//! navigations to content whose slug is already known, routes arriving
+//! from the browser are redirected with a replace navigation. Content
+//! loaded after the navigation can be fixed once its slug is known with
+//! [canonical_route()](SlugCanonicalization::canonical_route):
+//!
+//! ```ignore
+//! let slugs = SlugCanonicalization::new(
+//!     |route: &AppRoute| match route {
+//!         AppRoute::Article(_, slug) => Some(slug.as_str()),
+//!         _ => None,
+//!     },
+//!     |state: &State, route: &AppRoute| match route {
+//!         AppRoute::Article(id, _) => state.articles.get(id).map(|article| slug::slugify(&article.title)),
+//!         _ => None,
+//!     },
+//!     |route: &AppRoute, slug: String| match route {
+//!         AppRoute::Article(id, _) => AppRoute::Article(*id, slug),
+//!         route => route.clone(),
+//!     },
+//! );
+//!
+//! // Once the article has been loaded:
+//! if let Some(route) = slugs.canonical_route(&store.state(), &store.state().route) {
+//!     store.replace_route(route);
+//! }
+//! ```
+
+use crate::guard::{GuardContext, GuardOutcome, RouteGuard};
+
+type SlugFn<R> = dyn Fn(&R) -> Option<&str>;
+type CanonicalSlugFn<State, R> = dyn Fn(&State, &R) -> Option<String>;
+type WithSlugFn<R> = dyn Fn(&R, String) -> R;
+
+/// Fixes the slugs of content routes, see the
+/// [module documentation](self).
+pub struct SlugCanonicalization<R, State> {
+    slug: Box<SlugFn<R>>,
+    canonical_slug: Box<CanonicalSlugFn<State, R>>,
+    with_slug: Box<WithSlugFn<R>>,
+}
+
+impl<R, State> SlugCanonicalization<R, State> {
+    /// Create a new [SlugCanonicalization], where
+    ///
+    /// + `slug` returns the slug of a content route, or `None` for other
+    ///   routes.
+    /// + `canonical_slug` returns the canonical slug of the content of a
+    ///   route known in the state, or `None` if it isn't known (yet).
+    /// + `with_slug` returns the route with its slug replaced.
+    pub fn new<S, C, W>(slug: S, canonical_slug: C, with_slug: W) -> Self
+    where
+        S: Fn(&R) -> Option<&str> + 'static,
+        C: Fn(&State, &R) -> Option<String> + 'static,
+        W: Fn(&R, String) -> R + 'static,
+    {
+        Self {
+            slug: Box::new(slug),
+            canonical_slug: Box::new(canonical_slug),
+            with_slug: Box::new(with_slug),
+        }
+    }
+
+    /// The `route` with the canonical slug, or `None` if it isn't a
+    /// content route, its canonical slug isn't known, or it already has
+    /// it.
+    pub fn canonical_route(&self, state: &State, route: &R) -> Option<R> {
+        let slug = (self.slug)(route)?;
+        let canonical = (self.canonical_slug)(state, route)?;
+        if slug == canonical {
+            None
+        } else {
+            Some((self.with_slug)(route, canonical))
+        }
+    }
+}
+
+impl<R, State> RouteGuard<R, State> for SlugCanonicalization<R, State> {
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R> {
+        match self.canonical_route(&context.state, context.target) {
+            Some(route) => GuardOutcome::Redirect(route),
+            None => GuardOutcome::Allow,
+        }
+    }
+}
+
+/// Split a path segment of the form `42-some-title` into its id and slug
+/// (`("42", "some-title")`). The slug is empty if the segment is only an
+/// id.
+pub fn split_slug(segment: &str) -> (&str, &str) {
+    match segment.split_once('-') {
+        Some((id, slug)) => (id, slug),
+        None => (segment, ""),
+    }
+}
+
+/// A slug for the `title`: lower case alphanumeric words separated by
+/// single dashes, e.g. `Hello, World!` becomes `hello-world`.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for word in title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.extend(word.chars().flat_map(char::to_lowercase));
+    }
+    slug
+}
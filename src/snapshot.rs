@@ -0,0 +1,212 @@
+//! Snapshots of the router data tracked by the
+//! [RouteMiddleware](crate::RouteMiddleware), for state persistence and
+//! devtools import/export. With the `serde` feature a [RouterSnapshot]
+//! can be serialized alongside the store's state.
+//!
+//! The snapshot is taken from the active history backend: the
+//! in-memory history with
+//! [memory navigation](crate::RouteMiddleware::with_memory_navigation),
+//! whose whole stack is captured and restored, otherwise the route
+//! service, of which only the current route is known (the browser
+//! doesn't expose its history entries).
+//!
+//! The store's state (including its route) is not part of the
+//! snapshot, it is expected to be persisted and restored by the
+//! application.
+//!
+//! Snapshots carry the version of their format. When restoring, snapshots
+//! of previous versions are migrated (see [RouterSnapshot::migrate()]),
+//! and snapshots of unknown (future) versions are discarded.
+
+#[cfg(feature = "scroll")]
+use crate::scroll::ScrollPosition;
+use crate::{logging::warning, RouteAction};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The version of the format of the snapshots taken by this version of
+/// the crate. Version `0` is a snapshot serialized before snapshots were
+/// versioned.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// The router data tracked by the middleware, obtained with
+/// [RouteMiddleware::snapshot()](crate::RouteMiddleware::snapshot).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouterSnapshot<R> {
+    /// The version of the format of the snapshot, see
+    /// [SNAPSHOT_VERSION].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: u32,
+    /// The current route of the history backend.
+    pub route: R,
+    /// The entries of the history stack, oldest first, including the
+    /// current route.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history: Vec<R>,
+    /// The index of the current entry in [history](Self::history).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history_index: usize,
+    /// The status of the navigations of the middleware.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub status: NavigationStatus<R>,
+    /// The tracked pairs of mask path and masked route, see
+    /// [masking](crate::masking).
+    pub masks: Vec<(String, R)>,
+    /// The [generation](crate::undo::NavigationMarker::generation) of
+    /// the navigation marker.
+    pub navigation_generation: u64,
+    /// The saved pairs of path and scroll position, see
+    /// [scroll](crate::scroll). Requires the `scroll` feature.
+    #[cfg(feature = "scroll")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scroll_positions: Vec<(String, ScrollPosition)>,
+}
+
+/// The status of the navigations of the
+/// [RouteMiddleware](crate::RouteMiddleware).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationStatus<R> {
+    /// See [RouteMiddleware::failed_route()](crate::RouteMiddleware::failed_route).
+    pub failed_route: Option<R>,
+    /// See
+    /// [RouteMiddleware::last_failed_navigation()](crate::RouteMiddleware::last_failed_navigation).
+    pub last_failed_navigation: Option<RouteAction<R>>,
+    /// See
+    /// [RouteMiddleware::pending_confirmation()](crate::RouteMiddleware::pending_confirmation).
+    pub pending_confirmation: Option<RouteAction<R>>,
+}
+
+impl<R> Default for NavigationStatus<R> {
+    fn default() -> Self {
+        Self {
+            failed_route: None,
+            last_failed_navigation: None,
+            pending_confirmation: None,
+        }
+    }
+}
+
+impl<R> RouterSnapshot<R>
+where
+    R: Clone,
+{
+    /// Migrate a snapshot of a previous version to the current
+    /// [SNAPSHOT_VERSION], or `None` if its version is unknown.
+    pub fn migrate(mut self) -> Option<Self> {
+        match self.version {
+            // Version 1 only added the version, version 2 added the
+            // history stack, the navigation status and the scroll
+            // positions. The history of previous versions is just the
+            // route.
+            0 | 1 => {
+                self.history = vec![self.route.clone()];
+                self.history_index = 0;
+                self.version = SNAPSHOT_VERSION;
+                Some(self)
+            }
+            SNAPSHOT_VERSION => Some(self),
+            version => {
+                warning!(
+                    "Discarding router snapshot of unknown version {} (expected <= {})",
+                    version,
+                    SNAPSHOT_VERSION
+                );
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::MemoryRouteService,
+        test_kit::{TestAction, TestMiddleware, TestRoute, TestState},
+        RouteMiddleware,
+    };
+    use reactive_state::{ReducerResult, StoreRef};
+    use std::rc::Rc;
+    use switch_router::SwitchRouteService;
+
+    fn middleware(service: &MemoryRouteService<TestRoute>) -> TestMiddleware {
+        let store = StoreRef::new(
+            |state: &Rc<TestState>, _action: &TestAction| ReducerResult {
+                state: state.clone(),
+                events: vec![],
+                effects: vec![],
+            },
+            TestState {
+                route: TestRoute::new("/"),
+            },
+        );
+        RouteMiddleware::new(service.clone(), store)
+    }
+
+    fn routes(paths: &[&str]) -> Vec<TestRoute> {
+        paths.iter().copied().map(TestRoute::new).collect()
+    }
+
+    fn snapshot(history: &[&str], history_index: usize) -> RouterSnapshot<TestRoute> {
+        RouterSnapshot {
+            version: SNAPSHOT_VERSION,
+            route: TestRoute::new(history[history_index]),
+            history: routes(history),
+            history_index,
+            status: NavigationStatus {
+                failed_route: Some(TestRoute::new("/broken")),
+                last_failed_navigation: Some(RouteAction::ChangeRoute(TestRoute::new("/admin"))),
+                pending_confirmation: None,
+            },
+            masks: vec![("/photo/5".to_string(), TestRoute::new("/feed"))],
+            navigation_generation: 3,
+            #[cfg(feature = "scroll")]
+            scroll_positions: vec![("/feed".to_string(), ScrollPosition::new(0.0, 640.0))],
+        }
+    }
+
+    #[test]
+    fn memory_history_roundtrip() {
+        let service = MemoryRouteService::new(TestRoute::new("/"));
+        let middleware = middleware(&service).with_memory_navigation(true);
+        let snapshot = snapshot(&["/", "/feed", "/photo/5"], 1);
+
+        middleware.restore(snapshot.clone());
+        assert_eq!(middleware.snapshot(), Some(snapshot));
+        #[cfg(feature = "scroll")]
+        assert_eq!(
+            middleware.scroll_position(&TestRoute::new("/feed")),
+            Some(ScrollPosition::new(0.0, 640.0))
+        );
+        assert_eq!(middleware.failed_route(), Some(TestRoute::new("/broken")));
+        // The route service isn't navigated with memory navigation.
+        assert_eq!(service.entries(), routes(&["/"]));
+    }
+
+    #[test]
+    fn route_service_only_restores_the_current_route() {
+        let service = MemoryRouteService::new(TestRoute::new("/"));
+        let middleware = middleware(&service);
+
+        middleware.restore(snapshot(&["/", "/feed", "/photo/5"], 1));
+        assert_eq!(service.get_route(), TestRoute::new("/feed"));
+        let restored = middleware.snapshot().unwrap();
+        assert_eq!(restored.history, routes(&["/feed"]));
+        assert_eq!(restored.history_index, 0);
+    }
+
+    #[test]
+    fn previous_versions_are_migrated() {
+        let expected = snapshot(&["/feed"], 0);
+        let mut legacy = expected.clone();
+        legacy.version = 1;
+        legacy.history = Vec::new();
+        assert_eq!(legacy.migrate(), Some(expected));
+
+        let mut future = snapshot(&["/feed"], 0);
+        future.version = SNAPSHOT_VERSION + 1;
+        assert_eq!(future.migrate(), None);
+    }
+}
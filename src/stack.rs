@@ -0,0 +1,95 @@
+//! Adding the [RouteMiddleware] to the store in the right position
+//! relative to the application's other middleware.
+//!
+//! The store runs its middleware in the order they were added, the
+//! first seeing each action first. The router rewrites the route
+//! actions it reduces (e.g. a guarded `ChangeRoute` may be reduced as a
+//! redirect, or not at all), so middleware which log or persist what was
+//! reduced needs to run after it: added before the router, a logger
+//! shows navigations which were never committed, and persistence can
+//! store transient routes (such as the offline or maintenance routes)
+//! which are then redirected a second time on the next load. Middleware
+//! which needs to see the actions as dispatched (e.g. an action recorder
+//! for replays) runs before it.
+//!
+//! Rather than relying on the order of the `add_middleware()` calls, a
+//! [MiddlewareStack] declares it:
+//!
+//! ```ignore
+//! RouteMiddleware::new(route_service, store.clone())
+//!     .with_guard(auth_guard)
+//!     .before(LoggerMiddleware::new())
+//!     .before(PersistenceMiddleware::new())
+//!     .after(RecorderMiddleware::new())
+//!     .install(&store);
+//! ```
+
+use crate::RouteMiddleware;
+use reactive_state::{middleware::Middleware, Store};
+use std::hash::Hash;
+
+type AddMiddlewareFn<State, Action, Event, Effect> =
+    dyn FnOnce(&Store<State, Action, Event, Effect>);
+
+/// The [RouteMiddleware] and the middleware which runs before and after
+/// it, see the [module documentation](self).
+pub struct MiddlewareStack<State, Action, Event, Effect> {
+    /// The middleware running before the router, in order.
+    before_router: Vec<Box<AddMiddlewareFn<State, Action, Event, Effect>>>,
+    router: Box<AddMiddlewareFn<State, Action, Event, Effect>>,
+    /// The middleware running after the router, in order.
+    after_router: Vec<Box<AddMiddlewareFn<State, Action, Event, Effect>>>,
+}
+
+impl<State, Action, Event, Effect> MiddlewareStack<State, Action, Event, Effect>
+where
+    Event: Clone + Hash + Eq,
+{
+    /// Create a new [MiddlewareStack] around the `router`.
+    pub fn new<R, RS>(router: RouteMiddleware<R, RS, State, Action, Event, Effect>) -> Self
+    where
+        RouteMiddleware<R, RS, State, Action, Event, Effect>:
+            Middleware<State, Action, Event, Effect> + 'static,
+    {
+        Self {
+            before_router: Vec::new(),
+            router: Box::new(move |store| store.add_middleware(router)),
+            after_router: Vec::new(),
+        }
+    }
+
+    /// Run the router before `other` (and after the middleware already
+    /// running before the router), so that `other` sees the actions as
+    /// reduced by the router. For logging and persistence middleware.
+    pub fn before<M>(mut self, other: M) -> Self
+    where
+        M: Middleware<State, Action, Event, Effect> + 'static,
+    {
+        self.after_router
+            .push(Box::new(move |store| store.add_middleware(other)));
+        self
+    }
+
+    /// Run the router after `other` (and after the middleware already
+    /// running before the router), so that `other` sees the actions as
+    /// dispatched.
+    pub fn after<M>(mut self, other: M) -> Self
+    where
+        M: Middleware<State, Action, Event, Effect> + 'static,
+    {
+        self.before_router
+            .push(Box::new(move |store| store.add_middleware(other)));
+        self
+    }
+
+    /// Add the middleware to the `store`, in order.
+    pub fn install(self, store: &Store<State, Action, Event, Effect>) {
+        for add in self.before_router {
+            add(store);
+        }
+        (self.router)(store);
+        for add in self.after_router {
+            add(store);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Simple key/value storage used to persist router data, such as
+//! [masked routes](crate::masking).
+//!
+//! The data this crate persists (in storage, and in the browser's
+//! history entries) is prefixed with the version of its format, see
+//! [versioned()], so that data left behind by a previous version of the
+//! crate (e.g. in `sessionStorage`, or in the history entries of a tab
+//! which stayed open across a deployment) is either migrated, or
+//! discarded, rather than misread. Values without a prefix were written
+//! before the format was versioned, and have version `0`.
+
+use crate::logging::debug;
+use std::{cell::RefCell, collections::HashMap};
+
+/// The version of the format of the values persisted by this crate.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Prefix `value` with the current [FORMAT_VERSION], e.g. `v1|/items/5`.
+pub fn versioned(value: &str) -> String {
+    format!("v{}|{}", FORMAT_VERSION, value)
+}
+
+/// Split a value written with [versioned()] into its version and
+/// payload. Values without a version prefix have version `0`.
+pub fn parse_versioned(value: &str) -> (u32, &str) {
+    value
+        .strip_prefix('v')
+        .and_then(|rest| rest.split_once('|'))
+        .and_then(|(version, payload)| Some((version.parse().ok()?, payload)))
+        .unwrap_or((0, value))
+}
+
+/// The payload of a value written with [versioned()], or `None` (with
+/// a debug message) if its version isn't one of the `supported`
+/// versions, so that it is discarded.
+pub(crate) fn supported_payload<'a>(value: &'a str, supported: &[u32]) -> Option<&'a str> {
+    let (version, payload) = parse_versioned(value);
+    if supported.contains(&version) {
+        Some(payload)
+    } else {
+        debug!(
+            "Discarding persisted value {:?} of unsupported format version {}",
+            value, version
+        );
+        None
+    }
+}
+
+/// A string key/value store.
+pub trait KeyValueStorage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&self, key: &str, value: &str);
+    fn remove(&self, key: &str);
+}
+
+/// A [KeyValueStorage] which keeps values in memory. See
+/// [SessionStorage](crate::web::SessionStorage) and
+/// [LocalStorage](crate::web::LocalStorage) for storage which survives
+/// a page reload.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    values: RefCell<HashMap<String, String>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KeyValueStorage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.borrow().get(key).cloned()
+    }
+
+    fn set(&self, key: &str, value: &str) {
+        self.values
+            .borrow_mut()
+            .insert(key.to_string(), value.to_string());
+    }
+
+    fn remove(&self, key: &str) {
+        self.values.borrow_mut().remove(key);
+    }
+}
@@ -0,0 +1,228 @@
+//! A framework agnostic way to be notified of route changes, for view
+//! layers without an integration (e.g. vanilla `web-sys` applications,
+//! or custom renderers).
+//!
+//! A [RouteSubscription] is obtained from the middleware with
+//! [route_subscription()](crate::RouteMiddleware::route_subscription)
+//! before it is added to the store. After each navigation which changes
+//! the route is committed, the middleware calls every subscribed
+//! listener with the new route, until its [SubscriptionGuard] is dropped.
+//!
+//! Expensive listeners (e.g. a map view) can be subscribed with
+//! [RouteSubscription::subscribe_debounced()], so that rapid route
+//! changes are coalesced, and only the last route is delivered once the
+//! route has stopped changing, while the other listeners still receive
+//! every change.
+//!
+//! Listeners interested in a section of the application (such as a
+//! cache of the section's data, to be evicted when leaving it) can be
+//! subscribed to a [scope](crate::scope) with
+//! [RouteSubscription::subscribe_scope()], and are only notified of the
+//! route changes entering, leaving or moving within it:
+//!
+//! ```ignore
+//! let _guard = subscription.subscribe_scope("/projects/*", move |change: &ScopeChange<'_, AppRoute>| {
+//!     if change.transition == ScopeTransition::Left {
+//!         project_cache.clear();
+//!     }
+//! });
+//! ```
+
+use crate::scope::{RouteScope, ScopeTransition};
+#[cfg(feature = "web")]
+use std::cell::Cell;
+use std::{cell::RefCell, rc::Rc};
+
+/// Called with the previous and the current route.
+type ListenerFn<R> = dyn Fn(&R, &R);
+
+/// A route change affecting a scope, see
+/// [RouteSubscription::subscribe_scope()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeChange<'a, R> {
+    pub scope: &'a str,
+    pub transition: ScopeTransition,
+    /// The route before the change.
+    pub previous: &'a R,
+    /// The route after the change.
+    pub current: &'a R,
+}
+
+struct Listeners<R> {
+    next_id: u64,
+    listeners: Vec<(u64, Rc<ListenerFn<R>>)>,
+}
+
+/// A handle for subscribing to route changes, see the
+/// [module documentation](self).
+pub struct RouteSubscription<R> {
+    listeners: Rc<RefCell<Listeners<R>>>,
+}
+
+impl<R> RouteSubscription<R> {
+    pub(crate) fn new() -> Self {
+        Self {
+            listeners: Rc::new(RefCell::new(Listeners {
+                next_id: 0,
+                listeners: Vec::new(),
+            })),
+        }
+    }
+
+    /// Call `listener` with the new route after each route change, until
+    /// the returned guard is dropped.
+    #[must_use = "the listener is unsubscribed when the guard is dropped"]
+    pub fn subscribe<F>(&self, listener: F) -> SubscriptionGuard<R>
+    where
+        F: Fn(&R) + 'static,
+    {
+        self.add_listener(move |_previous: &R, current: &R| listener(current))
+    }
+
+    fn add_listener<F>(&self, listener: F) -> SubscriptionGuard<R>
+    where
+        F: Fn(&R, &R) + 'static,
+    {
+        let mut listeners = self.listeners.borrow_mut();
+        let id = listeners.next_id;
+        listeners.next_id += 1;
+        listeners.listeners.push((id, Rc::new(listener)));
+        SubscriptionGuard {
+            id,
+            listeners: Rc::downgrade(&self.listeners),
+        }
+    }
+
+    /// Call `listener` after each route change entering, leaving or
+    /// moving within `scope` (see [RouteScope::in_scope()]), until the
+    /// returned guard is dropped.
+    #[must_use = "the listener is unsubscribed when the guard is dropped"]
+    pub fn subscribe_scope<S, F>(&self, scope: S, listener: F) -> SubscriptionGuard<R>
+    where
+        R: RouteScope,
+        S: Into<String>,
+        F: Fn(&ScopeChange<'_, R>) + 'static,
+    {
+        let scope = scope.into();
+        self.add_listener(move |previous: &R, current: &R| {
+            let transition =
+                ScopeTransition::between(previous.in_scope(&scope), current.in_scope(&scope));
+            if let Some(transition) = transition {
+                listener(&ScopeChange {
+                    scope: &scope,
+                    transition,
+                    previous,
+                    current,
+                });
+            }
+        })
+    }
+
+    /// Call `listener` with the route once it hasn't changed for
+    /// `quiet_period_ms`, until the returned guard is dropped. The quiet
+    /// period requires the `web` feature, without it the listener is
+    /// called after each route change.
+    #[must_use = "the listener is unsubscribed when the guard is dropped"]
+    pub fn subscribe_debounced<F>(&self, quiet_period_ms: u32, listener: F) -> SubscriptionGuard<R>
+    where
+        R: Clone + 'static,
+        F: Fn(&R) + 'static,
+    {
+        #[cfg(feature = "web")]
+        {
+            let debounced = Rc::new(Debounced {
+                generation: Cell::new(0),
+                latest: RefCell::new(None),
+                listener: Box::new(listener),
+            });
+            self.subscribe(move |route: &R| {
+                let generation = debounced.generation.get() + 1;
+                debounced.generation.set(generation);
+                debounced.latest.replace(Some(route.clone()));
+                // Pending deliveries are dropped along with the listener.
+                let debounced = Rc::downgrade(&debounced);
+                crate::web::set_timeout(quiet_period_ms, move || {
+                    if let Some(debounced) = debounced.upgrade() {
+                        debounced.deliver(generation);
+                    }
+                });
+            })
+        }
+        #[cfg(not(feature = "web"))]
+        {
+            crate::logging::debug!(
+                "Subscribing without the {}ms quiet period: debouncing requires the web feature",
+                quiet_period_ms
+            );
+            self.subscribe(listener)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listeners.borrow().listeners.is_empty()
+    }
+
+    /// Call the listeners with the `previous` and `current` route. The
+    /// listeners may subscribe and unsubscribe while being notified.
+    pub(crate) fn notify(&self, previous: &R, current: &R) {
+        let listeners: Vec<Rc<ListenerFn<R>>> = self
+            .listeners
+            .borrow()
+            .listeners
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect();
+        for listener in listeners {
+            listener(previous, current);
+        }
+    }
+}
+
+impl<R> Clone for RouteSubscription<R> {
+    fn clone(&self) -> Self {
+        Self {
+            listeners: self.listeners.clone(),
+        }
+    }
+}
+
+/// The state of a listener subscribed with
+/// [RouteSubscription::subscribe_debounced()].
+#[cfg(feature = "web")]
+struct Debounced<R> {
+    /// Incremented on each route change, so that only the delivery
+    /// scheduled by the last change is performed.
+    generation: Cell<u64>,
+    latest: RefCell<Option<R>>,
+    listener: Box<dyn Fn(&R)>,
+}
+
+#[cfg(feature = "web")]
+impl<R> Debounced<R> {
+    fn deliver(&self, generation: u64) {
+        if generation != self.generation.get() {
+            return;
+        }
+        let latest = self.latest.take();
+        if let Some(route) = latest {
+            (self.listener)(&route);
+        }
+    }
+}
+
+/// Unsubscribes its listener from the [RouteSubscription] when dropped.
+pub struct SubscriptionGuard<R> {
+    id: u64,
+    listeners: std::rc::Weak<RefCell<Listeners<R>>>,
+}
+
+impl<R> Drop for SubscriptionGuard<R> {
+    fn drop(&mut self) {
+        if let Some(listeners) = self.listeners.upgrade() {
+            listeners
+                .borrow_mut()
+                .listeners
+                .retain(|(id, _)| *id != self.id);
+        }
+    }
+}
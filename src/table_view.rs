@@ -0,0 +1,291 @@
+//! A compact, stable encoding of the state of a table view (its sort
+//! order and filters) into a single query parameter, so that links to a
+//! view can be shared. Requires the `table-view` feature.
+//!
+//! A [TableView] is encoded by a [TableViewCodec] as e.g.
+//! `v1;s:-c,n;f:st:open,closed`: the version of the encoding, the sort
+//! keys (`-` for descending), and a filter set per column. Columns are
+//! encoded by short codes registered with
+//! [with_column()](TableViewCodec::with_column), which keeps the links
+//! short (views with a lot of filters could otherwise exceed the
+//! [url_length](crate::url_length) guard), and stable when the columns
+//! are renamed in the application. Changes to the set of columns
+//! themselves are handled by bumping the
+//! [version](TableViewCodec::with_version) and registering a
+//! [migration](TableViewCodec::with_migration) for links using the
+//! previous one.
+//!
+//! ```ignore
+//! let codec = TableViewCodec::new("view")
+//!     .with_column("created_at", "c")
+//!     .with_column("status", "st");
+//! let view = codec.read(&QueryParams::from_path(&route.path()));
+//! // ?view=v1;s:-c;f:st:open,closed
+//! let view = view.with_sort("created_at", SortDirection::Descending);
+//! ```
+
+use crate::{logging::debug, query::QueryParams};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// A column of a [TableView] to sort by.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortKey {
+    pub column: String,
+    pub direction: SortDirection,
+}
+
+/// The sort order and filters of a table.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableView {
+    /// The columns to sort by, most significant first.
+    pub sort: Vec<SortKey>,
+    /// The values of each column to include, by column.
+    pub filters: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl TableView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the view has no sort keys, and no filter values.
+    pub fn is_empty(&self) -> bool {
+        self.sort.is_empty() && self.filters.values().all(BTreeSet::is_empty)
+    }
+
+    /// Sort by `column` in `direction`, after the existing sort keys. An
+    /// existing sort key for the column is replaced.
+    pub fn with_sort<S: Into<String>>(mut self, column: S, direction: SortDirection) -> Self {
+        let column = column.into();
+        self.sort.retain(|key| key.column != column);
+        self.sort.push(SortKey { column, direction });
+        self
+    }
+
+    /// Include the rows whose `column` has `value`, in addition to the
+    /// values already in the column's filter set.
+    pub fn with_filter<C: Into<String>, V: Into<String>>(mut self, column: C, value: V) -> Self {
+        self.filters
+            .entry(column.into())
+            .or_default()
+            .insert(value.into());
+        self
+    }
+}
+
+/// Escape the characters of the encoding's syntax with `!`.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '!' | ';' | ':' | ',') {
+            escaped.push('!');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Split `value` at the unescaped occurrences of `separator`, without
+/// unescaping the parts.
+fn split_escaped(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '!' {
+            escaped = true;
+        } else if c == separator {
+            parts.push(&value[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '!' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+type MigrateFn = dyn Fn(TableView) -> TableView;
+
+/// Encodes a [TableView] into a query parameter, see the
+/// [module documentation](self).
+pub struct TableViewCodec {
+    key: String,
+    version: u32,
+    /// The columns and their codes.
+    columns: Vec<(String, String)>,
+    /// The migrations from each version to the next.
+    migrations: BTreeMap<u32, Box<MigrateFn>>,
+}
+
+impl TableViewCodec {
+    /// Create a new [TableViewCodec] for the query parameter `key`, with
+    /// version `1`.
+    pub fn new<S: Into<String>>(key: S) -> Self {
+        Self {
+            key: key.into(),
+            version: 1,
+            columns: Vec::new(),
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// The current version of the encoding. Views encoded with a later
+    /// version are discarded.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Encode `column` as `code`. Columns without a code are encoded by
+    /// their name.
+    pub fn with_column<N: Into<String>, C: Into<String>>(mut self, column: N, code: C) -> Self {
+        self.columns.push((column.into(), code.into()));
+        self
+    }
+
+    /// Migrate views decoded with version `from` to version `from + 1`.
+    /// Views are decoded with the codes of the current version, so codes
+    /// which were removed arrive here as the column name.
+    pub fn with_migration<F>(mut self, from: u32, migrate: F) -> Self
+    where
+        F: Fn(TableView) -> TableView + 'static,
+    {
+        self.migrations.insert(from, Box::new(migrate));
+        self
+    }
+
+    fn code<'a>(&'a self, column: &'a str) -> &'a str {
+        self.columns
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, code)| code.as_str())
+            .unwrap_or(column)
+    }
+
+    fn column(&self, code: String) -> String {
+        self.columns
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(name, _)| name.clone())
+            .unwrap_or(code)
+    }
+
+    /// Encode the `view`.
+    pub fn encode(&self, view: &TableView) -> String {
+        let mut encoded = format!("v{}", self.version);
+        if !view.sort.is_empty() {
+            let sort: Vec<String> = view
+                .sort
+                .iter()
+                .map(|key| {
+                    let code = escape(self.code(&key.column));
+                    match key.direction {
+                        // A leading `-` would read as descending.
+                        SortDirection::Ascending if code.starts_with('-') => format!("!{}", code),
+                        SortDirection::Ascending => code,
+                        SortDirection::Descending => format!("-{}", code),
+                    }
+                })
+                .collect();
+            encoded.push_str(";s:");
+            encoded.push_str(&sort.join(","));
+        }
+        for (column, values) in view.filters.iter().filter(|(_, values)| !values.is_empty()) {
+            encoded.push_str(";f:");
+            encoded.push_str(&escape(self.code(column)));
+            encoded.push(':');
+            let values: Vec<String> = values.iter().map(|value| escape(value)).collect();
+            encoded.push_str(&values.join(","));
+        }
+        encoded
+    }
+
+    /// Decode a view encoded with [encode()](Self::encode), migrating it
+    /// to the current version. Returns `None` if it is malformed, or was
+    /// encoded with an unsupported version.
+    pub fn decode(&self, encoded: &str) -> Option<TableView> {
+        let parts = split_escaped(encoded, ';');
+        let version: u32 = parts[0].strip_prefix('v')?.parse().ok()?;
+        if version > self.version {
+            debug!(
+                "Discarding table view {:?} of unsupported version {}",
+                encoded, version
+            );
+            return None;
+        }
+
+        let mut view = TableView::new();
+        for part in &parts[1..] {
+            let fields = split_escaped(part, ':');
+            match fields.as_slice() {
+                ["s", sort] => {
+                    for key in split_escaped(sort, ',') {
+                        let (direction, code) = match key.strip_prefix('-') {
+                            Some(code) => (SortDirection::Descending, code),
+                            None => (SortDirection::Ascending, key),
+                        };
+                        view = view.with_sort(self.column(unescape(code)), direction);
+                    }
+                }
+                ["f", code, values] => {
+                    let column = self.column(unescape(code));
+                    for value in split_escaped(values, ',') {
+                        view = view.with_filter(column.clone(), unescape(value));
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        for from in version..self.version {
+            if let Some(migrate) = self.migrations.get(&from) {
+                view = migrate(view);
+            }
+        }
+        Some(view)
+    }
+
+    /// Read the view from the query `params`, an empty view if it is
+    /// missing or can't be decoded.
+    pub fn read(&self, params: &QueryParams) -> TableView {
+        params
+            .get(&self.key)
+            .and_then(|encoded| self.decode(encoded))
+            .unwrap_or_default()
+    }
+
+    /// Write the `view` to the query `params`, removing the parameter if
+    /// the view is empty.
+    pub fn write(&self, params: &mut QueryParams, view: &TableView) {
+        if view.is_empty() {
+            params.remove(&self.key);
+        } else {
+            params.insert(self.key.as_str(), self.encode(view));
+        }
+    }
+}
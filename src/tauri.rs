@@ -0,0 +1,78 @@
+//! Routing for [Tauri](https://tauri.app/) desktop apps with multiple
+//! windows, requires the `tauri` feature.
+//!
+//! The main window uses the usual browser route service, which keeps the
+//! webview's URL in sync with the route. [WindowRouting] opens the routes
+//! matching its rules in their own windows instead of navigating the
+//! main window, and dispatches [RouteAction::Back](crate::RouteAction::Back)
+//! when the OS-level back gesture (or mouse back button) is used.
+//!
+//! The calls to Tauri are made through the [TauriBridge] trait, which is
+//! implemented with the Tauri bindings used by the application (e.g.
+//! `WebviewWindow::new()` and `listen()`), so this crate doesn't depend
+//! on a particular version of them.
+
+type MatcherFn<R> = dyn Fn(&R) -> bool;
+type GestureFn = dyn Fn();
+
+/// The calls to Tauri made by [WindowRouting].
+pub trait TauriBridge {
+    /// Open a window labelled `label` showing `url`, or navigate and
+    /// focus the window if it is already open.
+    fn open_window(&self, label: &str, url: &str);
+    /// Call `handler` whenever the OS-level back gesture is used in the
+    /// main window.
+    fn listen_back_gesture(&self, handler: Box<GestureFn>);
+}
+
+struct WindowRule<R> {
+    label: String,
+    matcher: Box<MatcherFn<R>>,
+}
+
+/// Routing of routes to Tauri windows, installed with
+/// [RouteMiddleware::with_window_routing()](crate::RouteMiddleware::with_window_routing).
+/// See the [module documentation](self).
+pub struct WindowRouting<R> {
+    bridge: Box<dyn TauriBridge>,
+    rules: Vec<WindowRule<R>>,
+}
+
+impl<R> WindowRouting<R> {
+    pub fn new<B>(bridge: B) -> Self
+    where
+        B: TauriBridge + 'static,
+    {
+        Self {
+            bridge: Box::new(bridge),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Open the routes accepted by `matcher` in the window labelled
+    /// `label`. The first matching rule is used.
+    pub fn open_in_window<S, F>(mut self, label: S, matcher: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&R) -> bool + 'static,
+    {
+        self.rules.push(WindowRule {
+            label: label.into(),
+            matcher: Box::new(matcher),
+        });
+        self
+    }
+
+    /// The label of the window `route` is opened in, or `None` if it is
+    /// navigated to in the main window.
+    pub fn window_for(&self, route: &R) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|rule| (rule.matcher)(route))
+            .map(|rule| rule.label.as_str())
+    }
+
+    pub fn bridge(&self) -> &dyn TauriBridge {
+        &*self.bridge
+    }
+}
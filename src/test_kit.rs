@@ -0,0 +1,273 @@
+//! Scaffolding for testing code which works with the
+//! [RouteMiddleware], such as downstream crates writing their own
+//! route-aware middleware or guards (requires the `test-util` feature).
+//!
+//! A [TestKit] spins up a store with a minimal state, action and event
+//! implementing the crate's traits, routed by a [RouteMiddleware] over a
+//! [MemoryRouteService], and provides handles to drive navigation (like
+//! the application, or like the browser with the [testing] helpers) and
+//! assert its outcome:
+//!
+//! ```ignore
+//! let kit = TestKit::with_middleware("/", |middleware| middleware.with_guard(MyGuard));
+//! kit.add_middleware(MyMiddleware::new());
+//! kit.navigate("/admin");
+//! kit.assert_route("/login");
+//! assert!(kit.take_events().contains(&TestEvent::RouteChanged));
+//! ```
+
+use crate::{
+    memory::MemoryRouteService, testing, IsRouteAction, RouteAction, RouteMiddleware, RouteState,
+};
+use reactive_state::{middleware::Middleware, Callback, ReducerResult, StoreRef};
+use std::{cell::RefCell, rc::Rc};
+use switch_router::{SwitchRoute, SwitchRouteService};
+
+/// A route which is just its path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestRoute(pub String);
+
+impl TestRoute {
+    pub fn new<S: Into<String>>(path: S) -> Self {
+        TestRoute(path.into())
+    }
+}
+
+impl SwitchRoute for TestRoute {
+    fn path(&self) -> String {
+        self.0.clone()
+    }
+
+    fn switch(route: &str) -> Self {
+        TestRoute::new(route)
+    }
+}
+
+impl From<&str> for TestRoute {
+    fn from(path: &str) -> Self {
+        TestRoute::new(path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestState {
+    pub route: TestRoute,
+}
+
+impl RouteState<TestRoute> for TestState {
+    fn get_route(&self) -> &TestRoute {
+        &self.route
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestAction {
+    Route(RouteAction<TestRoute>),
+    /// An action which isn't a route action, reduced without changing
+    /// the state.
+    Other(String),
+}
+
+impl From<RouteAction<TestRoute>> for TestAction {
+    fn from(action: RouteAction<TestRoute>) -> Self {
+        TestAction::Route(action)
+    }
+}
+
+impl IsRouteAction<TestRoute> for TestAction {
+    fn route_action(&self) -> Option<&RouteAction<TestRoute>> {
+        match self {
+            TestAction::Route(action) => Some(action),
+            TestAction::Other(_) => None,
+        }
+    }
+
+    fn into_route_action(self) -> Option<RouteAction<TestRoute>> {
+        match self {
+            TestAction::Route(action) => Some(action),
+            TestAction::Other(_) => None,
+        }
+    }
+}
+
+/// The events emitted by the middleware, without their details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TestEvent {
+    RouteChanged,
+    UrlTooLong,
+    NavigationBlocked,
+    ConfirmationRequired,
+    ChunkLoadFailed,
+    InitialNavigationComplete,
+    NavigationFailed,
+}
+
+impl TestEvent {
+    /// Every variant, for subscribing to them all.
+    pub const ALL: [TestEvent; 7] = [
+        TestEvent::RouteChanged,
+        TestEvent::UrlTooLong,
+        TestEvent::NavigationBlocked,
+        TestEvent::ConfirmationRequired,
+        TestEvent::ChunkLoadFailed,
+        TestEvent::InitialNavigationComplete,
+        TestEvent::NavigationFailed,
+    ];
+}
+
+crate::route_event! {
+    impl RouteEvent<TestRoute> for TestEvent {
+        route_changed => TestEvent::RouteChanged,
+        url_too_long => |_, _| TestEvent::UrlTooLong,
+        navigation_blocked => TestEvent::NavigationBlocked,
+        confirmation_required => |_| TestEvent::ConfirmationRequired,
+        chunk_load_failed => |_| TestEvent::ChunkLoadFailed,
+        initial_navigation_complete => TestEvent::InitialNavigationComplete,
+        navigation_failed => |_| TestEvent::NavigationFailed,
+    }
+}
+
+pub type TestStore = StoreRef<TestState, TestAction, TestEvent, ()>;
+pub type TestMiddleware =
+    RouteMiddleware<TestRoute, MemoryRouteService<TestRoute>, TestState, TestAction, TestEvent, ()>;
+
+/// Commits the route of [RouteAction::ChangeRoute] and
+/// [RouteAction::BrowserChangeRoute], emitting [TestEvent::RouteChanged].
+fn reducer(state: &Rc<TestState>, action: &TestAction) -> ReducerResult<TestState, TestEvent, ()> {
+    match action {
+        TestAction::Route(RouteAction::ChangeRoute(route))
+        | TestAction::Route(RouteAction::BrowserChangeRoute(route, _)) => ReducerResult {
+            state: Rc::new(TestState {
+                route: route.clone(),
+            }),
+            events: vec![TestEvent::RouteChanged],
+            effects: vec![],
+        },
+        _ => ReducerResult {
+            state: state.clone(),
+            events: vec![],
+            effects: vec![],
+        },
+    }
+}
+
+/// A store routed by a [RouteMiddleware], see the
+/// [module documentation](self).
+pub struct TestKit {
+    pub store: TestStore,
+    /// A clone of the route service of the middleware.
+    pub service: MemoryRouteService<TestRoute>,
+    events: Rc<RefCell<Vec<TestEvent>>>,
+    _listener: Callback<TestState, TestEvent>,
+}
+
+impl TestKit {
+    /// A [TestKit] starting at the route with the `initial` path, with a
+    /// [RouteMiddleware] with the default configuration.
+    pub fn new(initial: &str) -> Self {
+        Self::with_middleware(initial, |middleware| middleware)
+    }
+
+    /// A [TestKit] starting at the route with the `initial` path, with a
+    /// [RouteMiddleware] configured by `configure`.
+    pub fn with_middleware<F>(initial: &str, configure: F) -> Self
+    where
+        F: FnOnce(TestMiddleware) -> TestMiddleware,
+    {
+        let initial = TestRoute::new(initial);
+        let store = StoreRef::new(
+            reducer,
+            TestState {
+                route: initial.clone(),
+            },
+        );
+        let service = MemoryRouteService::new(initial);
+        let middleware = configure(RouteMiddleware::new(service.clone(), store.clone()));
+        store.add_middleware(middleware);
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let listener = {
+            let events = events.clone();
+            Callback::new(move |_state: Rc<TestState>, event: Option<TestEvent>| {
+                events.borrow_mut().extend(event);
+            })
+        };
+        store.subscribe_events(&listener, TestEvent::ALL.iter().copied());
+
+        Self {
+            store,
+            service,
+            events,
+            _listener: listener,
+        }
+    }
+
+    /// Add another middleware (e.g. the one under test) to the store,
+    /// after the [RouteMiddleware].
+    pub fn add_middleware<M>(&self, middleware: M)
+    where
+        M: Middleware<TestState, TestAction, TestEvent, ()> + 'static,
+    {
+        self.store.add_middleware(middleware);
+    }
+
+    pub fn dispatch<A: Into<TestAction>>(&self, action: A) {
+        self.store.dispatch(action.into());
+    }
+
+    /// Navigate to the route with `path`, as the application does.
+    pub fn navigate(&self, path: &str) {
+        self.dispatch(RouteAction::ChangeRoute(TestRoute::new(path)));
+    }
+
+    /// Navigate to the route with `path`, as the user does by entering
+    /// its URL, see [testing::simulate_browser_navigation()].
+    pub fn browser_navigate(&self, path: &str) {
+        testing::simulate_browser_navigation(&self.service, TestRoute::new(path));
+    }
+
+    /// Press the browser's back button, see
+    /// [testing::simulate_popstate_back()].
+    pub fn back(&self) -> Option<TestRoute> {
+        testing::simulate_popstate_back(&self.service)
+    }
+
+    /// Deliver the popstate events caused by [RouteAction::Back], see
+    /// [testing::flush_popstate()].
+    pub fn flush_popstate(&self) {
+        testing::flush_popstate(&self.service);
+    }
+
+    /// The route of the state.
+    pub fn route(&self) -> TestRoute {
+        self.store.state().route.clone()
+    }
+
+    /// The route displayed by the route service.
+    pub fn url(&self) -> TestRoute {
+        self.service.get_route()
+    }
+
+    /// The paths of the history entries of the route service, oldest
+    /// first.
+    pub fn history(&self) -> Vec<String> {
+        self.service
+            .entries()
+            .into_iter()
+            .map(|route| route.path())
+            .collect()
+    }
+
+    /// The events emitted since the last call, oldest first.
+    pub fn take_events(&self) -> Vec<TestEvent> {
+        self.events.replace(Vec::new())
+    }
+
+    /// Assert that both the state and the route service are at the
+    /// route with `path`.
+    #[track_caller]
+    pub fn assert_route(&self, path: &str) {
+        assert_eq!(self.route().path(), path, "route of the state");
+        assert_eq!(self.url().path(), path, "route of the route service");
+    }
+}
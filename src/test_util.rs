@@ -0,0 +1,111 @@
+//! Utilities for property testing applications using the
+//! [RouteMiddleware](crate::RouteMiddleware) with
+//! [proptest](https://docs.rs/proptest) (requires the `test-util`
+//! feature).
+//!
+//! + [route_action()] and the [Arbitrary] implementation for
+//!   [RouteAction] generate route actions, given a strategy for the
+//!   application's route type.
+//! + [check_path_roundtrip()] and [check_canonical_path()] assert the
+//!   invariants the middleware relies upon when converting routes to
+//!   and from URLs.
+
+use crate::{
+    encoding::normalize_path, navigation::NavigationKind, query::QueryParams, RouteAction,
+};
+use proptest::prelude::*;
+use std::fmt::Debug;
+use switch_router::SwitchRoute;
+
+/// A strategy generating [QueryParams] with up to 4 pairs, including
+/// characters which require percent encoding.
+pub fn query_params() -> impl Strategy<Value = QueryParams> {
+    prop::collection::vec(("[a-z]{1,8}", "[a-zA-Z0-9 &=%/]{0,8}"), 0..4)
+        .prop_map(|pairs| pairs.into_iter().collect())
+}
+
+/// A strategy generating the [RouteAction]s which navigate within the
+/// application, with routes generated by `route`. Actions which leave
+/// the page or require a browser (such as
+/// [RouteAction::ExternalNavigate]) are not generated.
+pub fn route_action<S>(route: S) -> impl Strategy<Value = RouteAction<S::Value>>
+where
+    S: Strategy + Clone + 'static,
+    S::Value: Clone + 'static,
+{
+    prop_oneof![
+        Just(RouteAction::Back),
+        route.clone().prop_map(RouteAction::ChangeRoute),
+        route.clone().prop_map(RouteAction::ReplaceRoute),
+        query_params().prop_map(RouteAction::UpdateQuery),
+        (-3i32..=3).prop_map(RouteAction::ChangePage),
+        query_params().prop_map(RouteAction::LiveQueryUpdate),
+        Just(RouteAction::CommitQuery),
+        (route.clone(), route.clone())
+            .prop_map(|(route, mask)| RouteAction::ChangeRouteMasked { route, mask }),
+        route
+            .clone()
+            .prop_map(|route| RouteAction::BrowserChangeRoute(route, NavigationKind::External)),
+        Just(RouteAction::PollBrowserRoute),
+        Just(RouteAction::Reload),
+        route.prop_map(RouteAction::OpenInNewTab),
+    ]
+}
+
+impl Arbitrary for QueryParams {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        query_params().boxed()
+    }
+}
+
+impl<R> Arbitrary for RouteAction<R>
+where
+    R: Arbitrary + Clone + 'static,
+{
+    type Parameters = R::Parameters;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        route_action(any_with::<R>(args).boxed()).boxed()
+    }
+}
+
+/// Assert that `route` survives conversion to its path and back, as it
+/// does when it is set on the route service and arrives again from the
+/// browser.
+pub fn check_path_roundtrip<R>(route: &R) -> Result<(), TestCaseError>
+where
+    R: SwitchRoute + Debug,
+{
+    let path = route.path();
+    let switched = R::switch(&path);
+    prop_assert_eq!(
+        &switched,
+        route,
+        "route switched from its path {:?} differs",
+        path
+    );
+    prop_assert_eq!(switched.path(), path);
+    Ok(())
+}
+
+/// Assert that the path of `route` is already normalized, so it won't
+/// be rewritten by the middleware when
+/// [path normalization](crate::RouteMiddleware::with_path_normalization)
+/// is enabled.
+pub fn check_canonical_path<R>(route: &R) -> Result<(), TestCaseError>
+where
+    R: SwitchRoute + Debug,
+{
+    let path = route.path();
+    prop_assert_eq!(
+        normalize_path(&path),
+        path,
+        "path of route {:?} is not normalized",
+        route
+    );
+    Ok(())
+}
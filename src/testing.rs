@@ -0,0 +1,114 @@
+//! Helpers for integration testing applications using the
+//! [RouteMiddleware](crate::RouteMiddleware) with a
+//! [MemoryRouteService]. They drive the service like the browser would,
+//! so that tests cover the path from the route service callback through
+//! to the store's reducer, rather than only dispatching actions
+//! directly. They are only compiled for the crate's own tests, or with
+//! the `test-util` feature.
+//!
+//! Keep a clone of the [MemoryRouteService] before it is moved into the
+//! middleware, and pass it to these helpers.
+//!
+//! [assert_route_roundtrip()] checks that the application's routes
+//! survive being set on a route service and read back, to catch skew
+//! between encoding and decoding routes before it surfaces as unexpected
+//! [RouteAction::BrowserChangeRoute](crate::RouteAction::BrowserChangeRoute)
+//! values in production.
+
+use crate::memory::MemoryRouteService;
+use std::fmt::{Debug, Write};
+use switch_router::{SwitchRoute, SwitchRouteService};
+
+/// Simulate the user navigating the browser to `route` (e.g. by
+/// entering its URL), adding a history entry and notifying the
+/// middleware.
+pub fn simulate_browser_navigation<R>(service: &MemoryRouteService<R>, route: R)
+where
+    R: SwitchRoute,
+{
+    service.push(route.clone());
+    service.emit(route);
+}
+
+/// Simulate the user pressing the browser's back button. Returns the
+/// route navigated back to, or `None` if there is no previous entry (in
+/// which case the middleware is not notified).
+pub fn simulate_popstate_back<R>(service: &MemoryRouteService<R>) -> Option<R>
+where
+    R: SwitchRoute,
+{
+    simulate_popstate(service, -1)
+}
+
+/// Simulate the user pressing the browser's forward button, see
+/// [simulate_popstate_back()].
+pub fn simulate_popstate_forward<R>(service: &MemoryRouteService<R>) -> Option<R>
+where
+    R: SwitchRoute,
+{
+    simulate_popstate(service, 1)
+}
+
+fn simulate_popstate<R>(service: &MemoryRouteService<R>, delta: isize) -> Option<R>
+where
+    R: SwitchRoute,
+{
+    let route = service.go(delta)?;
+    service.emit(route.clone());
+    Some(route)
+}
+
+/// Deliver the popstate events caused by
+/// [RouteAction::Back](crate::RouteAction::Back), as the browser does
+/// once the current task has completed.
+pub fn flush_popstate<R>(service: &MemoryRouteService<R>)
+where
+    R: SwitchRoute,
+{
+    service.deliver_pending();
+}
+
+/// Assert that each of the `routes` is read back unchanged from the
+/// `service` after being set on it. Panics listing every route which
+/// didn't round-trip, with its path and the path read back, marking
+/// where they differ.
+#[track_caller]
+pub fn assert_route_roundtrip<RS, I>(service: &mut RS, routes: I)
+where
+    RS: SwitchRouteService,
+    RS::Route: Debug,
+    I: IntoIterator<Item = RS::Route>,
+{
+    let mut failures = String::new();
+    let mut count = 0;
+    for route in routes {
+        count += 1;
+        service.set_route(route.clone());
+        let returned = service.get_route();
+        if returned == route {
+            continue;
+        }
+        let (path, returned_path) = (route.path(), returned.path());
+        let offset = path
+            .chars()
+            .zip(returned_path.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        // Writing to a String can't fail.
+        let _ = write!(
+            failures,
+            "\n  set:      {:?}\n  returned: {:?}\n  path:     {}\n  returned: {}\n            {}^\n",
+            route,
+            returned,
+            path,
+            returned_path,
+            " ".repeat(offset)
+        );
+    }
+    if !failures.is_empty() {
+        panic!(
+            "routes didn't round-trip through the route service ({} checked):\n{}",
+            count, failures
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Coordination with undo/redo middleware.
+//!
+//! Undo stepping "through" a navigation is confusing for users, so an
+//! undo middleware composed with the [RouteMiddleware](crate::RouteMiddleware)
+//! should treat route actions specially. This module provides:
+//!
+//! + [NavigationMarker]: a handle (obtained from
+//!   [RouteMiddleware::navigation_marker()](crate::RouteMiddleware::navigation_marker))
+//!   which marks the reduce cycles that contain route actions, so an undo
+//!   middleware can group or skip them.
+//! + [UndoPolicy]: the documented way to decide what an undo middleware
+//!   should do with an action, see [UndoPolicy::classify()].
+
+use crate::{IsRouteAction, RouteAction};
+use std::{cell::Cell, rc::Rc};
+use switch_router::SwitchRoute;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Marks the reduce cycles in which the route middleware handles a route
+/// action. Clones share the same underlying marker.
+#[derive(Debug, Clone, Default)]
+pub struct NavigationMarker {
+    generation: Rc<Cell<u64>>,
+    reducing_route_action: Rc<Cell<bool>>,
+}
+
+impl NavigationMarker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Incremented every time a route action has been reduced. An undo
+    /// middleware can record the generation with each undo entry, and
+    /// treat a change in generation as a barrier.
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    /// Whether the current reduce cycle is for a route action. This can
+    /// be queried by middleware further down the chain.
+    pub fn is_reducing_route_action(&self) -> bool {
+        self.reducing_route_action.get()
+    }
+
+    pub(crate) fn restore_generation(&self, generation: u64) {
+        self.generation.set(generation);
+    }
+
+    pub(crate) fn begin(&self) {
+        self.reducing_route_action.set(true);
+    }
+
+    pub(crate) fn end(&self) {
+        self.reducing_route_action.set(false);
+        self.generation.set(self.generation.get().wrapping_add(1));
+    }
+}
+
+/// How route actions should interact with undo history.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UndoPolicy {
+    /// Route actions are not recorded, and undo can't step past them.
+    #[default]
+    Barrier,
+    /// Route actions are not recorded, undo skips over them.
+    Exclude,
+    /// Route actions are recorded like any other action.
+    Include,
+}
+
+/// What an undo middleware should do with an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoClass {
+    /// Record the action in undo history.
+    Record,
+    /// Don't record the action.
+    Skip,
+    /// Don't record the action, and clear the undo history before it.
+    Barrier,
+}
+
+impl UndoPolicy {
+    /// Classify the `action` according to this policy. Actions which are
+    /// not route actions are always [UndoClass::Record].
+    pub fn classify<R, A>(self, action: &A) -> UndoClass
+    where
+        R: SwitchRoute + 'static,
+        A: IsRouteAction<R>,
+    {
+        match action.route_action() {
+            None => UndoClass::Record,
+            Some(route_action) => self.classify_route_action(route_action),
+        }
+    }
+
+    /// Classify a `route_action` according to this policy. Actions which
+    /// don't navigate (such as
+    /// [RouteAction::OpenInNewTab]) are skipped
+    /// rather than forming barriers.
+    pub fn classify_route_action<R>(self, route_action: &RouteAction<R>) -> UndoClass {
+        if let RouteAction::Routed { action, .. } = route_action {
+            return self.classify_route_action(action);
+        }
+        let navigates = !matches!(
+            route_action,
+            RouteAction::OpenInNewTab(_) | RouteAction::PollBrowserRoute
+        );
+        match self {
+            UndoPolicy::Include => UndoClass::Record,
+            UndoPolicy::Exclude => UndoClass::Skip,
+            UndoPolicy::Barrier if navigates => UndoClass::Barrier,
+            UndoPolicy::Barrier => UndoClass::Skip,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Applying an update of the application (e.g. a new service worker
+//! which is waiting to be activated) at a safe moment: the next
+//! navigation, when the page is changing anyway, rather than reloading
+//! while the user is in the middle of something.
+//!
+//! When the application learns that an update is waiting, it dispatches
+//! [RouteAction::SetUpdatePending](crate::RouteAction::SetUpdatePending)
+//! (see
+//! [RouteStore::set_update_pending()](crate::RouteStore::set_update_pending)).
+//! Once the next navigation has been committed, the middleware
+//! (configured with
+//! [with_update_on_navigation()](crate::RouteMiddleware::with_update_on_navigation))
+//! clears the flag, and calls the hook with the route that was navigated
+//! to. With the `web` feature,
+//! [skip_waiting_and_reload()](crate::web::skip_waiting_and_reload)
+//! activates the waiting service worker and reloads the page, which
+//! loads the new route with the new version:
+//!
+//! ```ignore
+//! let middleware = RouteMiddleware::new(route_service, store.clone())
+//!     .with_update_on_navigation(|_route: &AppRoute| web::skip_waiting_and_reload());
+//!
+//! // In the service worker's `updatefound` handler:
+//! store.set_update_pending(true);
+//! ```
+
+/// Applies a pending update after a navigation to the route, see the
+/// [module documentation](self).
+pub(crate) type ApplyUpdateFn<R> = dyn Fn(&R);
@@ -0,0 +1,176 @@
+//! Detection of routes whose URL exceeds what browsers (and the
+//! proxies/CDNs in front of them) will reliably carry.
+//!
+//! The length measured is that of the full URL: the origin, followed by
+//! the route's `href` as formatted by the middleware's [Href](crate::href::Href)
+//! (including the base path, and the `#` in hash mode). When a
+//! [UrlLengthGuard] is configured on the
+//! [RouteMiddleware](crate::RouteMiddleware), any navigation to a route
+//! whose URL is too long emits
+//! [RouteEvent::url_too_long()](crate::RouteEvent::url_too_long), both
+//! when the application navigates to it, and when it arrives from the
+//! browser (e.g. a pasted link). Optionally the full path can be stashed
+//! in an [OverflowStorage], with only a short token being placed in the
+//! URL.
+
+use crate::hash::fnv1a;
+use std::{cell::RefCell, collections::HashMap};
+
+/// A conservative default for the maximum URL length, based on the
+/// lowest common limit of browsers and CDNs in use.
+pub const DEFAULT_MAX_URL_LENGTH: usize = 2000;
+
+/// Storage for route paths which are too long to be placed in the URL.
+pub trait OverflowStorage {
+    /// Store the `path`, returning the token which can later be used
+    /// to [load()](OverflowStorage::load) it, or `None` if storing failed.
+    fn store(&self, path: &str) -> Option<String>;
+    /// Load a path previously stored under `token`.
+    fn load(&self, token: &str) -> Option<String>;
+}
+
+/// An [OverflowStorage] which keeps the overflowed paths in memory. The
+/// paths will not survive a page reload, use
+/// [SessionStorageOverflow](crate::web::SessionStorageOverflow) for that.
+#[derive(Default, Debug)]
+pub struct MemoryOverflowStorage {
+    paths: RefCell<HashMap<String, String>>,
+}
+
+impl MemoryOverflowStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OverflowStorage for MemoryOverflowStorage {
+    fn store(&self, path: &str) -> Option<String> {
+        let token = overflow_token(path);
+        self.paths
+            .borrow_mut()
+            .insert(token.clone(), path.to_string());
+        Some(token)
+    }
+
+    fn load(&self, token: &str) -> Option<String> {
+        self.paths.borrow().get(token).cloned()
+    }
+}
+
+/// Generate a short token for the specified `path`. The same path always
+/// produces the same token (64 bit FNV-1a hash in hex), so repeatedly
+/// navigating to the same overflowing route doesn't fill up the storage.
+pub fn overflow_token(path: &str) -> String {
+    format!("{:016x}", fnv1a(path))
+}
+
+type RouteTokenFn<R> = dyn Fn(&R) -> Option<String>;
+
+/// How a route is swapped for a short token route when its path
+/// overflows, and how to recognise that token route when it comes back
+/// from the browser.
+pub struct UrlOverflow<R> {
+    storage: Box<dyn OverflowStorage>,
+    token_route: Box<dyn Fn(String) -> R>,
+    route_token: Box<RouteTokenFn<R>>,
+}
+
+impl<R> UrlOverflow<R> {
+    /// Create a new [UrlOverflow].
+    ///
+    /// + `token_route` constructs the route which carries the token in
+    ///   the URL (e.g. `/overflow/<token>`).
+    /// + `route_token` extracts the token from such a route, returning
+    ///   `None` for every other route.
+    pub fn new<S, TR, RT>(storage: S, token_route: TR, route_token: RT) -> Self
+    where
+        S: OverflowStorage + 'static,
+        TR: Fn(String) -> R + 'static,
+        RT: Fn(&R) -> Option<String> + 'static,
+    {
+        Self {
+            storage: Box::new(storage),
+            token_route: Box::new(token_route),
+            route_token: Box::new(route_token),
+        }
+    }
+
+    /// Store the `path` and return the token route to place in the URL
+    /// instead.
+    pub(crate) fn overflow(&self, path: &str) -> Option<R> {
+        self.storage
+            .store(path)
+            .map(|token| (self.token_route)(token))
+    }
+
+    /// If `route` is a token route, return the path that was stored
+    /// for it.
+    pub(crate) fn resolve(&self, route: &R) -> Option<Result<String, String>> {
+        (self.route_token)(route).map(|token| match self.storage.load(&token) {
+            Some(path) => Ok(path),
+            None => Err(token),
+        })
+    }
+}
+
+/// Configuration for detecting routes which are too long for the URL.
+pub struct UrlLengthGuard<R> {
+    max_length: usize,
+    origin: Option<String>,
+    overflow: Option<UrlOverflow<R>>,
+}
+
+impl<R> UrlLengthGuard<R> {
+    /// Create a new [UrlLengthGuard] which only warns (via
+    /// [RouteEvent::url_too_long()](crate::RouteEvent::url_too_long))
+    /// when a URL exceeds `max_length`.
+    pub fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            origin: None,
+            overflow: None,
+        }
+    }
+
+    /// The origin the application is served from (e.g.
+    /// `https://example.com`), counted towards the length of its URLs.
+    /// With the `web` feature, defaults to the origin of the page.
+    pub fn with_origin<S: Into<String>>(mut self, origin: S) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Place overflowing paths in `overflow` instead of the URL.
+    pub fn with_overflow(mut self, overflow: UrlOverflow<R>) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// The length of the URL with the specified `href` (which doesn't
+    /// include the origin).
+    pub fn url_length(&self, href: &str) -> usize {
+        self.origin.as_ref().map_or(0, String::len) + href.len()
+    }
+
+    pub(crate) fn exceeds(&self, url_length: usize) -> bool {
+        url_length > self.max_length
+    }
+
+    pub(crate) fn overflow(&self) -> Option<&UrlOverflow<R>> {
+        self.overflow.as_ref()
+    }
+}
+
+impl<R> Default for UrlLengthGuard<R> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_URL_LENGTH)
+    }
+}
@@ -0,0 +1,953 @@
+//! Browser specific functionality, enabled with the `web` feature.
+
+use crate::{
+    embed::{EmbedConfig, EmbedMessage},
+    href::Href,
+    logging::{debug, error},
+    shortcuts::{KeyBindings, KeyPress},
+    storage,
+    url_length::{overflow_token, OverflowStorage},
+    RouteAction,
+};
+use js_sys::{Function, Object, Reflect};
+use std::{cell::Cell, rc::Rc};
+use switch_router::SwitchRoute;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// The format versions of the values persisted by the storage backed types
+/// of this module which can be read. Version `0` (an unversioned value) is
+/// read like version `1`.
+const SUPPORTED_VERSIONS: &[u32] = &[0, 1];
+
+/// The payload of the `value` stored under `key`, removing it from
+/// `storage` if its format version is not supported.
+fn supported_item(storage: &web_sys::Storage, key: &str, value: String) -> Option<String> {
+    match storage::supported_payload(&value, SUPPORTED_VERSIONS) {
+        Some(payload) => Some(payload.to_string()),
+        None => {
+            if let Err(err) = storage.remove_item(key) {
+                error!("Unable to remove {:?} from storage: {:?}", key, err);
+            }
+            None
+        }
+    }
+}
+
+/// An [OverflowStorage] backed by the browser's `sessionStorage`, so
+/// overflowed routes survive page reloads within the same tab.
+#[derive(Debug, Clone)]
+pub struct SessionStorageOverflow {
+    key_prefix: String,
+}
+
+impl SessionStorageOverflow {
+    pub const DEFAULT_KEY_PREFIX: &'static str = "switch-router-middleware:overflow:";
+
+    pub fn new() -> Self {
+        Self::with_key_prefix(Self::DEFAULT_KEY_PREFIX)
+    }
+
+    /// Create a new [SessionStorageOverflow] storing paths under keys
+    /// starting with `key_prefix`.
+    pub fn with_key_prefix<S: Into<String>>(key_prefix: S) -> Self {
+        Self {
+            key_prefix: key_prefix.into(),
+        }
+    }
+
+    fn key(&self, token: &str) -> String {
+        format!("{}{}", self.key_prefix, token)
+    }
+}
+
+impl Default for SessionStorageOverflow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn session_storage() -> Option<web_sys::Storage> {
+    match web_sys::window()?.session_storage() {
+        Ok(storage) => storage,
+        Err(err) => {
+            error!("Unable to access sessionStorage: {:?}", err);
+            None
+        }
+    }
+}
+
+impl OverflowStorage for SessionStorageOverflow {
+    fn store(&self, path: &str) -> Option<String> {
+        let token = overflow_token(path);
+        match session_storage()?.set_item(&self.key(&token), &storage::versioned(path)) {
+            Ok(()) => Some(token),
+            Err(err) => {
+                error!(
+                    "Unable to store overflowed route in sessionStorage: {:?}",
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    fn load(&self, token: &str) -> Option<String> {
+        let storage = session_storage()?;
+        let key = self.key(token);
+        match storage.get_item(&key) {
+            Ok(path) => supported_item(&storage, &key, path?),
+            Err(err) => {
+                error!(
+                    "Unable to load overflowed route from sessionStorage: {:?}",
+                    err
+                );
+                None
+            }
+        }
+    }
+}
+
+/// A [StateStash](crate::oauth::StateStash) backed by the browser's
+/// `sessionStorage`, so the state survives the full page loads of an
+/// external OAuth redirect.
+#[cfg(feature = "oauth")]
+#[derive(Debug, Clone)]
+pub struct SessionStorageStateStash {
+    key: String,
+}
+
+#[cfg(feature = "oauth")]
+impl SessionStorageStateStash {
+    pub const DEFAULT_KEY: &'static str = "switch-router-middleware:oauth-state";
+
+    pub fn new() -> Self {
+        Self::with_key(Self::DEFAULT_KEY)
+    }
+
+    pub fn with_key<S: Into<String>>(key: S) -> Self {
+        Self { key: key.into() }
+    }
+}
+
+#[cfg(feature = "oauth")]
+impl Default for SessionStorageStateStash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "oauth")]
+impl crate::oauth::StateStash for SessionStorageStateStash {
+    fn stash(&self, state: &str) {
+        if let Some(storage) = session_storage() {
+            if let Err(err) = storage.set_item(&self.key, &storage::versioned(state)) {
+                error!("Unable to stash OAuth state in sessionStorage: {:?}", err);
+            }
+        }
+    }
+
+    fn take(&self) -> Option<String> {
+        let storage = session_storage()?;
+        // The state is removed below whatever its format version.
+        let state = match storage.get_item(&self.key) {
+            Ok(state) => state.and_then(|state| {
+                storage::supported_payload(&state, SUPPORTED_VERSIONS).map(str::to_string)
+            }),
+            Err(err) => {
+                error!("Unable to load OAuth state from sessionStorage: {:?}", err);
+                None
+            }
+        };
+        if let Err(err) = storage.remove_item(&self.key) {
+            error!(
+                "Unable to remove OAuth state from sessionStorage: {:?}",
+                err
+            );
+        }
+        state
+    }
+}
+
+/// The route path currently displayed in the address bar, with the
+/// `href` configuration's base path (or hash mode) removed.
+pub fn address_path(href: &Href) -> Option<String> {
+    let location = web_sys::window()?.location();
+    let address = if href.hash_mode() {
+        location
+            .hash()
+            .map(|hash| hash.trim_start_matches('#').to_string())
+    } else {
+        location.pathname().and_then(|pathname| {
+            let path = pathname
+                .strip_prefix(href.base_path())
+                .unwrap_or(&pathname)
+                .to_string();
+            Ok(format!(
+                "{}{}{}",
+                path,
+                location.search()?,
+                location.hash()?
+            ))
+        })
+    };
+    match address {
+        Ok(address) => Some(address),
+        Err(err) => {
+            error!("Unable to read the address bar: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Identifies this page load, distinguishing its history keys from the
+/// keys of previous page loads.
+pub(crate) fn history_session() -> String {
+    let time_origin = performance().map(|performance| performance.time_origin());
+    format!("{}", time_origin.unwrap_or_default())
+}
+
+/// The key stored in the current history entry's state.
+pub(crate) fn history_key() -> Option<String> {
+    match web_sys::window()?
+        .history()
+        .and_then(|history| history.state())
+    {
+        Ok(state) => state.as_string(),
+        Err(err) => {
+            error!("Unable to read the history state: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Store `key` in the current history entry's state.
+pub(crate) fn set_history_key(key: &str) {
+    match web_sys::window().map(|window| window.history()) {
+        Some(Ok(history)) => {
+            if let Err(err) = history.replace_state(&JsValue::from_str(key), "") {
+                error!("Unable to store the history key: {:?}", err);
+            }
+        }
+        Some(Err(err)) => error!("Unable to store the history key: {:?}", err),
+        None => error!("Unable to store the history key: no window"),
+    }
+}
+
+/// The origin of the current page, e.g. `https://example.com`.
+pub fn location_origin() -> Option<String> {
+    match web_sys::window()?.location().origin() {
+        Ok(origin) => Some(origin),
+        Err(err) => {
+            error!("Unable to read the origin: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Call the method `name` of `target` with `arg`, if it has one, so that
+/// optional browser APIs can be detected.
+fn call_method(target: &JsValue, name: &str, arg: &JsValue) -> Option<Result<JsValue, JsValue>> {
+    let method = Reflect::get(target, &JsValue::from_str(name))
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?;
+    Some(method.call1(target, arg))
+}
+
+/// Share `url` titled `title` using `navigator.share()`, falling back to
+/// copying `url` to the clipboard where it isn't available, or fails
+/// for another reason than the user cancelling it.
+pub fn share(url: &str, title: &str) {
+    let navigator: JsValue = match web_sys::window() {
+        Some(window) => window.navigator().into(),
+        None => {
+            error!("Unable to share {:?}: no window", url);
+            return;
+        }
+    };
+    let data = Object::new();
+    let _ = Reflect::set(&data, &JsValue::from_str("url"), &JsValue::from_str(url));
+    if !title.is_empty() {
+        let _ = Reflect::set(
+            &data,
+            &JsValue::from_str("title"),
+            &JsValue::from_str(title),
+        );
+    }
+    let promise = match call_method(&navigator, "share", &data) {
+        Some(Ok(promise)) => promise,
+        Some(Err(err)) => {
+            debug!("Unable to share {:?}: {:?}", url, err);
+            copy_to_clipboard(url);
+            return;
+        }
+        None => {
+            copy_to_clipboard(url);
+            return;
+        }
+    };
+    let shared_url = url.to_string();
+    let fallback = Closure::<dyn FnMut(JsValue)>::once_into_js(move |err: JsValue| {
+        let url = shared_url;
+        let name = Reflect::get(&err, &JsValue::from_str("name"))
+            .ok()
+            .and_then(|name| name.as_string());
+        if name.as_deref() == Some("AbortError") {
+            debug!("Sharing {:?} was cancelled", url);
+        } else {
+            debug!("Unable to share {:?}: {:?}", url, err);
+            copy_to_clipboard(&url);
+        }
+    });
+    if let Some(Err(err)) = call_method(&promise, "catch", &fallback) {
+        error!("Unable to share {:?}: {:?}", url, err);
+    }
+}
+
+/// Copy `text` to the clipboard using `navigator.clipboard.writeText()`.
+pub fn copy_to_clipboard(text: &str) {
+    let clipboard = web_sys::window()
+        .and_then(|window| Reflect::get(&window.navigator(), &JsValue::from_str("clipboard")).ok())
+        .filter(|clipboard| !clipboard.is_undefined());
+    let result = match clipboard {
+        Some(clipboard) => call_method(&clipboard, "writeText", &JsValue::from_str(text)),
+        None => None,
+    };
+    match result {
+        Some(Ok(_)) => {}
+        Some(Err(err)) => error!("Unable to copy {:?} to the clipboard: {:?}", text, err),
+        None => error!("Unable to copy {:?} to the clipboard: no clipboard", text),
+    }
+}
+
+/// Activate the service worker waiting to take over the page, and
+/// reload the page once it has, see [update](crate::update). The waiting
+/// worker is sent the `{ type: "SKIP_WAITING" }` message, which it
+/// should answer by calling `self.skipWaiting()` (Workbox's convention).
+/// Without a waiting worker, the page is reloaded immediately.
+pub fn skip_waiting_and_reload() {
+    let container = web_sys::window()
+        .and_then(|window| {
+            Reflect::get(&window.navigator(), &JsValue::from_str("serviceWorker")).ok()
+        })
+        .filter(|container| !container.is_undefined());
+    let container = match container {
+        Some(container) => container,
+        None => return reload_location(),
+    };
+    let reload = Closure::<dyn FnMut()>::once_into_js(reload_location);
+    if let Err(err) = Reflect::set(
+        &container,
+        &JsValue::from_str("oncontrollerchange"),
+        &reload,
+    ) {
+        error!("Unable to listen for the service worker update: {:?}", err);
+        return reload_location();
+    }
+    let skip_waiting = Closure::<dyn FnMut(JsValue)>::once_into_js(|registration: JsValue| {
+        let waiting = Reflect::get(&registration, &JsValue::from_str("waiting"))
+            .ok()
+            .filter(|waiting| !waiting.is_undefined() && !waiting.is_null());
+        let message = Object::new();
+        let _ = Reflect::set(
+            &message,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("SKIP_WAITING"),
+        );
+        match waiting.map(|waiting| call_method(&waiting, "postMessage", &message)) {
+            Some(Some(Ok(_))) => debug!("Activating the waiting service worker"),
+            _ => reload_location(),
+        }
+    });
+    let registration = call_method(&container, "getRegistration", &JsValue::UNDEFINED);
+    match registration.and_then(|promise| promise.ok()) {
+        Some(promise) => {
+            if call_method(&promise, "then", &skip_waiting).is_none() {
+                reload_location();
+            }
+        }
+        None => reload_location(),
+    }
+}
+
+/// Perform a full page load of `url` using `window.location.assign()`.
+pub fn assign_location(url: &str) {
+    match web_sys::window() {
+        Some(window) => {
+            if let Err(err) = window.location().assign(url) {
+                error!("Unable to navigate to external url {:?}: {:?}", url, err);
+            }
+        }
+        None => error!("Unable to navigate to external url {:?}: no window", url),
+    }
+}
+
+/// Reload the current page using `window.location.reload()`.
+pub fn reload_location() {
+    match web_sys::window() {
+        Some(window) => {
+            if let Err(err) = window.location().reload() {
+                error!("Unable to reload the page: {:?}", err);
+            }
+        }
+        None => error!("Unable to reload the page: no window"),
+    }
+}
+
+/// Open `url` in a new browser tab using `window.open()`.
+pub fn open_in_new_tab(url: &str) {
+    match web_sys::window() {
+        Some(window) => {
+            if let Err(err) = window.open_with_url_and_target(url, "_blank") {
+                error!("Unable to open {:?} in a new tab: {:?}", url, err);
+            }
+        }
+        None => error!("Unable to open {:?} in a new tab: no window", url),
+    }
+}
+
+pub(crate) fn local_storage() -> Option<web_sys::Storage> {
+    match web_sys::window()?.local_storage() {
+        Ok(storage) => storage,
+        Err(err) => {
+            error!("Unable to access localStorage: {:?}", err);
+            None
+        }
+    }
+}
+
+/// An [AssignmentStorage](crate::experiments::AssignmentStorage) backed
+/// by the browser's `localStorage`, so experiment assignments are sticky
+/// across sessions.
+#[cfg(feature = "experiments")]
+#[derive(Debug, Clone)]
+pub struct LocalStorageAssignments {
+    key_prefix: String,
+}
+
+#[cfg(feature = "experiments")]
+impl LocalStorageAssignments {
+    pub const DEFAULT_KEY_PREFIX: &'static str = "switch-router-middleware:experiment:";
+
+    pub fn new() -> Self {
+        Self::with_key_prefix(Self::DEFAULT_KEY_PREFIX)
+    }
+
+    pub fn with_key_prefix<S: Into<String>>(key_prefix: S) -> Self {
+        Self {
+            key_prefix: key_prefix.into(),
+        }
+    }
+}
+
+#[cfg(feature = "experiments")]
+impl Default for LocalStorageAssignments {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "experiments")]
+impl crate::experiments::AssignmentStorage for LocalStorageAssignments {
+    fn load(&self, experiment: &str) -> Option<String> {
+        let storage = local_storage()?;
+        let key = format!("{}{}", self.key_prefix, experiment);
+        match storage.get_item(&key) {
+            Ok(variant) => supported_item(&storage, &key, variant?),
+            Err(err) => {
+                error!("Unable to load experiment assignment: {:?}", err);
+                None
+            }
+        }
+    }
+
+    fn save(&self, experiment: &str, variant: &str) {
+        if let Some(storage) = local_storage() {
+            let key = format!("{}{}", self.key_prefix, experiment);
+            if let Err(err) = storage.set_item(&key, &storage::versioned(variant)) {
+                error!("Unable to save experiment assignment: {:?}", err);
+            }
+        }
+    }
+}
+
+fn storage_get(storage: Option<web_sys::Storage>, key: &str) -> Option<String> {
+    match storage?.get_item(key) {
+        Ok(value) => value,
+        Err(err) => {
+            error!("Unable to get {:?} from storage: {:?}", key, err);
+            None
+        }
+    }
+}
+
+fn storage_set(storage: Option<web_sys::Storage>, key: &str, value: &str) {
+    if let Some(storage) = storage {
+        if let Err(err) = storage.set_item(key, value) {
+            error!("Unable to set {:?} in storage: {:?}", key, err);
+        }
+    }
+}
+
+fn storage_remove(storage: Option<web_sys::Storage>, key: &str) {
+    if let Some(storage) = storage {
+        if let Err(err) = storage.remove_item(key) {
+            error!("Unable to remove {:?} from storage: {:?}", key, err);
+        }
+    }
+}
+
+/// A [KeyValueStorage](crate::storage::KeyValueStorage) backed by the
+/// browser's `sessionStorage`.
+#[derive(Debug, Clone, Default)]
+pub struct SessionStorage;
+
+impl crate::storage::KeyValueStorage for SessionStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        storage_get(session_storage(), key)
+    }
+
+    fn set(&self, key: &str, value: &str) {
+        storage_set(session_storage(), key, value)
+    }
+
+    fn remove(&self, key: &str) {
+        storage_remove(session_storage(), key)
+    }
+}
+
+/// A [KeyValueStorage](crate::storage::KeyValueStorage) backed by the
+/// browser's `localStorage`.
+#[derive(Debug, Clone, Default)]
+pub struct LocalStorage;
+
+impl crate::storage::KeyValueStorage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        storage_get(local_storage(), key)
+    }
+
+    fn set(&self, key: &str, value: &str) {
+        storage_set(local_storage(), key, value)
+    }
+
+    fn remove(&self, key: &str) {
+        storage_remove(local_storage(), key)
+    }
+}
+
+/// Go forward in the browser's history using `history.forward()`.
+pub fn history_forward() {
+    match web_sys::window().map(|window| window.history()) {
+        Some(Ok(history)) => {
+            if let Err(err) = history.forward() {
+                error!("Unable to go forward: {:?}", err);
+            }
+        }
+        Some(Err(err)) => error!("Unable to go forward: {:?}", err),
+        None => error!("Unable to go forward: no window"),
+    }
+}
+
+/// Whether the browser is online, according to `navigator.onLine`.
+pub fn is_online() -> bool {
+    web_sys::window()
+        .and_then(|window| Reflect::get(&window.navigator(), &JsValue::from_str("onLine")).ok())
+        .and_then(|online| online.as_bool())
+        .unwrap_or(true)
+}
+
+/// Listens for the `online` and `offline` events on the window,
+/// dispatching [RouteAction::ConnectivityChanged]. The listeners are
+/// removed when this is dropped.
+pub(crate) struct ConnectivityListener {
+    window: web_sys::Window,
+    online: Closure<dyn FnMut()>,
+    offline: Closure<dyn FnMut()>,
+}
+
+impl ConnectivityListener {
+    pub(crate) fn new<R, F>(dispatch: F) -> Option<Self>
+    where
+        R: 'static,
+        F: Fn(RouteAction<R>) + 'static,
+    {
+        let window = web_sys::window()?;
+        let dispatch = std::rc::Rc::new(dispatch);
+        let online = {
+            let dispatch = dispatch.clone();
+            Closure::wrap(
+                Box::new(move || dispatch(RouteAction::ConnectivityChanged(true)))
+                    as Box<dyn FnMut()>,
+            )
+        };
+        let offline =
+            Closure::wrap(
+                Box::new(move || dispatch(RouteAction::ConnectivityChanged(false)))
+                    as Box<dyn FnMut()>,
+            );
+        for (event, closure) in [("online", &online), ("offline", &offline)] {
+            if let Err(err) =
+                window.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+            {
+                error!("Unable to listen for {} events: {:?}", event, err);
+                return None;
+            }
+        }
+        Some(Self {
+            window,
+            online,
+            offline,
+        })
+    }
+}
+
+impl Drop for ConnectivityListener {
+    fn drop(&mut self) {
+        for (event, closure) in [("online", &self.online), ("offline", &self.offline)] {
+            if let Err(err) = self
+                .window
+                .remove_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+            {
+                error!("Unable to remove the {} listener: {:?}", event, err);
+            }
+        }
+    }
+}
+
+/// Listens for activity events on the window, dispatching
+/// [RouteAction::SessionIdle] once there has been none for the timeout.
+/// The listeners are removed, and the timer stopped, when this is
+/// dropped.
+pub(crate) struct IdleListener {
+    window: web_sys::Window,
+    events: Vec<String>,
+    closure: Closure<dyn FnMut()>,
+    active: Rc<Cell<bool>>,
+}
+
+impl IdleListener {
+    pub(crate) fn new<R, F>(timeout_ms: u32, events: &[String], dispatch: F) -> Option<Self>
+    where
+        R: 'static,
+        F: Fn(RouteAction<R>) + 'static,
+    {
+        let window = web_sys::window()?;
+        let last_activity = Rc::new(Cell::new(js_sys::Date::now()));
+        let closure = {
+            let last_activity = last_activity.clone();
+            Closure::wrap(
+                Box::new(move || last_activity.set(js_sys::Date::now())) as Box<dyn FnMut()>
+            )
+        };
+        for event in events {
+            if let Err(err) =
+                window.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+            {
+                error!("Unable to listen for {} events: {:?}", event, err);
+            }
+        }
+        let active = Rc::new(Cell::new(true));
+        schedule_idle_check(
+            timeout_ms,
+            timeout_ms,
+            last_activity,
+            active.clone(),
+            Rc::new(dispatch),
+        );
+        Some(Self {
+            window,
+            events: events.to_vec(),
+            closure,
+            active,
+        })
+    }
+}
+
+/// Check for inactivity after `delay_ms`, dispatching
+/// [RouteAction::SessionIdle] if there has been none for `timeout_ms`,
+/// and check again when the timeout would next expire.
+fn schedule_idle_check<R: 'static>(
+    timeout_ms: u32,
+    delay_ms: u32,
+    last_activity: Rc<Cell<f64>>,
+    active: Rc<Cell<bool>>,
+    dispatch: Rc<dyn Fn(RouteAction<R>)>,
+) {
+    set_timeout(delay_ms, move || {
+        if !active.get() {
+            return;
+        }
+        let idle_ms = (js_sys::Date::now() - last_activity.get()).max(0.0) as u32;
+        let delay_ms = if idle_ms >= timeout_ms {
+            dispatch(RouteAction::SessionIdle);
+            timeout_ms
+        } else {
+            timeout_ms - idle_ms
+        };
+        schedule_idle_check(timeout_ms, delay_ms, last_activity, active, dispatch);
+    });
+}
+
+impl Drop for IdleListener {
+    fn drop(&mut self) {
+        self.active.set(false);
+        for event in &self.events {
+            if let Err(err) = self
+                .window
+                .remove_event_listener_with_callback(event, self.closure.as_ref().unchecked_ref())
+            {
+                error!("Unable to remove the {} listener: {:?}", event, err);
+            }
+        }
+    }
+}
+
+/// Listens for `keydown` events on the window, dispatching the actions
+/// of the matching [KeyBindings]. The listener is removed when this is
+/// dropped.
+pub(crate) struct KeyListener {
+    window: web_sys::Window,
+    closure: Closure<dyn FnMut(web_sys::KeyboardEvent)>,
+}
+
+impl KeyListener {
+    pub(crate) fn new<R, F>(bindings: KeyBindings<R>, dispatch: F) -> Option<Self>
+    where
+        R: Clone + 'static,
+        F: Fn(RouteAction<R>) + 'static,
+    {
+        let window = web_sys::window()?;
+        let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            if event.repeat() || is_editable(event.target()) {
+                return;
+            }
+            let press = KeyPress {
+                key: event.key(),
+                alt: event.alt_key(),
+                ctrl: event.ctrl_key(),
+                meta: event.meta_key(),
+                shift: event.shift_key(),
+            };
+            if let Some(action) = bindings.handle(press, event.time_stamp()) {
+                event.prevent_default();
+                dispatch(action);
+            }
+        }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
+
+        if let Err(err) =
+            window.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
+        {
+            error!("Unable to listen for keyboard shortcuts: {:?}", err);
+            return None;
+        }
+        Some(Self { window, closure })
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        if let Err(err) = self
+            .window
+            .remove_event_listener_with_callback("keydown", self.closure.as_ref().unchecked_ref())
+        {
+            error!("Unable to remove keyboard shortcut listener: {:?}", err);
+        }
+    }
+}
+
+/// Listens for `message` events on the window, dispatching the actions
+/// of the [EmbedMessage]s sent from allowed origins. The listener is
+/// removed when this is dropped.
+pub(crate) struct MessageListener {
+    window: web_sys::Window,
+    closure: Closure<dyn FnMut(web_sys::MessageEvent)>,
+}
+
+impl MessageListener {
+    pub(crate) fn new<R, F>(config: EmbedConfig, dispatch: F) -> Option<Self>
+    where
+        R: SwitchRoute + 'static,
+        F: Fn(RouteAction<R>) + 'static,
+    {
+        let window = web_sys::window()?;
+        let closure = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+            if !config.is_allowed(&event.origin()) {
+                return;
+            }
+            let action = event
+                .data()
+                .as_string()
+                .and_then(|data| EmbedMessage::decode(&data))
+                .and_then(EmbedMessage::into_route_action);
+            if let Some(action) = action {
+                dispatch(action);
+            }
+        }) as Box<dyn FnMut(web_sys::MessageEvent)>);
+
+        if let Err(err) =
+            window.add_event_listener_with_callback("message", closure.as_ref().unchecked_ref())
+        {
+            error!("Unable to listen for embed messages: {:?}", err);
+            return None;
+        }
+        Some(Self { window, closure })
+    }
+}
+
+impl Drop for MessageListener {
+    fn drop(&mut self) {
+        if let Err(err) = self
+            .window
+            .remove_event_listener_with_callback("message", self.closure.as_ref().unchecked_ref())
+        {
+            error!("Unable to remove embed message listener: {:?}", err);
+        }
+    }
+}
+
+/// Post the `message` to the parent window at `target_origin`.
+pub fn post_to_parent(message: &EmbedMessage, target_origin: &str) {
+    match web_sys::window().map(|window| window.parent()) {
+        Some(Ok(Some(parent))) => {
+            if let Err(err) =
+                parent.post_message(&JsValue::from_str(&message.encode()), target_origin)
+            {
+                error!(
+                    "Unable to post {:?} to the parent window: {:?}",
+                    message, err
+                );
+            }
+        }
+        Some(Ok(None)) | None => error!("Unable to post {:?}: no parent window", message),
+        Some(Err(err)) => error!(
+            "Unable to post {:?} to the parent window: {:?}",
+            message, err
+        ),
+    }
+}
+
+/// A function posting string messages to the `port`, for the transport
+/// of a [worker](crate::worker).
+#[cfg(feature = "worker")]
+pub fn port_sender(port: web_sys::MessagePort) -> impl Fn(&str) {
+    move |data| {
+        if let Err(err) = port.post_message(&JsValue::from_str(data)) {
+            error!("Unable to post {:?} to the message port: {:?}", data, err);
+        }
+    }
+}
+
+/// Passes the string messages received on a `MessagePort` to a handler,
+/// for the transport of a [worker](crate::worker). The handler is removed
+/// when this is dropped.
+#[cfg(feature = "worker")]
+pub struct PortListener {
+    port: web_sys::MessagePort,
+    _closure: Closure<dyn FnMut(web_sys::MessageEvent)>,
+}
+
+#[cfg(feature = "worker")]
+impl PortListener {
+    pub fn new<F>(port: web_sys::MessagePort, handler: F) -> Self
+    where
+        F: Fn(&str) + 'static,
+    {
+        let closure = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+            if let Some(data) = event.data().as_string() {
+                handler(&data);
+            }
+        }) as Box<dyn FnMut(web_sys::MessageEvent)>);
+        port.set_onmessage(Some(closure.as_ref().unchecked_ref()));
+        port.start();
+        Self {
+            port,
+            _closure: closure,
+        }
+    }
+}
+
+#[cfg(feature = "worker")]
+impl Drop for PortListener {
+    fn drop(&mut self) {
+        self.port.set_onmessage(None);
+    }
+}
+
+/// Whether the `target` of a key press is a text input, in which case
+/// shortcuts are ignored.
+fn is_editable(target: Option<web_sys::EventTarget>) -> bool {
+    match target.and_then(|target| target.dyn_into::<web_sys::Element>().ok()) {
+        Some(element) => {
+            matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                || element.has_attribute("contenteditable")
+        }
+        None => false,
+    }
+}
+
+/// Run `callback` after `timeout_ms`, using `window.setTimeout()`.
+pub fn set_timeout<F>(timeout_ms: u32, callback: F)
+where
+    F: FnOnce() + 'static,
+{
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => {
+            error!("Unable to set timeout: no window");
+            return;
+        }
+    };
+    let callback = Closure::<dyn FnMut()>::once_into_js(callback);
+    if let Err(err) = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+        callback.unchecked_ref(),
+        timeout_ms as i32,
+    ) {
+        error!("Unable to set timeout: {:?}", err);
+    }
+}
+
+/// Run `callback` when the browser is idle, using
+/// `window.requestIdleCallback()`.
+pub fn request_idle_callback<F>(callback: F)
+where
+    F: FnOnce() + 'static,
+{
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => {
+            error!("Unable to request idle callback: no window");
+            return;
+        }
+    };
+    let callback = Closure::<dyn FnMut()>::once_into_js(callback);
+    if let Err(err) = window.request_idle_callback(callback.unchecked_ref()) {
+        error!("Unable to request idle callback: {:?}", err);
+    }
+}
+
+fn performance() -> Option<web_sys::Performance> {
+    let performance = web_sys::window().and_then(|window| window.performance());
+    if performance.is_none() {
+        error!("Unable to access window.performance");
+    }
+    performance
+}
+
+/// Add a mark named `name` to the performance timeline.
+pub fn performance_mark(name: &str) {
+    if let Some(performance) = performance() {
+        if let Err(err) = performance.mark(name) {
+            error!("Unable to add performance mark {:?}: {:?}", name, err);
+        }
+    }
+}
+
+/// Add a measure named `name` between the marks `start` and `end` to
+/// the performance timeline.
+pub fn performance_measure(name: &str, start: &str, end: &str) {
+    if let Some(performance) = performance() {
+        if let Err(err) = performance.measure_with_start_mark_and_end_mark(name, start, end) {
+            error!("Unable to add performance measure {:?}: {:?}", name, err);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Multi-step forms (wizards) where each step is a route, e.g.
+//! `/signup/account`, `/signup/profile`, `/signup/confirm`. Requires the
+//! `wizard` feature.
+//!
+//! A [Wizard] maps its steps to their routes, in order, each with a
+//! predicate deciding from the state whether the step has been filled
+//! in validly. As a [RouteGuard] it enforces the order of the steps: a
+//! navigation to a step (e.g. typed in, or from a bookmark) is
+//! redirected to the first earlier step which isn't valid yet. The
+//! wizard is shared between the middleware and the application with an
+//! [Rc], see [guard()](Wizard::guard), and the application navigates
+//! between the steps with [next_step()](Wizard::next_step) and
+//! [prev_step()](Wizard::prev_step):
+//!
+//! ```ignore
+//! let wizard = Rc::new(
+//!     Wizard::new()
+//!         .with_step(AppRoute::SignupAccount, |state: &State| state.signup.account.is_valid())
+//!         .with_step(AppRoute::SignupProfile, |state: &State| state.signup.profile.is_valid())
+//!         .with_step(AppRoute::SignupConfirm, |_: &State| true),
+//! );
+//! let middleware = RouteMiddleware::new(route_service, store.clone())
+//!     .with_guard(wizard.guard());
+//!
+//! // The "Next" button:
+//! wizard.next_step(&store);
+//! ```
+//!
+//! Steps are matched by the path portion of their routes, so a step's
+//! route may carry query parameters.
+
+use crate::{
+    canonical::split_path,
+    guard::{GuardContext, GuardOutcome, RouteGuard},
+    logging::debug,
+    RouteState, RouteStore,
+};
+use reactive_state::StoreRef;
+use std::{hash::Hash, rc::Rc};
+use switch_router::SwitchRoute;
+
+type IsValidFn<State> = dyn Fn(&State) -> bool;
+
+struct WizardStep<R, State> {
+    route: R,
+    is_valid: Box<IsValidFn<State>>,
+}
+
+/// The steps of a multi-step form, see the
+/// [module documentation](self).
+pub struct Wizard<R, State> {
+    steps: Vec<WizardStep<R, State>>,
+}
+
+impl<R, State> Default for Wizard<R, State> {
+    fn default() -> Self {
+        Self { steps: Vec::new() }
+    }
+}
+
+impl<R, State> Wizard<R, State>
+where
+    R: SwitchRoute,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a step displayed at `route`, after the existing steps, where
+    /// `is_valid` decides whether it has been filled in validly. The
+    /// steps after it can only be navigated to once it is valid.
+    pub fn with_step<F>(mut self, route: R, is_valid: F) -> Self
+    where
+        F: Fn(&State) -> bool + 'static,
+    {
+        self.steps.push(WizardStep {
+            route,
+            is_valid: Box::new(is_valid),
+        });
+        self
+    }
+
+    /// The index of the step displayed at `route`, or `None` if it isn't
+    /// one of the steps.
+    pub fn step_index(&self, route: &R) -> Option<usize> {
+        let path = route.path();
+        let (path, _) = split_path(&path);
+        self.steps.iter().position(|step| {
+            let step_path = step.route.path();
+            split_path(&step_path).0 == path
+        })
+    }
+
+    /// The index of the first step which isn't valid in the `state`, or
+    /// `None` if they all are.
+    pub fn first_invalid(&self, state: &State) -> Option<usize> {
+        self.steps.iter().position(|step| !(step.is_valid)(state))
+    }
+
+    /// The route of the step after the one displayed at `current`, if the
+    /// current step is valid in the `state`.
+    pub fn next_route(&self, state: &State, current: &R) -> Option<R> {
+        let index = self.step_index(current)?;
+        if !(self.steps[index].is_valid)(state) {
+            debug!("Not moving past the invalid wizard step {}", current.path());
+            return None;
+        }
+        self.steps.get(index + 1).map(|step| step.route.clone())
+    }
+
+    /// The route of the step before the one displayed at `current`.
+    pub fn prev_route(&self, current: &R) -> Option<R> {
+        let index = self.step_index(current)?.checked_sub(1)?;
+        Some(self.steps[index].route.clone())
+    }
+
+    /// Navigate to the next step, if the current step is valid. Returns
+    /// whether there was a step to navigate to.
+    pub fn next_step<Action, Event, Effect>(
+        &self,
+        store: &StoreRef<State, Action, Event, Effect>,
+    ) -> bool
+    where
+        State: RouteState<R>,
+        Event: Clone + Hash + Eq,
+        StoreRef<State, Action, Event, Effect>: RouteStore<R>,
+    {
+        let state = store.state();
+        match self.next_route(&state, state.get_route()) {
+            Some(route) => {
+                store.change_route(route);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Navigate to the previous step. Returns whether there was a step to
+    /// navigate to.
+    pub fn prev_step<Action, Event, Effect>(
+        &self,
+        store: &StoreRef<State, Action, Event, Effect>,
+    ) -> bool
+    where
+        State: RouteState<R>,
+        Event: Clone + Hash + Eq,
+        StoreRef<State, Action, Event, Effect>: RouteStore<R>,
+    {
+        match self.prev_route(store.state().get_route()) {
+            Some(route) => {
+                store.change_route(route);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// A [RouteGuard] for the middleware, sharing this wizard with the
+    /// application.
+    pub fn guard(self: &Rc<Self>) -> impl RouteGuard<R, State> {
+        let wizard = self.clone();
+        move |context: &GuardContext<'_, R, State>| wizard.check(context)
+    }
+}
+
+impl<R, State> RouteGuard<R, State> for Wizard<R, State>
+where
+    R: SwitchRoute,
+{
+    fn check(&self, context: &GuardContext<'_, R, State>) -> GuardOutcome<R> {
+        let index = match self.step_index(context.target) {
+            Some(index) => index,
+            None => return GuardOutcome::Allow,
+        };
+        match self.first_invalid(&context.state) {
+            Some(invalid) if invalid < index => {
+                debug!(
+                    "Redirecting {} to the first invalid wizard step",
+                    context.target.path()
+                );
+                GuardOutcome::Redirect(self.steps[invalid].route.clone())
+            }
+            _ => GuardOutcome::Allow,
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! Running the store, and this middleware, inside a Web Worker, requires
+//! the `worker` feature.
+//!
+//! Workers have no access to the DOM, so the browser's history is driven
+//! by a thin proxy on the main thread, communicating with the worker by
+//! posting [WorkerMessage]s over a message channel:
+//!
+//! + In the worker, the middleware uses a [WorkerRouteService], which
+//!   posts the route service operations to the main thread, and delivers
+//!   the routes it receives to the middleware like popstate events.
+//! + On the main thread, a [MainThreadProxy] applies the operations to
+//!   the real route service, and posts its route changes to the worker.
+//!
+//! The transport is a plain function posting strings, with the `web`
+//! feature [web::port_sender()](crate::web::port_sender) and
+//! [web::PortListener](crate::web::PortListener) connect both sides to
+//! the ports of a `MessageChannel`. The browser specific options of the
+//! middleware (such as
+//! [with_key_bindings()](crate::RouteMiddleware::with_key_bindings)) are
+//! not available in a worker, and must be left disabled there.
+//!
+//! The middleware is not `Send`: like the store, it is built on `Rc` and
+//! `RefCell`, and so are [WorkerRouteService] and [MainThreadProxy].
+//! Running in a worker doesn't require it, because a worker has its own
+//! instance of the application (store, middleware and route service),
+//! created inside the worker. Nothing but the encoded [WorkerMessage]s
+//! crosses between the threads.
+
+use std::{cell::RefCell, rc::Rc};
+use switch_router::{Callback, SwitchRoute, SwitchRouteService};
+
+type PostFn = dyn Fn(&str);
+
+/// A message exchanged between the worker and the main thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerMessage {
+    /// Sent by the worker, push a history entry for the path.
+    SetRoute(String),
+    /// Sent by the worker, replace the current history entry with the
+    /// path.
+    ReplaceRoute(String),
+    /// Sent by the worker, go back in the history.
+    Back,
+    /// Sent by the main thread, the route changed to the path.
+    RouteChanged(String),
+}
+
+impl WorkerMessage {
+    /// Encode this message as the string data of a `postMessage`, e.g.
+    /// `set:/settings`.
+    pub fn encode(&self) -> String {
+        match self {
+            WorkerMessage::SetRoute(path) => format!("set:{}", path),
+            WorkerMessage::ReplaceRoute(path) => format!("replace:{}", path),
+            WorkerMessage::Back => "back".to_string(),
+            WorkerMessage::RouteChanged(path) => format!("route:{}", path),
+        }
+    }
+
+    /// Decode a message [encoded](Self::encode) in `data`, returns `None`
+    /// if it isn't one.
+    pub fn decode(data: &str) -> Option<Self> {
+        if data == "back" {
+            return Some(WorkerMessage::Back);
+        }
+        let (kind, path) = data.split_at(data.find(':')?);
+        let path = path[1..].to_string();
+        match kind {
+            "set" => Some(WorkerMessage::SetRoute(path)),
+            "replace" => Some(WorkerMessage::ReplaceRoute(path)),
+            "route" => Some(WorkerMessage::RouteChanged(path)),
+            _ => None,
+        }
+    }
+}
+
+struct WorkerRoute<R> {
+    route: R,
+    callbacks: Vec<Callback<R>>,
+}
+
+/// The [SwitchRouteService] of the middleware inside a worker, see the
+/// [module documentation](self). Clones share the same route, so a clone
+/// can be kept to [receive()](Self::receive) messages once the service
+/// has been moved into the middleware.
+pub struct WorkerRouteService<R> {
+    inner: Rc<RefCell<WorkerRoute<R>>>,
+    post: Rc<PostFn>,
+}
+
+impl<R> WorkerRouteService<R>
+where
+    R: SwitchRoute,
+{
+    /// Create a new [WorkerRouteService], starting at the `initial`
+    /// route (usually sent by the main thread when creating the worker),
+    /// which posts messages to the main thread with `post`.
+    pub fn new<F>(initial: R, post: F) -> Self
+    where
+        F: Fn(&str) + 'static,
+    {
+        Self {
+            inner: Rc::new(RefCell::new(WorkerRoute {
+                route: initial,
+                callbacks: Vec::new(),
+            })),
+            post: Rc::new(post),
+        }
+    }
+
+    fn post(&self, message: WorkerMessage) {
+        (self.post)(&message.encode());
+    }
+
+    /// Handle a message posted by the main thread, delivering a changed
+    /// route to the registered callbacks.
+    pub fn receive(&self, data: &str) {
+        let path = match WorkerMessage::decode(data) {
+            Some(WorkerMessage::RouteChanged(path)) => path,
+            _ => return,
+        };
+        let route = R::switch(&path);
+        let callbacks = {
+            let mut inner = self.inner.borrow_mut();
+            if inner.route == route {
+                return;
+            }
+            inner.route = route.clone();
+            inner.callbacks.clone()
+        };
+        // The callbacks are invoked outside of the borrow, they dispatch
+        // actions which use the service.
+        for callback in callbacks {
+            callback.emit(route.clone());
+        }
+    }
+}
+
+impl<R> Clone for WorkerRouteService<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            post: self.post.clone(),
+        }
+    }
+}
+
+impl<R> SwitchRouteService for WorkerRouteService<R>
+where
+    R: SwitchRoute,
+{
+    type Route = R;
+
+    fn set_route<SRI: Into<R>>(&mut self, switch_route: SRI) {
+        let route = switch_route.into();
+        let path = route.path();
+        self.inner.borrow_mut().route = route;
+        self.post(WorkerMessage::SetRoute(path));
+    }
+
+    fn replace_route<SRI: Into<R>>(&mut self, switch_route: SRI) -> R {
+        let route = switch_route.into();
+        let path = route.path();
+        let previous = std::mem::replace(&mut self.inner.borrow_mut().route, route);
+        self.post(WorkerMessage::ReplaceRoute(path));
+        previous
+    }
+
+    fn register_callback(&mut self, callback: &Callback<R>) {
+        self.inner.borrow_mut().callbacks.push(callback.clone());
+    }
+
+    /// Asks the main thread to go back, the route is delivered to the
+    /// callbacks when it is received.
+    fn back(&mut self) -> Option<R> {
+        self.post(WorkerMessage::Back);
+        None
+    }
+
+    fn get_route(&self) -> R {
+        self.inner.borrow().route.clone()
+    }
+}
+
+/// Drives the real route service on the main thread on behalf of a
+/// worker, see the [module documentation](self).
+pub struct MainThreadProxy<R, RS> {
+    route_service: RS,
+    /// Posts the route changes of the route service to the worker, kept
+    /// alive for as long as the proxy.
+    _callback: Callback<R>,
+}
+
+impl<R, RS> MainThreadProxy<R, RS>
+where
+    R: SwitchRoute + 'static,
+    RS: SwitchRouteService<Route = R>,
+{
+    /// Create a new [MainThreadProxy] for the `route_service`, which
+    /// posts messages to the worker with `post`.
+    pub fn new<F>(mut route_service: RS, post: F) -> Self
+    where
+        F: Fn(&str) + 'static,
+    {
+        let callback = Callback::new(move |route: R| {
+            post(&WorkerMessage::RouteChanged(route.path()).encode());
+        });
+        route_service.register_callback(&callback);
+        Self {
+            route_service,
+            _callback: callback,
+        }
+    }
+
+    /// The message to send to a new worker, with the current route.
+    pub fn initial_message(&self) -> String {
+        WorkerMessage::RouteChanged(self.route_service.get_route().path()).encode()
+    }
+
+    /// Handle a message posted by the worker, applying it to the route
+    /// service.
+    pub fn receive(&mut self, data: &str) {
+        match WorkerMessage::decode(data) {
+            Some(WorkerMessage::SetRoute(path)) => self.route_service.set_route(R::switch(&path)),
+            Some(WorkerMessage::ReplaceRoute(path)) => {
+                self.route_service.replace_route(R::switch(&path));
+            }
+            Some(WorkerMessage::Back) => {
+                self.route_service.back();
+            }
+            Some(WorkerMessage::RouteChanged(_)) | None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::MemoryRouteService, test_kit::TestRoute};
+    use std::collections::VecDeque;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn messages_cross_threads() {
+        assert_send::<WorkerMessage>();
+        for message in [
+            WorkerMessage::SetRoute("/settings?tab=1".to_string()),
+            WorkerMessage::ReplaceRoute("/users/5".to_string()),
+            WorkerMessage::Back,
+            WorkerMessage::RouteChanged("/a:b".to_string()),
+        ] {
+            assert_eq!(WorkerMessage::decode(&message.encode()), Some(message));
+        }
+        assert_eq!(WorkerMessage::decode("unknown:/"), None);
+    }
+
+    #[test]
+    fn worker_drives_the_main_thread_route_service() {
+        let to_main = Rc::new(RefCell::new(VecDeque::new()));
+        let to_worker = Rc::new(RefCell::new(VecDeque::new()));
+        let history = MemoryRouteService::new(TestRoute::new("/"));
+        let mut proxy = {
+            let to_worker = to_worker.clone();
+            MainThreadProxy::new(history.clone(), move |data: &str| {
+                to_worker.borrow_mut().push_back(data.to_string())
+            })
+        };
+        let mut worker = {
+            let to_main = to_main.clone();
+            WorkerRouteService::new(TestRoute::new("/"), move |data: &str| {
+                to_main.borrow_mut().push_back(data.to_string())
+            })
+        };
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let callback = {
+            let received = received.clone();
+            Callback::new(move |route: TestRoute| received.borrow_mut().push(route))
+        };
+        worker.register_callback(&callback);
+
+        worker.set_route(TestRoute::new("/settings"));
+        while let Some(data) = to_main.borrow_mut().pop_front() {
+            proxy.receive(&data);
+        }
+        assert_eq!(history.get_route(), TestRoute::new("/settings"));
+
+        worker.back();
+        while let Some(data) = to_main.borrow_mut().pop_front() {
+            proxy.receive(&data);
+        }
+        history.deliver_pending();
+        while let Some(data) = to_worker.borrow_mut().pop_front() {
+            worker.receive(&data);
+        }
+        assert_eq!(worker.get_route(), TestRoute::new("/"));
+        assert_eq!(*received.borrow(), vec![TestRoute::new("/")]);
+    }
+}
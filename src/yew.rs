@@ -0,0 +1,317 @@
+//! [Yew](https://yew.rs/) components for applications routed by the
+//! [RouteMiddleware](crate::RouteMiddleware), requires the `yew` feature.
+//!
+//! The components and hooks read the current route from a
+//! [RouteContext], which the application creates once from its store,
+//! and provides to the components beneath it:
+//!
+//! ```ignore
+//! let context = RouteContext::from_store(&store);
+//! html! {
+//!     <ContextProvider<RouteContext<AppRoute>> {context}>
+//!         <nav>
+//!             <RouterLink<AppRoute> to={AppRoute::Settings} prefix={true}>{ "Settings" }</RouterLink<AppRoute>>
+//!         </nav>
+//!         <RouterSwitch<AppRoute> render={Callback::from(render_route)} />
+//!     </ContextProvider<RouteContext<AppRoute>>>
+//! }
+//! ```
+//!
+//! [RouterLink] formats its `href` with the [Href] of the context. Create
+//! the context with
+//! [RouteMiddleware::route_context()](crate::RouteMiddleware::route_context)
+//! (before adding the middleware to the store), so that links respect
+//! the base path and hash mode the middleware is configured with.
+//!
+//! Function components use the [use_route()] and [use_navigator()]
+//! hooks, which subscribe to the store's
+//! [RouteEvent::route_changed()](crate::RouteEvent::route_changed) event
+//! only, so that components re-render when navigating, rather than on
+//! every notification of the store.
+
+use crate::{
+    href::Href,
+    logging::error,
+    route_dispatcher,
+    scope::{route_is_active, route_is_active_prefix, RouteScope},
+    RouteAction, RouteEvent, RouteState,
+};
+use ::yew::prelude::*;
+use reactive_state::StoreRef;
+use std::{any::Any, cell::Cell, hash::Hash, rc::Rc};
+use switch_router::SwitchRoute;
+
+type RouteFn<R> = dyn Fn() -> R;
+type SubscribeFn = dyn Fn(Box<dyn Fn()>) -> Subscription;
+
+/// Keeps a listener subscribed to the store's route changes, for as long
+/// as it is alive.
+pub struct Subscription(#[allow(dead_code)] Box<dyn Any>);
+
+/// The current route, a callback to dispatch route actions to the store,
+/// (when created from a store) a way to subscribe to route changes, and
+/// the [Href] formatting the links, provided to the components and hooks
+/// of this module with a `ContextProvider`.
+#[derive(Clone)]
+pub struct RouteContext<R> {
+    route: Rc<RouteFn<R>>,
+    dispatch: Callback<RouteAction<R>>,
+    subscribe: Option<Rc<SubscribeFn>>,
+    href: Href,
+}
+
+impl<R> RouteContext<R>
+where
+    R: SwitchRoute + 'static,
+{
+    /// Create a [RouteContext] for a fixed `route`, which needs to be
+    /// re-created whenever the route changes.
+    pub fn new(route: R, dispatch: Callback<RouteAction<R>>) -> Self {
+        Self {
+            route: Rc::new(move || route.clone()),
+            dispatch,
+            subscribe: None,
+            href: Href::default(),
+        }
+    }
+
+    /// Create a [RouteContext] which follows the current route of the
+    /// `store`, and dispatches to it.
+    pub fn from_store<State, Action, Event, Effect>(
+        store: &StoreRef<State, Action, Event, Effect>,
+    ) -> Self
+    where
+        State: RouteState<R> + 'static,
+        Action: From<RouteAction<R>> + 'static,
+        Event: RouteEvent<R> + Clone + Hash + Eq + 'static,
+        Effect: 'static,
+    {
+        let dispatch = route_dispatcher(store.downgrade(), Rc::new(Cell::new(None)), Action::from);
+        let route_store = store.clone();
+        let subscribe_store = store.clone();
+        Self {
+            route: Rc::new(move || route_store.state().get_route().clone()),
+            dispatch: Callback::from(dispatch),
+            subscribe: Some(Rc::new(move |listener: Box<dyn Fn()>| {
+                let callback = reactive_state::Callback::new(
+                    move |_state: Rc<State>, _event: Option<Event>| listener(),
+                );
+                subscribe_store.subscribe_event(&callback, Event::route_changed());
+                Subscription(Box::new(callback))
+            })),
+            href: Href::default(),
+        }
+    }
+
+    /// Format the `href` of links with `href`, rather than the default
+    /// [Href], see
+    /// [RouteMiddleware::route_context()](crate::RouteMiddleware::route_context).
+    pub fn with_href(mut self, href: Href) -> Self {
+        self.href = href;
+        self
+    }
+
+    pub fn route(&self) -> R {
+        (self.route)()
+    }
+
+    pub fn dispatch(&self, action: RouteAction<R>) {
+        self.dispatch.emit(action)
+    }
+
+    /// Generate the `href` for `route` with the [Href] of the context.
+    pub fn href(&self, route: &R) -> String {
+        self.href.href(route)
+    }
+
+    /// Call `listener` whenever the route changes, until the returned
+    /// [Subscription] is dropped. Returns `None` for a context with a
+    /// fixed route.
+    pub fn subscribe<F>(&self, listener: F) -> Option<Subscription>
+    where
+        F: Fn() + 'static,
+    {
+        self.subscribe
+            .as_ref()
+            .map(|subscribe| subscribe(Box::new(listener)))
+    }
+}
+
+/// Contexts are compared by their current route and [Href].
+impl<R> PartialEq for RouteContext<R>
+where
+    R: SwitchRoute + 'static,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.route() == other.route() && self.href == other.href
+    }
+}
+
+/// A handle for navigating from a function component, see
+/// [use_navigator()].
+#[derive(Clone, PartialEq)]
+pub struct Navigator<R> {
+    dispatch: Callback<RouteAction<R>>,
+    href: Href,
+}
+
+impl<R> Navigator<R>
+where
+    R: SwitchRoute + 'static,
+{
+    /// Navigate to `route`, see [RouteAction::ChangeRoute].
+    pub fn push(&self, route: R) {
+        self.dispatch(RouteAction::ChangeRoute(route));
+    }
+
+    /// Navigate to `route`, replacing the current history entry, see
+    /// [RouteAction::ReplaceRoute].
+    pub fn replace(&self, route: R) {
+        self.dispatch(RouteAction::ReplaceRoute(route));
+    }
+
+    pub fn back(&self) {
+        self.dispatch(RouteAction::Back);
+    }
+
+    pub fn dispatch(&self, action: RouteAction<R>) {
+        self.dispatch.emit(action)
+    }
+
+    /// Generate the `href` for `route` with the [Href] of the
+    /// [RouteContext].
+    pub fn href(&self, route: &R) -> String {
+        self.href.href(route)
+    }
+}
+
+/// The current route of the [RouteContext], or `None` if there is no
+/// context. The component re-renders when the route changes.
+#[hook]
+pub fn use_route<R>() -> Option<R>
+where
+    R: SwitchRoute + 'static,
+{
+    let context = use_context::<RouteContext<R>>();
+    let route = use_state_eq(|| context.as_ref().map(RouteContext::route));
+    {
+        let route = route.clone();
+        use_effect_with(context, move |context| {
+            // The route of a re-created context with a fixed route.
+            route.set(context.as_ref().map(RouteContext::route));
+            let subscription = context.as_ref().and_then(|context| {
+                let listener_context = context.clone();
+                context.subscribe(move || route.set(Some(listener_context.route())))
+            });
+            move || drop(subscription)
+        });
+    }
+    (*route).clone()
+}
+
+/// A [Navigator] for the [RouteContext], or `None` if there is no
+/// context. Unlike [use_route()], it doesn't re-render the component when
+/// the route changes.
+#[hook]
+pub fn use_navigator<R>() -> Option<Navigator<R>>
+where
+    R: SwitchRoute + 'static,
+{
+    use_context::<RouteContext<R>>().map(|context| Navigator {
+        dispatch: context.dispatch,
+        href: context.href,
+    })
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RouterLinkProps<R>
+where
+    R: PartialEq,
+{
+    /// The route to navigate to.
+    pub to: R,
+    /// Whether the link is also active for the routes beneath `to`, see
+    /// [is_active_prefix()](crate::scope::is_active_prefix).
+    #[prop_or_default]
+    pub prefix: bool,
+    #[prop_or_default]
+    pub classes: Classes,
+    /// The class added while the link is active.
+    #[prop_or(AttrValue::Static("active"))]
+    pub active_class: AttrValue,
+    #[prop_or_default]
+    pub children: Children,
+}
+
+/// An anchor linking to a route, which navigates with a
+/// [RouteAction::ChangeRoute] when clicked, rather than reloading the
+/// page. Clicks with a modifier key, or with a button other than the
+/// main one, are left to the browser (e.g. to open the link in a new
+/// tab). The `href` is formatted with the [Href] of the [RouteContext].
+/// The `active_class` is added while the link is active.
+#[function_component]
+pub fn RouterLink<R>(props: &RouterLinkProps<R>) -> Html
+where
+    R: RouteScope + 'static,
+{
+    let (route, navigator) = match (use_route::<R>(), use_navigator::<R>()) {
+        (Some(route), Some(navigator)) => (route, navigator),
+        _ => {
+            error!("RouterLink requires a RouteContext to be provided");
+            return html! {};
+        }
+    };
+    let active = if props.prefix {
+        route_is_active_prefix(&route, &props.to)
+    } else {
+        route_is_active(&route, &props.to)
+    };
+    let href = navigator.href(&props.to);
+    let mut classes = props.classes.clone();
+    if active {
+        classes.push(props.active_class.clone());
+    }
+    let onclick = {
+        let to = props.to.clone();
+        Callback::from(move |event: MouseEvent| {
+            if event.button() != 0
+                || event.ctrl_key()
+                || event.meta_key()
+                || event.shift_key()
+                || event.alt_key()
+            {
+                return;
+            }
+            event.prevent_default();
+            navigator.push(to.clone());
+        })
+    };
+    let aria_current = if active { Some("page") } else { None };
+
+    html! {
+        <a {href} class={classes} aria-current={aria_current} {onclick}>
+            { for props.children.iter() }
+        </a>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RouterSwitchProps<R> {
+    /// Renders the content for the current route.
+    pub render: Callback<R, Html>,
+}
+
+/// Renders the content for the current route of the [RouteContext].
+#[function_component]
+pub fn RouterSwitch<R>(props: &RouterSwitchProps<R>) -> Html
+where
+    R: SwitchRoute + 'static,
+{
+    match use_route::<R>() {
+        Some(route) => props.render.emit(route),
+        None => {
+            error!("RouterSwitch requires a RouteContext to be provided");
+            html! {}
+        }
+    }
+}
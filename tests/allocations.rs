@@ -0,0 +1,299 @@
+//! Reducing an action which doesn't change the route must not allocate:
+//! dashboards poll the browser route on intervals, and every action of
+//! the application passes through the middleware.
+//!
+//! The allocations are counted per thread, so that the tests running in
+//! parallel don't affect each other. The middleware is compared with a
+//! middleware which does nothing, so that the allocations of the store
+//! itself aren't counted.
+
+use reactive_state::{
+    middleware::{Middleware, ReduceFn, ReduceMiddlewareResult},
+    ReducerResult, Store, StoreRef,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    rc::Rc,
+};
+use switch_router::{Callback, SwitchRoute, SwitchRouteService};
+use switch_router_middleware::{
+    IsRouteAction, RouteAction, RouteEvent, RouteMiddleware, RouteState,
+};
+
+/// Counts the heap allocations of the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The counter is unavailable while the thread is being torn down.
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The number of heap allocations made by `f` on the current thread.
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    let start = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - start
+}
+
+/// A route which can be cloned without allocating.
+#[derive(Debug, Clone, PartialEq)]
+enum AppRoute {
+    Home,
+    Item(u32),
+    Unknown(String),
+}
+
+impl SwitchRoute for AppRoute {
+    fn path(&self) -> String {
+        match self {
+            AppRoute::Home => "/".to_string(),
+            AppRoute::Item(id) => format!("/items/{}", id),
+            AppRoute::Unknown(path) => path.clone(),
+        }
+    }
+
+    fn switch(route: &str) -> Self {
+        match route.strip_prefix("/items/") {
+            Some(id) => id
+                .parse()
+                .map(AppRoute::Item)
+                .unwrap_or_else(|_| AppRoute::Unknown(route.to_string())),
+            None if route == "/" => AppRoute::Home,
+            None => AppRoute::Unknown(route.to_string()),
+        }
+    }
+}
+
+/// A route service which only keeps the current route.
+struct RouteService {
+    route: AppRoute,
+}
+
+impl SwitchRouteService for RouteService {
+    type Route = AppRoute;
+
+    fn set_route<SRI: Into<AppRoute>>(&mut self, switch_route: SRI) {
+        self.route = switch_route.into();
+    }
+
+    fn replace_route<SRI: Into<AppRoute>>(&mut self, switch_route: SRI) -> AppRoute {
+        std::mem::replace(&mut self.route, switch_route.into())
+    }
+
+    fn register_callback(&mut self, _callback: &Callback<AppRoute>) {}
+
+    fn back(&mut self) -> Option<AppRoute> {
+        None
+    }
+
+    fn get_route(&self) -> AppRoute {
+        self.route.clone()
+    }
+}
+
+#[derive(Debug)]
+struct AppState {
+    route: AppRoute,
+}
+
+impl RouteState<AppRoute> for AppState {
+    fn get_route(&self) -> &AppRoute {
+        &self.route
+    }
+}
+
+#[derive(Debug, Clone)]
+enum AppAction {
+    Route(RouteAction<AppRoute>),
+    Refresh,
+}
+
+impl From<RouteAction<AppRoute>> for AppAction {
+    fn from(action: RouteAction<AppRoute>) -> Self {
+        AppAction::Route(action)
+    }
+}
+
+impl IsRouteAction<AppRoute> for AppAction {
+    fn route_action(&self) -> Option<&RouteAction<AppRoute>> {
+        match self {
+            AppAction::Route(action) => Some(action),
+            AppAction::Refresh => None,
+        }
+    }
+
+    fn into_route_action(self) -> Option<RouteAction<AppRoute>> {
+        match self {
+            AppAction::Route(action) => Some(action),
+            AppAction::Refresh => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum AppEvent {
+    RouteChanged,
+}
+
+impl RouteEvent<AppRoute> for AppEvent {
+    fn route_changed() -> Self {
+        AppEvent::RouteChanged
+    }
+}
+
+type AppStore = StoreRef<AppState, AppAction, AppEvent, ()>;
+
+fn reducer(state: &Rc<AppState>, action: &AppAction) -> ReducerResult<AppState, AppEvent, ()> {
+    match action {
+        AppAction::Route(RouteAction::ChangeRoute(route))
+        | AppAction::Route(RouteAction::BrowserChangeRoute(route, _)) => ReducerResult {
+            state: Rc::new(AppState {
+                route: route.clone(),
+            }),
+            events: vec![AppEvent::RouteChanged],
+            effects: vec![],
+        },
+        _ => ReducerResult {
+            state: state.clone(),
+            events: vec![],
+            effects: vec![],
+        },
+    }
+}
+
+/// A middleware which does nothing, for comparison.
+struct PassThrough;
+
+impl Middleware<AppState, AppAction, AppEvent, ()> for PassThrough {
+    fn on_reduce(
+        &self,
+        store: &Store<AppState, AppAction, AppEvent, ()>,
+        action: Option<&AppAction>,
+        reduce: ReduceFn<AppState, AppAction, AppEvent, ()>,
+    ) -> ReduceMiddlewareResult<AppEvent, ()> {
+        reduce(store, action)
+    }
+}
+
+/// A middleware which drops every action, so that the middlewares after
+/// it reduce `None`.
+struct DropActions;
+
+impl Middleware<AppState, AppAction, AppEvent, ()> for DropActions {
+    fn on_reduce(
+        &self,
+        store: &Store<AppState, AppAction, AppEvent, ()>,
+        _action: Option<&AppAction>,
+        reduce: ReduceFn<AppState, AppAction, AppEvent, ()>,
+    ) -> ReduceMiddlewareResult<AppEvent, ()> {
+        reduce(store, None)
+    }
+}
+
+fn bare_store() -> AppStore {
+    StoreRef::new(
+        reducer,
+        AppState {
+            route: AppRoute::Item(1),
+        },
+    )
+}
+
+/// A store with the [RouteMiddleware] at [AppRoute::Item], or the
+/// [PassThrough] middleware instead.
+fn store(route_middleware: bool) -> AppStore {
+    let store = bare_store();
+    if route_middleware {
+        store.add_middleware(RouteMiddleware::new(
+            RouteService {
+                route: AppRoute::Item(1),
+            },
+            store.clone(),
+        ));
+    } else {
+        store.add_middleware(PassThrough);
+    }
+    store
+}
+
+/// The allocations made by dispatching `action` to `store`, once it has
+/// been dispatched a first time (which may initialise the store).
+fn dispatch_allocations(store: &AppStore, action: &AppAction) -> usize {
+    store.dispatch(action.clone());
+    count_allocations(|| store.dispatch(action.clone()))
+}
+
+fn assert_no_allocations(
+    name: &str,
+    middleware: &AppStore,
+    baseline: &AppStore,
+    action: AppAction,
+) {
+    let made = dispatch_allocations(middleware, &action);
+    let expected = dispatch_allocations(baseline, &action);
+    assert!(
+        made <= expected,
+        "{} made {} allocations with the middleware, {} without",
+        name,
+        made,
+        expected
+    );
+}
+
+#[test]
+fn polling_an_unchanged_route_does_not_allocate() {
+    assert_no_allocations(
+        "polling an unchanged route",
+        &store(true),
+        &store(false),
+        AppAction::from(RouteAction::PollBrowserRoute),
+    );
+}
+
+#[test]
+fn actions_without_a_route_action_do_not_allocate() {
+    let middleware = store(true);
+    let baseline = store(false);
+    assert_no_allocations(
+        "an action which isn't a route action",
+        &middleware,
+        &baseline,
+        AppAction::Refresh,
+    );
+    assert_eq!(middleware.state().route, AppRoute::Item(1));
+
+    // A previous middleware dropping the action.
+    let middleware = bare_store();
+    middleware.add_middleware(DropActions);
+    middleware.add_middleware(RouteMiddleware::new(
+        RouteService {
+            route: AppRoute::Item(1),
+        },
+        middleware.clone(),
+    ));
+    let baseline = bare_store();
+    baseline.add_middleware(DropActions);
+    baseline.add_middleware(PassThrough);
+    assert_no_allocations(
+        "reducing no action",
+        &middleware,
+        &baseline,
+        AppAction::from(RouteAction::ChangeRoute(AppRoute::Item(2))),
+    );
+    assert_eq!(middleware.state().route, AppRoute::Item(1));
+}
@@ -0,0 +1,195 @@
+//! The order in which the actions of batches unpacked by the
+//! [RouteMiddleware] reach the reducer, see
+//! [with_action_batches()](RouteMiddleware::with_action_batches).
+#![cfg(feature = "test-util")]
+
+use reactive_state::{ReducerResult, StoreRef};
+use std::rc::Rc;
+use switch_router::SwitchRouteService;
+use switch_router_middleware::{
+    batch::BatchOrder,
+    memory::MemoryRouteService,
+    test_kit::{TestEvent, TestRoute},
+    IsRouteAction, RouteAction, RouteMiddleware, RouteState,
+};
+
+#[derive(Debug)]
+struct AppState {
+    route: TestRoute,
+    /// The actions reduced, in order.
+    reduced: Vec<String>,
+}
+
+impl RouteState<TestRoute> for AppState {
+    fn get_route(&self) -> &TestRoute {
+        &self.route
+    }
+}
+
+#[derive(Debug, Clone)]
+enum AppAction {
+    Route(RouteAction<TestRoute>),
+    Other(&'static str),
+    Batch(Vec<AppAction>),
+}
+
+impl From<RouteAction<TestRoute>> for AppAction {
+    fn from(action: RouteAction<TestRoute>) -> Self {
+        AppAction::Route(action)
+    }
+}
+
+impl IsRouteAction<TestRoute> for AppAction {
+    fn route_action(&self) -> Option<&RouteAction<TestRoute>> {
+        match self {
+            AppAction::Route(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    fn into_route_action(self) -> Option<RouteAction<TestRoute>> {
+        match self {
+            AppAction::Route(action) => Some(action),
+            _ => None,
+        }
+    }
+}
+
+fn reducer(state: &Rc<AppState>, action: &AppAction) -> ReducerResult<AppState, TestEvent, ()> {
+    let mut reduced = state.reduced.clone();
+    let mut route = state.route.clone();
+    match action {
+        AppAction::Route(RouteAction::ChangeRoute(new_route))
+        | AppAction::Route(RouteAction::BrowserChangeRoute(new_route, _)) => {
+            reduced.push(format!("route {}", new_route.0));
+            route = new_route.clone();
+        }
+        AppAction::Route(_) => {}
+        AppAction::Other(name) => reduced.push(name.to_string()),
+        AppAction::Batch(_) => reduced.push("batch".to_string()),
+    }
+    ReducerResult {
+        state: Rc::new(AppState { route, reduced }),
+        events: vec![],
+        effects: vec![],
+    }
+}
+
+fn store(
+    order: BatchOrder,
+) -> (
+    StoreRef<AppState, AppAction, TestEvent, ()>,
+    MemoryRouteService<TestRoute>,
+) {
+    let store = StoreRef::new(
+        reducer,
+        AppState {
+            route: TestRoute::new("/"),
+            reduced: Vec::new(),
+        },
+    );
+    let service = MemoryRouteService::new(TestRoute::new("/"));
+    store.add_middleware(
+        RouteMiddleware::new(service.clone(), store.clone()).with_action_batches(
+            |action: &AppAction| match action {
+                AppAction::Batch(actions) => Some(actions.as_slice()),
+                _ => None,
+            },
+            order,
+        ),
+    );
+    (store, service)
+}
+
+fn route(path: &str) -> AppAction {
+    AppAction::from(RouteAction::ChangeRoute(TestRoute::new(path)))
+}
+
+/// The actions reduced when `batch` is dispatched in `order`, and the
+/// route of the route service afterwards.
+fn reduced(order: BatchOrder, batch: Vec<AppAction>) -> (Vec<String>, String) {
+    let (store, service) = store(order);
+    store.dispatch(AppAction::Batch(batch));
+    (store.state().reduced.clone(), service.get_route().0)
+}
+
+fn expected(reduced: &[&str], route: &str) -> (Vec<String>, String) {
+    (
+        reduced.iter().map(|action| action.to_string()).collect(),
+        route.to_string(),
+    )
+}
+
+fn mixed() -> Vec<AppAction> {
+    vec![
+        AppAction::Other("a"),
+        route("/x"),
+        AppAction::Other("b"),
+        route("/y"),
+    ]
+}
+
+#[test]
+fn mixed_batches_are_reduced_in_order() {
+    assert_eq!(
+        reduced(BatchOrder::AsDispatched, mixed()),
+        expected(&["a", "route /x", "b", "route /y"], "/y")
+    );
+    assert_eq!(
+        reduced(BatchOrder::RouteActionsFirst, mixed()),
+        expected(&["route /x", "route /y", "a", "b"], "/y")
+    );
+    assert_eq!(
+        reduced(BatchOrder::RouteActionsLast, mixed()),
+        expected(&["a", "b", "route /x", "route /y"], "/y")
+    );
+}
+
+/// A nested batch counts as a non-route action of the enclosing batch,
+/// and is unpacked in the same order when its turn comes.
+#[test]
+fn nested_batches_are_ordered_in_turn() {
+    let nested = || {
+        vec![
+            AppAction::Other("a"),
+            AppAction::Batch(vec![route("/b"), AppAction::Other("c")]),
+            route("/d"),
+        ]
+    };
+    assert_eq!(
+        reduced(BatchOrder::AsDispatched, nested()),
+        expected(&["a", "route /b", "c", "route /d"], "/d")
+    );
+    // The route action of the nested batch comes after the route action
+    // of the enclosing batch, so it wins.
+    assert_eq!(
+        reduced(BatchOrder::RouteActionsFirst, nested()),
+        expected(&["route /d", "a", "route /b", "c"], "/b")
+    );
+    assert_eq!(
+        reduced(BatchOrder::RouteActionsLast, nested()),
+        expected(&["a", "c", "route /b", "route /d"], "/d")
+    );
+}
+
+#[test]
+fn empty_batches_reduce_nothing() {
+    for order in [
+        BatchOrder::AsDispatched,
+        BatchOrder::RouteActionsFirst,
+        BatchOrder::RouteActionsLast,
+    ] {
+        assert_eq!(reduced(order, vec![]), expected(&[], "/"));
+        assert_eq!(
+            reduced(order, vec![AppAction::Batch(vec![])]),
+            expected(&[], "/")
+        );
+    }
+    assert_eq!(
+        reduced(
+            BatchOrder::RouteActionsLast,
+            vec![AppAction::Batch(vec![]), route("/a"), AppAction::Other("b")]
+        ),
+        expected(&["b", "route /a"], "/a")
+    );
+}
@@ -0,0 +1,52 @@
+//! Scaffolding shared by the integration tests which need to keep a
+//! handle on the [RouteMiddleware](switch_router_middleware::RouteMiddleware)
+//! after adding it to the store.
+
+use reactive_state::{
+    middleware::{Middleware, ReduceFn, ReduceMiddlewareResult},
+    ReducerResult, Store,
+};
+use std::rc::Rc;
+use switch_router::SwitchRouteService;
+use switch_router_middleware::{
+    test_kit::{TestAction, TestEvent, TestRoute, TestState},
+    RouteAction, RouteMiddleware,
+};
+
+/// Adds the middleware to the store behind an [Rc], so that the test
+/// can keep a reference to it.
+pub struct Shared<RS>(pub Rc<RouteMiddleware<TestRoute, RS, TestState, TestAction, TestEvent, ()>>);
+
+impl<RS> Middleware<TestState, TestAction, TestEvent, ()> for Shared<RS>
+where
+    RS: SwitchRouteService<Route = TestRoute> + 'static,
+{
+    fn on_reduce(
+        &self,
+        store: &Store<TestState, TestAction, TestEvent, ()>,
+        action: Option<&TestAction>,
+        reduce: ReduceFn<TestState, TestAction, TestEvent, ()>,
+    ) -> ReduceMiddlewareResult<TestEvent, ()> {
+        self.0.on_reduce(store, action, reduce)
+    }
+}
+
+/// Commits the route of [RouteAction::ChangeRoute] and
+/// [RouteAction::BrowserChangeRoute].
+pub fn reducer(
+    state: &Rc<TestState>,
+    action: &TestAction,
+) -> ReducerResult<TestState, TestEvent, ()> {
+    let state = match action {
+        TestAction::Route(RouteAction::ChangeRoute(route))
+        | TestAction::Route(RouteAction::BrowserChangeRoute(route, _)) => Rc::new(TestState {
+            route: route.clone(),
+        }),
+        _ => state.clone(),
+    };
+    ReducerResult {
+        state,
+        events: vec![],
+        effects: vec![],
+    }
+}
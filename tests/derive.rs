@@ -0,0 +1,158 @@
+//! The routes built by `#[derive(RouteParams)]` for each shape of
+//! variant, and the events built by `#[derive(RouteEvent)]`.
+#![cfg(all(feature = "derive", feature = "test-util"))]
+
+use switch_router_middleware::{
+    params::RouteParams, scope::ScopeTransition, test_kit::TestRoute, RouteEvent,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum AppRoute {
+    Home,
+    UserPage { user_id: u64, tab: Option<String> },
+    Post(u64, String),
+}
+
+mod routes {
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum NestedRoute {
+        Item(u32),
+    }
+}
+
+#[derive(RouteParams, Debug, Clone, PartialEq)]
+#[route_params(AppRoute::UserPage)]
+pub struct UserPageParams {
+    /// The id of the user.
+    pub user_id: u64,
+    pub(crate) tab: Option<String>,
+}
+
+#[derive(RouteParams, Debug, Clone, PartialEq)]
+#[route_params(AppRoute::Post)]
+struct PostParams(pub u64, String);
+
+#[derive(RouteParams, Debug, PartialEq)]
+#[route_params(AppRoute::Home)]
+struct HomeParams;
+
+#[derive(RouteParams, Debug, PartialEq)]
+#[route_params(routes::NestedRoute::Item)]
+struct ItemParams(u32);
+
+#[test]
+fn named_fields_round_trip() {
+    let route = AppRoute::UserPage {
+        user_id: 5,
+        tab: Some("posts".to_string()),
+    };
+    let params = UserPageParams::from_route(&route).unwrap();
+    assert_eq!(
+        params,
+        UserPageParams {
+            user_id: 5,
+            tab: Some("posts".to_string()),
+        }
+    );
+    assert_eq!(AppRoute::from(params), route);
+}
+
+#[test]
+fn unnamed_fields_round_trip() {
+    let route = AppRoute::Post(3, "hello".to_string());
+    let params = PostParams::from_route(&route).unwrap();
+    assert_eq!(params, PostParams(3, "hello".to_string()));
+    assert_eq!(params.into_route(), route);
+
+    let route = routes::NestedRoute::Item(7);
+    assert_eq!(ItemParams::from_route(&route), Some(ItemParams(7)));
+    assert_eq!(routes::NestedRoute::from(ItemParams(7)), route);
+}
+
+#[test]
+fn unit_variant_round_trips() {
+    assert_eq!(HomeParams::from_route(&AppRoute::Home), Some(HomeParams));
+    assert_eq!(AppRoute::from(HomeParams), AppRoute::Home);
+}
+
+#[test]
+fn other_variants_have_no_params() {
+    let post = AppRoute::Post(3, "hello".to_string());
+    assert_eq!(UserPageParams::from_route(&post), None);
+    assert_eq!(HomeParams::from_route(&post), None);
+    assert_eq!(PostParams::from_route(&AppRoute::Home), None);
+}
+
+#[derive(RouteEvent, Debug, PartialEq)]
+#[route_event(TestRoute)]
+enum AppEvent {
+    #[route_event(route_changed)]
+    RouteChanged,
+    #[route_event(url_too_long)]
+    UrlTooLong(usize, usize),
+    /// Several constructors can share a variant.
+    #[route_event(navigation_blocked, initial_navigation_complete)]
+    Settled,
+    #[route_event(chunk_load_failed)]
+    ChunkLoadFailed(String),
+    #[route_event(navigation_failed)]
+    NavigationFailed,
+    #[route_event(scope_changed)]
+    ScopeChanged {
+        scope: String,
+        transition: ScopeTransition,
+    },
+    /// An event of the application.
+    Saved,
+}
+
+#[test]
+fn variants_hold_the_arguments_of_their_constructors() {
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::route_changed(),
+        AppEvent::RouteChanged
+    );
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::url_too_long(2100, 2000),
+        Some(AppEvent::UrlTooLong(2100, 2000))
+    );
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::chunk_load_failed("settings"),
+        Some(AppEvent::ChunkLoadFailed("settings".to_string()))
+    );
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::scope_changed("admin", ScopeTransition::Entered),
+        Some(AppEvent::ScopeChanged {
+            scope: "admin".to_string(),
+            transition: ScopeTransition::Entered,
+        })
+    );
+}
+
+#[test]
+fn variants_without_fields_ignore_the_arguments() {
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::navigation_failed("/broken"),
+        Some(AppEvent::NavigationFailed)
+    );
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::navigation_blocked(),
+        Some(AppEvent::Settled)
+    );
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::initial_navigation_complete(),
+        Some(AppEvent::Settled)
+    );
+}
+
+#[test]
+fn constructors_without_a_variant_are_ignored() {
+    assert_eq!(
+        <AppEvent as RouteEvent<TestRoute>>::confirmation_required("unsaved changes"),
+        None
+    );
+    assert_ne!(
+        <AppEvent as RouteEvent<TestRoute>>::route_changed(),
+        AppEvent::Saved
+    );
+}
@@ -0,0 +1,48 @@
+//! The [RouteMiddleware] holds the store weakly, so that it is dropped
+//! along with the store, rather than keeping it alive through the
+//! callback registered with the route service.
+#![cfg(feature = "test-util")]
+
+mod common;
+
+use common::{reducer, Shared};
+use reactive_state::StoreRef;
+use std::rc::Rc;
+use switch_router::SwitchRouteService;
+use switch_router_middleware::{
+    memory::MemoryRouteService,
+    test_kit::{TestAction, TestRoute, TestState},
+    testing, RouteAction, RouteMiddleware,
+};
+
+#[test]
+fn middleware_is_dropped_with_the_store() {
+    let store = StoreRef::new(
+        reducer,
+        TestState {
+            route: TestRoute::new("/"),
+        },
+    );
+    let service = MemoryRouteService::new(TestRoute::new("/"));
+    let middleware = Rc::new(RouteMiddleware::new(service.clone(), store.clone()));
+    let weak_middleware = Rc::downgrade(&middleware);
+    store.add_middleware(Shared(middleware));
+
+    store.dispatch(TestAction::from(RouteAction::ChangeRoute(TestRoute::new(
+        "/users",
+    ))));
+    testing::simulate_browser_navigation(&service, TestRoute::new("/settings"));
+    assert_eq!(store.state().route, TestRoute::new("/settings"));
+
+    drop(store);
+    assert!(
+        weak_middleware.upgrade().is_none(),
+        "the middleware outlived the store"
+    );
+
+    // The callback registered with the route service outlives the
+    // middleware (the service is shared with the test), and ignores
+    // routes once the store is gone.
+    testing::simulate_browser_navigation(&service, TestRoute::new("/about"));
+    assert_eq!(service.get_route(), TestRoute::new("/about"));
+}
@@ -0,0 +1,152 @@
+//! Navigations started while the route service is in use (by a listener
+//! of the route service, or a subscriber of the store) are queued and
+//! performed in order, rather than dropped.
+#![cfg(feature = "test-util")]
+
+mod common;
+
+use common::{reducer, Shared};
+use reactive_state::StoreRef;
+use std::{cell::RefCell, rc::Rc};
+use switch_router::{Callback, SwitchRoute, SwitchRouteService};
+use switch_router_middleware::{
+    memory::MemoryRouteService,
+    snapshot::{NavigationStatus, RouterSnapshot, SNAPSHOT_VERSION},
+    test_kit::{TestAction, TestRoute, TestState},
+    RouteAction, RouteMiddleware,
+};
+
+type SetListener = Box<dyn Fn(&TestRoute)>;
+
+/// A [MemoryRouteService] which records the operations performed on it,
+/// and notifies a listener from within [set_route()](SwitchRouteService::set_route).
+#[derive(Clone)]
+struct RecordingService {
+    inner: MemoryRouteService<TestRoute>,
+    ops: Rc<RefCell<Vec<String>>>,
+    on_set: Rc<RefCell<Option<SetListener>>>,
+}
+
+impl RecordingService {
+    fn new(initial: &str) -> Self {
+        Self {
+            inner: MemoryRouteService::new(TestRoute::new(initial)),
+            ops: Rc::new(RefCell::new(Vec::new())),
+            on_set: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    fn on_set<F: Fn(&TestRoute) + 'static>(&self, listener: F) {
+        self.on_set.replace(Some(Box::new(listener)));
+    }
+
+    fn ops(&self) -> Vec<String> {
+        self.ops.borrow().clone()
+    }
+}
+
+impl SwitchRouteService for RecordingService {
+    type Route = TestRoute;
+
+    fn set_route<SRI: Into<TestRoute>>(&mut self, switch_route: SRI) {
+        let route = switch_route.into();
+        self.ops.borrow_mut().push(format!("set {}", route.path()));
+        self.inner.set_route(route.clone());
+        if let Some(listener) = &*self.on_set.borrow() {
+            listener(&route);
+        }
+    }
+
+    fn replace_route<SRI: Into<TestRoute>>(&mut self, switch_route: SRI) -> TestRoute {
+        let route = switch_route.into();
+        self.ops
+            .borrow_mut()
+            .push(format!("replace {}", route.path()));
+        self.inner.replace_route(route)
+    }
+
+    fn register_callback(&mut self, callback: &Callback<TestRoute>) {
+        self.inner.register_callback(callback);
+    }
+
+    fn back(&mut self) -> Option<TestRoute> {
+        self.ops.borrow_mut().push("back".to_string());
+        self.inner.back()
+    }
+
+    fn get_route(&self) -> TestRoute {
+        self.inner.get_route()
+    }
+}
+
+fn snapshot(path: &str) -> RouterSnapshot<TestRoute> {
+    RouterSnapshot {
+        version: SNAPSHOT_VERSION,
+        route: TestRoute::new(path),
+        history: vec![TestRoute::new(path)],
+        history_index: 0,
+        status: NavigationStatus::default(),
+        masks: Vec::new(),
+        navigation_generation: 0,
+        #[cfg(feature = "scroll")]
+        scroll_positions: Vec::new(),
+    }
+}
+
+#[test]
+fn reentrant_service_operations_are_applied_in_order() {
+    let store = StoreRef::new(
+        reducer,
+        TestState {
+            route: TestRoute::new("/"),
+        },
+    );
+    let service = RecordingService::new("/");
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let middleware = {
+        let errors = errors.clone();
+        Rc::new(
+            RouteMiddleware::new(service.clone(), store.clone()).with_error_sink(
+                move |message: std::fmt::Arguments<'_>| {
+                    errors.borrow_mut().push(message.to_string())
+                },
+            ),
+        )
+    };
+    store.add_middleware(Shared(middleware.clone()));
+
+    {
+        let store = store.downgrade();
+        let middleware = Rc::downgrade(&middleware);
+        service.on_set(move |route| {
+            if route.path() != "/a" {
+                return;
+            }
+            // Dispatched while the store is reducing the navigation to
+            // `/a`, performed once it has been reduced.
+            let store = store.upgrade().unwrap();
+            store.dispatch(TestAction::from(RouteAction::ChangeRoute(TestRoute::new(
+                "/b",
+            ))));
+            // Performed on the route service as soon as it is released by
+            // the navigation to `/a`.
+            middleware.upgrade().unwrap().restore(snapshot("/restored"));
+        });
+    }
+
+    store.dispatch(TestAction::from(RouteAction::ChangeRoute(TestRoute::new(
+        "/a",
+    ))));
+
+    assert_eq!(service.ops(), vec!["set /a", "replace /restored", "set /b"]);
+    assert_eq!(
+        service.inner.entries(),
+        vec![
+            TestRoute::new("/"),
+            TestRoute::new("/restored"),
+            TestRoute::new("/b")
+        ]
+    );
+    assert_eq!(store.state().route, TestRoute::new("/b"));
+    assert!(errors.borrow().is_empty(), "{:?}", errors.borrow());
+}